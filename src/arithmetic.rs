@@ -0,0 +1,493 @@
+// element-wise math on numeric columns, e.g. a derived `price * qty`. Every kernel takes two
+// columns of the same length, or a column and a scalar, and returns a new column. A null on either
+// side gives null, so the validity of the result is the AND of the inputs' bitmaps.
+//
+// Integers can overflow. `Overflow::Checked` fails the kernel when a valid row overflows and
+// `Overflow::Wrapping` wraps around like the `wrapping_*` methods of std. The loops for add, sub,
+// mul, neg and abs do the same thing for every row (they wrap and only note whether anything
+// overflowed), so the compiler can vectorize them. Only when a row overflowed are the valid rows
+// looked at again, a null slot holds a placeholder that may overflow without meaning anything.
+//
+// Division and remainder by zero fail the kernel, or give null when `ArithmeticOptions` asks for
+// that. Floats follow the same rules for zero, everything else about them is IEEE.
+
+use crate::bitvec::BitVec;
+use crate::fixed::{Fixed, Native};
+
+/// What integer kernels do when a result doesn't fit the type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// The kernel fails.
+    #[default]
+    Checked,
+    /// The result wraps around.
+    Wrapping,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArithmeticOptions {
+    overflow: Overflow,
+    null_on_division_by_zero: bool,
+}
+
+impl ArithmeticOptions {
+    pub fn new() -> ArithmeticOptions {
+        ArithmeticOptions::default()
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> ArithmeticOptions {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Division and remainder by zero give null instead of failing the kernel.
+    pub fn with_null_on_division_by_zero(mut self, null_on_division_by_zero: bool) -> Self {
+        self.null_on_division_by_zero = null_on_division_by_zero;
+        self
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    pub fn null_on_division_by_zero(&self) -> bool {
+        self.null_on_division_by_zero
+    }
+}
+
+/// Natives the arithmetic kernels work on. The `overflowing_*` methods wrap and tell whether they
+/// did, like the ones of std. Floats never overflow.
+pub trait Numeric: Native {
+    fn overflowing_add(self, other: Self) -> (Self, bool);
+
+    fn overflowing_sub(self, other: Self) -> (Self, bool);
+
+    fn overflowing_mul(self, other: Self) -> (Self, bool);
+
+    /// Only called with a divisor that isn't zero.
+    fn overflowing_div(self, other: Self) -> (Self, bool);
+
+    /// Only called with a divisor that isn't zero.
+    fn overflowing_rem(self, other: Self) -> (Self, bool);
+
+    fn overflowing_neg(self) -> (Self, bool);
+
+    fn overflowing_abs(self) -> (Self, bool);
+
+    fn is_zero(self) -> bool;
+}
+
+macro_rules! signed {
+    ($($type:ty),*) => {
+        $(
+            impl Numeric for $type {
+                fn overflowing_add(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_add(self, other)
+                }
+
+                fn overflowing_sub(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_sub(self, other)
+                }
+
+                fn overflowing_mul(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_mul(self, other)
+                }
+
+                fn overflowing_div(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_div(self, other)
+                }
+
+                fn overflowing_rem(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_rem(self, other)
+                }
+
+                fn overflowing_neg(self) -> ($type, bool) {
+                    <$type>::overflowing_neg(self)
+                }
+
+                fn overflowing_abs(self) -> ($type, bool) {
+                    <$type>::overflowing_abs(self)
+                }
+
+                fn is_zero(self) -> bool {
+                    self == 0
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! unsigned {
+    ($($type:ty),*) => {
+        $(
+            impl Numeric for $type {
+                fn overflowing_add(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_add(self, other)
+                }
+
+                fn overflowing_sub(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_sub(self, other)
+                }
+
+                fn overflowing_mul(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_mul(self, other)
+                }
+
+                fn overflowing_div(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_div(self, other)
+                }
+
+                fn overflowing_rem(self, other: $type) -> ($type, bool) {
+                    <$type>::overflowing_rem(self, other)
+                }
+
+                // only zero has a negation that fits
+                fn overflowing_neg(self) -> ($type, bool) {
+                    <$type>::overflowing_neg(self)
+                }
+
+                fn overflowing_abs(self) -> ($type, bool) {
+                    (self, false)
+                }
+
+                fn is_zero(self) -> bool {
+                    self == 0
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! float {
+    ($($type:ty),*) => {
+        $(
+            impl Numeric for $type {
+                fn overflowing_add(self, other: $type) -> ($type, bool) {
+                    (self + other, false)
+                }
+
+                fn overflowing_sub(self, other: $type) -> ($type, bool) {
+                    (self - other, false)
+                }
+
+                fn overflowing_mul(self, other: $type) -> ($type, bool) {
+                    (self * other, false)
+                }
+
+                fn overflowing_div(self, other: $type) -> ($type, bool) {
+                    (self / other, false)
+                }
+
+                fn overflowing_rem(self, other: $type) -> ($type, bool) {
+                    (self % other, false)
+                }
+
+                fn overflowing_neg(self) -> ($type, bool) {
+                    (-self, false)
+                }
+
+                fn overflowing_abs(self) -> ($type, bool) {
+                    (self.abs(), false)
+                }
+
+                fn is_zero(self) -> bool {
+                    self == 0.0
+                }
+            }
+        )*
+    };
+}
+
+signed!(i8, i16, i32, i64);
+unsigned!(u8, u16, u32, u64);
+float!(f32, f64);
+
+impl<T: Numeric> Fixed<T> {
+    /// `self + other` for every row.
+    pub fn add(
+        &self,
+        other: &Fixed<T>,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.binary(other, options, T::overflowing_add)
+    }
+
+    /// `self - other` for every row.
+    pub fn sub(
+        &self,
+        other: &Fixed<T>,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.binary(other, options, T::overflowing_sub)
+    }
+
+    /// `self * other` for every row.
+    pub fn mul(
+        &self,
+        other: &Fixed<T>,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.binary(other, options, T::overflowing_mul)
+    }
+
+    /// `self / other` for every row, integers round toward zero.
+    pub fn div(
+        &self,
+        other: &Fixed<T>,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.divide(other, options, T::overflowing_div)
+    }
+
+    /// `self % other` for every row, the result has the sign of `self`.
+    pub fn rem(
+        &self,
+        other: &Fixed<T>,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.divide(other, options, T::overflowing_rem)
+    }
+
+    /// `self + value` for every row.
+    pub fn add_scalar(
+        &self,
+        value: T,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.add(&self.splat(value), options)
+    }
+
+    /// `self - value` for every row.
+    pub fn sub_scalar(
+        &self,
+        value: T,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.sub(&self.splat(value), options)
+    }
+
+    /// `self * value` for every row.
+    pub fn mul_scalar(
+        &self,
+        value: T,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.mul(&self.splat(value), options)
+    }
+
+    /// `self / value` for every row.
+    pub fn div_scalar(
+        &self,
+        value: T,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.div(&self.splat(value), options)
+    }
+
+    /// `self % value` for every row.
+    pub fn rem_scalar(
+        &self,
+        value: T,
+        options: ArithmeticOptions,
+    ) -> Result<Fixed<T>, &'static str> {
+        self.rem(&self.splat(value), options)
+    }
+
+    /// `-self` for every row. Only zero can be negated without overflowing an unsigned type.
+    pub fn neg(&self, options: ArithmeticOptions) -> Result<Fixed<T>, &'static str> {
+        self.unary(options, T::overflowing_neg)
+    }
+
+    /// The absolute value of every row, the minimum of a signed type overflows.
+    pub fn abs(&self, options: ArithmeticOptions) -> Result<Fixed<T>, &'static str> {
+        self.unary(options, T::overflowing_abs)
+    }
+
+    // a column holding `value` in every row, so scalars go through the same loops as columns.
+    fn splat(&self, value: T) -> Fixed<T> {
+        Fixed::from_parts(vec![value; self.len()], None)
+    }
+
+    fn binary(
+        &self,
+        other: &Fixed<T>,
+        options: ArithmeticOptions,
+        op: impl Fn(T, T) -> (T, bool),
+    ) -> Result<Fixed<T>, &'static str> {
+        assert_eq!(self.len(), other.len(), "arrays must have the same length");
+        let validity = and_validity(self.validity(), other.validity());
+        let (a, b) = (self.data(), other.data());
+        let mut overflow = false;
+        let data: Vec<T> = a
+            .iter()
+            .zip(b)
+            .map(|(&a, &b)| {
+                let (value, overflowed) = op(a, b);
+                overflow |= overflowed;
+                value
+            })
+            .collect();
+        if overflow && options.overflow == Overflow::Checked {
+            let valid = |i: usize| validity.as_ref().is_none_or(|v| v[i]);
+            if (0..a.len()).any(|i| valid(i) && op(a[i], b[i]).1) {
+                return Err("arithmetic overflow");
+            }
+        }
+        Ok(Fixed::from_parts(data, validity))
+    }
+
+    // division can't be vectorized anyway, so zeros and overflows are handled row by row.
+    fn divide(
+        &self,
+        other: &Fixed<T>,
+        options: ArithmeticOptions,
+        op: impl Fn(T, T) -> (T, bool),
+    ) -> Result<Fixed<T>, &'static str> {
+        assert_eq!(self.len(), other.len(), "arrays must have the same length");
+        let mut validity = and_validity(self.validity(), other.validity());
+        let mut data = Vec::with_capacity(self.len());
+        for (index, (&a, &b)) in self.data().iter().zip(other.data()).enumerate() {
+            let valid = validity.as_ref().is_none_or(|v| v[index]);
+            if !valid {
+                data.push(T::default());
+                continue;
+            }
+            if b.is_zero() {
+                if !options.null_on_division_by_zero {
+                    return Err("division by zero");
+                }
+                let len = self.len();
+                validity
+                    .get_or_insert_with(|| BitVec::ones(len))
+                    .set(index, false);
+                data.push(T::default());
+                continue;
+            }
+            let (value, overflowed) = op(a, b);
+            if overflowed && options.overflow == Overflow::Checked {
+                return Err("arithmetic overflow");
+            }
+            data.push(value);
+        }
+        Ok(Fixed::from_parts(data, validity))
+    }
+
+    fn unary(
+        &self,
+        options: ArithmeticOptions,
+        op: impl Fn(T) -> (T, bool),
+    ) -> Result<Fixed<T>, &'static str> {
+        let mut overflow = false;
+        let data: Vec<T> = self
+            .data()
+            .iter()
+            .map(|&a| {
+                let (value, overflowed) = op(a);
+                overflow |= overflowed;
+                value
+            })
+            .collect();
+        if overflow
+            && options.overflow == Overflow::Checked
+            && (0..self.len()).any(|i| self.is_valid(i) && op(self.data()[i]).1)
+        {
+            return Err("arithmetic overflow");
+        }
+        Ok(Fixed::from_parts(data, self.validity().cloned()))
+    }
+}
+
+fn and_validity(a: Option<&BitVec>, b: Option<&BitVec>) -> Option<BitVec> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.and(b)),
+        (Some(v), None) | (None, Some(v)) => Some(v.clone()),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArithmeticOptions, Overflow};
+    use crate::fixed::Fixed;
+
+    fn column<T: crate::fixed::Native>(values: &[Option<T>]) -> Fixed<T> {
+        values.iter().copied().collect()
+    }
+
+    #[test]
+    fn columns_and_scalars() {
+        let options = ArithmeticOptions::new();
+        let price = column(&[Some(250), Some(100), None, Some(-4)]);
+        let qty = column(&[Some(2), None, Some(5), Some(3)]);
+
+        let total = price.mul(&qty, options).unwrap();
+        let total: Vec<_> = total.get_records().collect();
+        assert_eq!(total, vec![Some(500), None, None, Some(-12)]);
+        let diff: Vec<_> = price.sub(&qty, options).unwrap().get_records().collect();
+        assert_eq!(diff, vec![Some(248), None, None, Some(-7)]);
+
+        let taxed = price.add_scalar(1, options).unwrap();
+        assert_eq!(taxed.data()[..2], [251, 101]);
+        assert_eq!(taxed.validity(), price.validity());
+        let abs: Vec<_> = price
+            .neg(options)
+            .unwrap()
+            .abs(options)
+            .unwrap()
+            .get_records()
+            .collect();
+        assert_eq!(abs, vec![Some(250), Some(100), None, Some(4)]);
+
+        let halves = column(&[Some(1.0), Some(-3.0)])
+            .mul_scalar(0.5, options)
+            .unwrap();
+        assert_eq!(halves.data(), &[0.5, -1.5]);
+    }
+
+    #[test]
+    fn overflow() {
+        let checked = ArithmeticOptions::new();
+        let wrapping = checked.with_overflow(Overflow::Wrapping);
+        let a = column(&[Some(100i8), Some(i8::MIN)]);
+        assert_eq!(
+            a.add_scalar(100, checked).unwrap_err(),
+            "arithmetic overflow"
+        );
+        assert_eq!(a.add_scalar(100, wrapping).unwrap().data(), &[-56, -28]);
+        assert!(a.abs(checked).is_err());
+        assert_eq!(a.abs(wrapping).unwrap().data()[1], i8::MIN);
+        assert!(column(&[Some(1u32)]).neg(checked).is_err());
+
+        // the placeholder behind a null doesn't count
+        let b = column(&[Some(1i8), None]);
+        let c = column(&[Some(2i8), Some(i8::MIN)]);
+        let sum = b.sub(&c, checked).unwrap();
+        assert_eq!(sum.get_records().collect::<Vec<_>>(), vec![Some(-1), None]);
+    }
+
+    #[test]
+    fn division() {
+        let options = ArithmeticOptions::new();
+        let a = column(&[Some(7), Some(-7), Some(1), None]);
+        let b = column(&[Some(2), Some(2), Some(0), Some(0)]);
+        assert_eq!(a.div(&b, options).unwrap_err(), "division by zero");
+        let nulls = options.with_null_on_division_by_zero(true);
+        let quotient: Vec<_> = a.div(&b, nulls).unwrap().get_records().collect();
+        assert_eq!(quotient, vec![Some(3), Some(-3), None, None]);
+        let remainder: Vec<_> = a.rem(&b, nulls).unwrap().get_records().collect();
+        assert_eq!(remainder, vec![Some(1), Some(-1), None, None]);
+
+        let min = column(&[Some(i32::MIN)]);
+        assert_eq!(
+            min.div_scalar(-1, options).unwrap_err(),
+            "arithmetic overflow"
+        );
+        let wrapping = options.with_overflow(Overflow::Wrapping);
+        assert_eq!(min.div_scalar(-1, wrapping).unwrap().data(), &[i32::MIN]);
+        assert_eq!(min.rem_scalar(-1, wrapping).unwrap().data(), &[0]);
+
+        let floats = column(&[Some(1.0f64)]);
+        assert!(floats.div_scalar(0.0, options).is_err());
+        assert_eq!(floats.div_scalar(4.0, options).unwrap().data(), &[0.25]);
+    }
+}
@@ -0,0 +1,561 @@
+// a dynamic interface over every kind of column. `ColumnType` knows all the kinds, which is what
+// storage and files need, but code that only reads values (printing a table, comparing rows)
+// shouldn't have to match on every variant and grow with each new one. `Array` is the object
+// safe part all columns have in common and `Value` is a single value of any type, borrowed from
+// its column. `as_any` gets the concrete column back when the generic view isn't enough.
+//
+// Values of nested columns are built from the values of their children, like `FieldType` is on
+// the way in.
+
+use std::any::Any;
+use std::fmt::Debug;
+
+use crate::boolean::BooleanArray;
+use crate::database::{ColumnType, DataType};
+use crate::decimal::Decimal128Array;
+use crate::dictionary::DictionaryArray;
+use crate::enum_array::{EnumArray, EnumCode};
+use crate::fixed::{Fixed, Native};
+use crate::fixed_binary::FixedSizeBinaryArray;
+use crate::list::List;
+use crate::map::Map;
+use crate::stats::ToScalar;
+use crate::struct_array::StructArray;
+use crate::temporal::{TemporalArray, TemporalInt, TimeUnit};
+use crate::variable::{FromByteRef, Offset, StringArray, ToBytesRef, Variable};
+
+/// A single value of any column type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Null,
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    UInt8(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    Float32(f32),
+    Float64(f64),
+    /// Text of text, large text and dictionary columns.
+    Text(&'a str),
+    /// Bytes of binary, large binary and fixed size binary columns.
+    Binary(&'a [u8]),
+    Boolean(bool),
+    /// Days since 1970-01-01.
+    Date32(i32),
+    /// Units since midnight.
+    Time64(i64, TimeUnit),
+    /// Units since the epoch in UTC and the timezone of the column.
+    Timestamp(i64, TimeUnit, Option<&'a str>),
+    Duration(i64, TimeUnit),
+    /// Unscaled value and scale.
+    Decimal128(i128, u8),
+    /// The values of a list.
+    List(Vec<Value<'a>>),
+    /// The name and value of every field of a struct.
+    Struct(Vec<(&'a str, Value<'a>)>),
+    /// The key and value of every entry of a map.
+    Map(Vec<(&'a str, Value<'a>)>),
+    /// The label of an enum.
+    Enum(&'a str),
+}
+
+impl Value<'_> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+/// The parts every column has in common, so code can work on `&dyn Array` without knowing the
+/// kind of column.
+pub trait Array: Debug {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn data_type(&self) -> DataType;
+
+    /// Whether value `index` is null, `index` must be below `len`.
+    fn is_null(&self, index: usize) -> bool;
+
+    /// Value `index`, `Some(Value::Null)` for nulls and `None` out of bounds.
+    fn get_value(&self, index: usize) -> Option<Value<'_>>;
+
+    /// A copy of `length` values starting at `offset`.
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array>;
+
+    /// The concrete column, for `downcast_ref`.
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Natives that are a column type of their own, every one but the i128 of decimals.
+pub trait PrimitiveType: Native {
+    const DATA_TYPE: DataType;
+
+    fn to_value(self) -> Value<'static>;
+}
+
+macro_rules! primitive {
+    ($($type:ty => $variant:ident),*) => {
+        $(
+            impl PrimitiveType for $type {
+                const DATA_TYPE: DataType = DataType::$variant;
+
+                fn to_value(self) -> Value<'static> {
+                    Value::$variant(self)
+                }
+            }
+        )*
+    };
+}
+
+primitive!(
+    i8 => Int8, i16 => Int16, i32 => Int32, i64 => Int64,
+    u8 => UInt8, u16 => UInt16, u32 => UInt32, u64 => UInt64,
+    f32 => Float32, f64 => Float64
+);
+
+/// What a `Variable` column holds, text or bytes.
+pub trait VariableType: FromByteRef + ToBytesRef + ToScalar + Debug + 'static {
+    /// The data type of a column of these with offsets `O`.
+    fn data_type<O: Offset>() -> DataType;
+
+    fn to_value(&self) -> Value<'_>;
+}
+
+impl VariableType for str {
+    fn data_type<O: Offset>() -> DataType {
+        match O::WIDTH {
+            4 => DataType::Text,
+            _ => DataType::LargeText,
+        }
+    }
+
+    fn to_value(&self) -> Value<'_> {
+        Value::Text(self)
+    }
+}
+
+impl VariableType for [u8] {
+    fn data_type<O: Offset>() -> DataType {
+        match O::WIDTH {
+            4 => DataType::Binary,
+            _ => DataType::LargeBinary,
+        }
+    }
+
+    fn to_value(&self) -> Value<'_> {
+        Value::Binary(self)
+    }
+}
+
+impl<T: PrimitiveType> Array for Fixed<T> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        T::DATA_TYPE
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(match self.is_valid(index) {
+            true => self.data()[index].to_value(),
+            false => Value::Null,
+        })
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<T: VariableType + ?Sized, O: Offset> Array for Variable<T, O> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        T::data_type::<O>()
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        Some(self.get(index)?.map_or(Value::Null, T::to_value))
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Array for BooleanArray {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::Boolean
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        Some(self.get(index)?.map_or(Value::Null, Value::Boolean))
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Array for DictionaryArray {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::Dictionary
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        self.get(index) == Some(None)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        Some(self.get(index)?.map_or(Value::Null, Value::Text))
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<T: TemporalInt> Array for TemporalArray<T> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        self.data_type().clone()
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.values().is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        let Some(value) = self.get(index)? else {
+            return Some(Value::Null);
+        };
+        let value = value.to_i64();
+        Some(match *self.data_type() {
+            DataType::Date32 => Value::Date32(value as i32),
+            DataType::Time64(unit) => Value::Time64(value, unit),
+            DataType::Timestamp(unit, _) => Value::Timestamp(value, unit, self.timezone()),
+            DataType::Duration(unit) => Value::Duration(value, unit),
+            _ => unreachable!("temporal columns have temporal types"),
+        })
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Array for Decimal128Array {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        self.data_type()
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.values().is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        let scale = self.scale();
+        Some(
+            self.get(index)?
+                .map_or(Value::Null, |value| Value::Decimal128(value, scale)),
+        )
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Array for FixedSizeBinaryArray {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::FixedSizeBinary(self.width())
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        Some(self.get(index)?.map_or(Value::Null, Value::Binary))
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Array for List<ColumnType> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::List(Box::new(self.values().data_type()))
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        let Some(range) = self.value_range(index)? else {
+            return Some(Value::Null);
+        };
+        let values = self.values();
+        Some(Value::List(
+            range.filter_map(|i| values.get_value(i)).collect(),
+        ))
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Array for StructArray {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        self.data_type()
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        if index >= self.len() {
+            return None;
+        }
+        if !self.is_valid(index) {
+            return Some(Value::Null);
+        }
+        Some(Value::Struct(
+            self.fields()
+                .iter()
+                .filter_map(|f| Some((f.name(), f.inner().get_value(index)?)))
+                .collect(),
+        ))
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Array for Map<StringArray, ColumnType> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        DataType::Map(Box::new(self.values().data_type()))
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        let Some(range) = self.value_range(index)? else {
+            return Some(Value::Null);
+        };
+        let (keys, values) = (self.keys(), self.values());
+        Some(Value::Map(
+            range
+                .filter_map(|i| Some((keys.get(i)??, values.get_value(i)?)))
+                .collect(),
+        ))
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl<T: EnumCode> Array for EnumArray<T> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        self.data_type()
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        !self.is_valid(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        Some(self.get(index)?.map_or(Value::Null, Value::Enum))
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Array, Value};
+    use crate::database::{ColumnType, DataType, Field, FieldType};
+    use crate::fixed::Fixed;
+    use crate::temporal::TimeUnit;
+    use crate::variable::{LargeStringArray, StringArray};
+
+    // generic code only sees the trait
+    fn render(array: &dyn Array) -> Vec<String> {
+        (0..array.len())
+            .map(|i| match array.get_value(i).unwrap() {
+                Value::Null => "null".to_string(),
+                Value::Int32(v) => v.to_string(),
+                Value::Text(v) => v.to_string(),
+                value => format!("{value:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn primitive_columns() {
+        let mut ids = Fixed::<i32>::new();
+        ids.add_record(4);
+        ids.add_null();
+        let mut names = StringArray::new();
+        names.add("a");
+        names.add_null();
+        let columns: Vec<Box<dyn Array>> = vec![Box::new(ids), Box::new(names)];
+        assert_eq!(render(columns[0].as_ref()), vec!["4", "null"]);
+        assert_eq!(render(columns[1].as_ref()), vec!["a", "null"]);
+        assert_eq!(columns[0].data_type(), DataType::Int32);
+        assert_eq!(columns[1].data_type(), DataType::Text);
+        assert!(columns[1].is_null(1));
+        assert_eq!(columns[1].get_value(2), None);
+        assert_eq!(LargeStringArray::new().data_type(), DataType::LargeText);
+
+        let sliced = columns[0].slice(1, 1);
+        assert!(sliced.get_value(0).unwrap().is_null());
+        let ids = columns[0].as_any().downcast_ref::<Fixed<i32>>().unwrap();
+        assert_eq!(ids.data()[0], 4);
+    }
+
+    #[test]
+    fn column_types() {
+        let timestamp = DataType::Timestamp(TimeUnit::Second, Some("+01:00".to_string()));
+        let mut at = ColumnType::empty(&timestamp, 0);
+        at.add_field(&FieldType::Int64(60)).unwrap();
+        assert_eq!(
+            at.get_value(0),
+            Some(Value::Timestamp(60, TimeUnit::Second, Some("+01:00")))
+        );
+        assert_eq!(Array::data_type(&at), timestamp);
+
+        let point = DataType::Struct(vec![
+            Field::new("x".to_string(), DataType::Int32),
+            Field::new("tags".to_string(), DataType::List(Box::new(DataType::Text))),
+        ]);
+        let mut points = ColumnType::empty(&point, 0);
+        let tags = FieldType::List(vec![FieldType::Text("a"), FieldType::Null]);
+        points
+            .add_field(&FieldType::Struct(vec![FieldType::Int32(1), tags]))
+            .unwrap();
+        points.add_null();
+        assert_eq!(
+            points.get_value(0),
+            Some(Value::Struct(vec![
+                ("x", Value::Int32(1)),
+                ("tags", Value::List(vec![Value::Text("a"), Value::Null])),
+            ]))
+        );
+        assert!(points.is_null(1));
+        assert_eq!(render(points.as_array())[1], "null");
+    }
+}
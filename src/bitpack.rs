@@ -0,0 +1,225 @@
+// delta and frame of reference bit packing for integer columns. Timestamps and ids grow slowly, so
+// the difference between neighbours (delta) is tiny even when the values themselves are large:
+//
+//   values  [1000, 1003, 1004, 1010]
+//   deltas  [3, 1, 6]
+//
+// Deltas are packed in blocks of 128. Each block stores its smallest delta (the frame of
+// reference) and how many bits the largest `delta - min` needs, then every value with exactly
+// that many bits. A block of sorted ids with a constant step needs zero bits per value.
+//
+//   first value                 i64
+//   per block: min delta        i64
+//              bit width        u8
+//              packed values    ceil(count * width / 8) bytes
+
+use crate::fixed::Native;
+
+/// Number of values in a bit packed block.
+pub const BLOCK_SIZE: usize = 128;
+
+/// Integers that can be delta encoded. They are widened to i64 so deltas never overflow, 64-bit
+/// values may wrap around but the deltas wrap back when decoding.
+pub trait DeltaInt: Native {
+    fn to_i64(self) -> i64;
+
+    fn from_i64(value: i64) -> Self;
+}
+
+macro_rules! delta_int {
+    ($($type:ty),*) => {
+        $(
+            impl DeltaInt for $type {
+                fn to_i64(self) -> i64 {
+                    self as i64
+                }
+
+                fn from_i64(value: i64) -> $type {
+                    value as $type
+                }
+            }
+        )*
+    };
+}
+
+delta_int!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+/// Number of bits needed to store `value`.
+pub fn bit_width(value: u64) -> u8 {
+    (64 - value.leading_zeros()) as u8
+}
+
+/// Appends the lowest `width` bits of every value, least significant bit first.
+pub fn pack(values: &[u64], width: u8, out: &mut Vec<u8>) {
+    if width == 0 {
+        return;
+    }
+    let mut buffer = 0u128;
+    let mut bits = 0u32;
+    for &value in values {
+        buffer |= (value as u128) << bits;
+        bits += width as u32;
+        while bits >= 8 {
+            out.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    }
+    if bits > 0 {
+        out.push(buffer as u8);
+    }
+}
+
+/// Reads `out.len()` values of `width` bits written by [`pack`].
+pub fn unpack(bytes: &[u8], width: u8, out: &mut [u64]) {
+    if width == 0 {
+        out.fill(0);
+        return;
+    }
+    let mask = if width == 64 {
+        u64::MAX
+    } else {
+        (1u64 << width) - 1
+    };
+    let mut buffer = 0u128;
+    let mut bits = 0u32;
+    let mut bytes = bytes.iter();
+    for value in out.iter_mut() {
+        // there are never more than 7 bits left over, so 64 more always fit
+        while bits < width as u32 {
+            buffer |= (*bytes.next().unwrap_or(&0) as u128) << bits;
+            bits += 8;
+        }
+        *value = buffer as u64 & mask;
+        buffer >>= width;
+        bits -= width as u32;
+    }
+}
+
+/// Delta encodes `values` and bit packs the deltas block by block.
+pub fn encode(values: &[i64]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let Some(&first) = values.first() else {
+        return out;
+    };
+    out.extend_from_slice(&first.to_le_bytes());
+    let deltas: Vec<i64> = values.windows(2).map(|w| w[1].wrapping_sub(w[0])).collect();
+    let mut packed = [0u64; BLOCK_SIZE];
+    for block in deltas.chunks(BLOCK_SIZE) {
+        let min = *block.iter().min().unwrap();
+        let mut max = 0;
+        for (slot, &delta) in packed.iter_mut().zip(block) {
+            *slot = delta.wrapping_sub(min) as u64;
+            max = max.max(*slot);
+        }
+        let width = bit_width(max);
+        out.extend_from_slice(&min.to_le_bytes());
+        out.push(width);
+        pack(&packed[..block.len()], width, &mut out);
+    }
+    out
+}
+
+/// Decodes `count` values written by [`encode`].
+pub fn decode(bytes: &[u8], count: usize) -> Result<Vec<i64>, &'static str> {
+    // the first value takes 8 bytes and a block header 9, with no bits per value at best.
+    if count > 1 + bytes.len() / 9 * BLOCK_SIZE {
+        return Err("delta blocks are truncated");
+    }
+    let mut values = Vec::with_capacity(count);
+    if count == 0 {
+        return if bytes.is_empty() {
+            Ok(values)
+        } else {
+            Err("trailing bytes after delta blocks")
+        };
+    }
+    let mut reader = bytes;
+    let mut take = |n: usize| -> Result<&[u8], &'static str> {
+        if reader.len() < n {
+            return Err("delta blocks are truncated");
+        }
+        let (head, rest) = reader.split_at(n);
+        reader = rest;
+        Ok(head)
+    };
+    let mut previous = i64::from_le_bytes(take(8)?.try_into().unwrap());
+    values.push(previous);
+    let mut block = [0u64; BLOCK_SIZE];
+    while values.len() < count {
+        let len = (count - values.len()).min(BLOCK_SIZE);
+        let min = i64::from_le_bytes(take(8)?.try_into().unwrap());
+        let width = take(1)?[0];
+        if width > 64 {
+            return Err("bit width larger than 64");
+        }
+        let packed = take((len * width as usize).div_ceil(8))?;
+        unpack(packed, width, &mut block[..len]);
+        for &delta in &block[..len] {
+            previous = previous.wrapping_add(min.wrapping_add(delta as i64));
+            values.push(previous);
+        }
+    }
+    if !reader.is_empty() {
+        return Err("trailing bytes after delta blocks");
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BLOCK_SIZE, bit_width, decode, encode, pack, unpack};
+
+    #[test]
+    fn pack_round_trip() {
+        for width in [0, 1, 3, 7, 8, 13, 33, 64] {
+            let mask = if width == 64 {
+                u64::MAX
+            } else {
+                (1 << width) - 1
+            };
+            let values: Vec<u64> = (0..100u64)
+                .map(|i| i.wrapping_mul(0x9e3779b97f4a7c15) & mask)
+                .collect();
+            let mut bytes = vec![];
+            pack(&values, width, &mut bytes);
+            assert_eq!(bytes.len(), (100 * width as usize).div_ceil(8));
+            let mut read = vec![0; 100];
+            unpack(&bytes, width, &mut read);
+            assert_eq!(read, values, "width {width}");
+        }
+        assert_eq!(bit_width(0), 0);
+        assert_eq!(bit_width(1), 1);
+        assert_eq!(bit_width(255), 8);
+    }
+
+    #[test]
+    fn delta_round_trip() {
+        assert_eq!(decode(&encode(&[]), 0), Ok(vec![]));
+        let values = vec![i32::MIN as i64, i32::MAX as i64, 0, -5, u32::MAX as i64];
+        assert_eq!(decode(&encode(&values), values.len()), Ok(values));
+
+        let values: Vec<i64> = (0..1000).map(|i| i * i % 977 - 300).collect();
+        assert_eq!(decode(&encode(&values), values.len()), Ok(values));
+    }
+
+    #[test]
+    fn sorted_values_pack_tightly() {
+        // auto increment ids: every delta is 1, so blocks need no bits at all
+        let ids: Vec<i64> = (1_000_000..1_010_000).collect();
+        let encoded = encode(&ids);
+        assert_eq!(encoded.len(), 8 + ids.len().div_ceil(BLOCK_SIZE) * 9);
+
+        // timestamps with some jitter
+        let timestamps: Vec<i64> = (0..10_000)
+            .map(|i| 1_700_000_000 + i * 60 + (i * 7 % 5))
+            .collect();
+        let encoded = encode(&timestamps);
+        assert!(encoded.len() < timestamps.len(), "{}", encoded.len());
+        assert_eq!(decode(&encoded, timestamps.len()), Ok(timestamps));
+
+        assert!(decode(&encoded[..encoded.len() - 1], 10_000).is_err());
+        // a count no input of that size can hold fails before allocating
+        assert_eq!(decode(&[0; 8], 1 << 62), Err("delta blocks are truncated"));
+    }
+}
@@ -11,7 +11,7 @@ macro_rules! bitvec {
     }};
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct BitVec {
     inner: Vec<u8>,    // 24 byte
     length: usize,     // 8 byte
@@ -23,8 +23,53 @@ impl BitVec {
         BitVec::default()
     }
 
+    pub fn with_capacity(capacity: usize) -> BitVec {
+        BitVec {
+            inner: Vec::with_capacity(capacity.div_ceil(8)),
+            ..Default::default()
+        }
+    }
+
+    // every bit set, e.g. when a validity bitmap is built for a column that had no nulls so far.
+    pub fn ones(length: usize) -> BitVec {
+        BitVec::from_bytes(vec![0xFF; length.div_ceil(8)], length)
+    }
+
+    // rebuilds a bitvec from its packed bytes, e.g. when reading a file.
+    pub fn from_bytes(bytes: Vec<u8>, length: usize) -> BitVec {
+        assert_eq!(
+            bytes.len(),
+            length.div_ceil(8),
+            "wrong number of bytes for length"
+        );
+        let mut inner = bytes;
+        // bits past the length must be zero so equality and pop work as usual
+        if !length.is_multiple_of(8) {
+            let last = inner.len() - 1;
+            inner[last] &= (1u8 << (length % 8)) - 1;
+        }
+        let ones: usize = inner.iter().map(|b| b.count_ones() as usize).sum();
+        BitVec {
+            inner,
+            length,
+            null_count: length - ones,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.inner
+    }
+
     pub fn len(&self) -> usize {
-        self.inner.len()
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.null_count
     }
 
     pub fn push(&mut self, value: bool) {
@@ -52,8 +97,11 @@ impl BitVec {
         let bucket = length / 8;
         let index = length % 8;
         let value = (self.inner[bucket] >> index) & 1;
+        // bits past the length stay zero, kernels read whole bytes
         if index == 0 {
             self.inner.pop().unwrap();
+        } else {
+            self._set(bucket, index, false);
         }
         self.length -= 1;
         if value == 0 {
@@ -63,10 +111,10 @@ impl BitVec {
     }
 
     pub fn get(&self, index: usize) -> Option<bool> {
-        let bucket = index / 8;
-        if bucket >= self.inner.len() {
+        if index >= self.length {
             return None;
         }
+        let bucket = index / 8;
         let index = index % 8;
 
         let value = (self.inner[bucket] >> index) & 1;
@@ -96,9 +144,13 @@ impl BitVec {
     pub fn set(&mut self, index: usize, value: bool) {
         let byte = index / 8;
         let bit = index % 8;
+        // null count only changes when the bit actually flips
+        let old = (self.inner[byte] >> bit) & 1 == 1;
         self._set(byte, bit, value);
-        if !value {
-            self.null_count += 1;
+        match (old, value) {
+            (true, false) => self.null_count += 1,
+            (false, true) => self.null_count -= 1,
+            _ => {}
         }
     }
 
@@ -111,6 +163,32 @@ impl BitVec {
         }
     }
 
+    // copies `length` bits starting at `offset` into a new bitvec.
+    pub fn slice(&self, offset: usize, length: usize) -> BitVec {
+        assert!(offset + length <= self.length, "slice out of bounds");
+        let mut bitvec = BitVec::with_capacity(length);
+        for value in self.iter().skip(offset).take(length) {
+            bitvec.push(value);
+        }
+        bitvec
+    }
+
+    /// Bits set in both, e.g. the rows valid on both sides of a binary kernel. Works a byte at a
+    /// time.
+    pub fn and(&self, other: &BitVec) -> BitVec {
+        assert_eq!(
+            self.length, other.length,
+            "bitvecs must have the same length"
+        );
+        let bytes = self
+            .inner
+            .iter()
+            .zip(&other.inner)
+            .map(|(a, b)| a & b)
+            .collect();
+        BitVec::from_bytes(bytes, self.length)
+    }
+
     pub fn iter(&self) -> Iter<'_> {
         Iter {
             ptr: self.inner.as_slice(),
@@ -172,10 +250,12 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn pop() {
         let mut vec = BitVec::new();
         vec.inner = vec![0xFF, 0xF5, 0x42]; // 11111111 11110101 01000010
         vec.length = 24;
+        vec.null_count = 8;
         dbg!(&vec);
         assert_eq!(vec.pop(), false);
         assert_eq!(vec.pop(), true);
@@ -226,6 +306,14 @@ mod test {
         assert_eq!(vec.get(16), Some(false));
     }
 
+    #[test]
+    fn from_bytes() {
+        let vec = BitVec::from_bytes(vec![0xFF, 0b11110101], 12);
+        assert_eq!(vec.len(), 12);
+        assert_eq!(vec.null_count(), 2);
+        assert_eq!(vec.as_bytes(), &[0xFF, 0b00000101]);
+    }
+
     #[test]
     #[should_panic]
     fn get_none() {
@@ -0,0 +1,310 @@
+// bloom filters for point lookups. Min/max can't help `id = 'a81f...'` when ids are random, every
+// chunk's range covers the whole alphabet. A bloom filter answers "is this value definitely not in
+// the chunk?" with a few bits per distinct value.
+//
+// This is the split block bloom filter used by parquet. The filter is a list of 256-bit blocks
+// (8 words of 32 bits). The upper half of the hash picks a block and the lower half sets one bit in
+// every word of it, so a lookup touches a single cache line.
+
+use crate::database::ColumnType;
+use crate::stats::Scalar;
+use crate::util::hash_bytes;
+use crate::variable::ToBytesRef;
+
+const SALT: [u32; 8] = [
+    0x47b6137b, 0x44974d91, 0x8824ad5b, 0xa2b7289d, 0x705495c7, 0x2df1424b, 0x9efc4947, 0x5c6bfb31,
+];
+
+const BLOCK_BYTES: usize = 32;
+// filters larger than this stop paying for themselves, the chunk is probably better scanned.
+const MAX_BYTES: usize = 128 * 1024;
+
+/// Default false positive probability of a filter.
+pub const DEFAULT_FPP: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    blocks: Vec<[u32; 8]>,
+}
+
+impl BloomFilter {
+    /// A filter sized for `distinct` values with a false positive probability of `fpp`.
+    pub fn with_capacity(distinct: u64, fpp: f64) -> BloomFilter {
+        assert!(fpp > 0.0 && fpp < 1.0, "fpp must be between 0 and 1");
+        // optimal number of bits for a split block filter with 8 hash functions
+        let bits = -8.0 * distinct.max(1) as f64 / (1.0 - fpp.powf(1.0 / 8.0)).ln();
+        let bytes = ((bits / 8.0).ceil() as usize)
+            .clamp(BLOCK_BYTES, MAX_BYTES)
+            .next_power_of_two();
+        BloomFilter {
+            blocks: vec![[0; 8]; bytes / BLOCK_BYTES],
+        }
+    }
+
+    /// Builds a filter holding every non null value of the column. The filter of a list column
+    /// holds the values of its lists.
+    pub fn from_column(column: &ColumnType, distinct: u64, fpp: f64) -> BloomFilter {
+        if let ColumnType::List(c) = column {
+            return BloomFilter::from_column(c.values(), distinct, fpp);
+        }
+        let mut filter = BloomFilter::with_capacity(distinct, fpp);
+        macro_rules! insert_fixed {
+            ($c:expr) => {
+                for value in $c.get_records().flatten() {
+                    filter.insert(&crate::stats::ToScalar::to_scalar(&value));
+                }
+            };
+        }
+        macro_rules! insert_variable {
+            ($c:expr) => {
+                for index in 0..$c.len() {
+                    if let Some(Some(value)) = $c.get(index) {
+                        filter.insert_hash(hash_bytes(value.to_bytes()));
+                    }
+                }
+            };
+        }
+        macro_rules! insert_labels {
+            ($c:expr) => {
+                for label in $c.get_records().flatten() {
+                    filter.insert_hash(hash_bytes(label.as_bytes()));
+                }
+            };
+        }
+        match column {
+            ColumnType::Int8(c) => insert_fixed!(c),
+            ColumnType::Int16(c) => insert_fixed!(c),
+            ColumnType::Int32(c) => insert_fixed!(c),
+            ColumnType::Int64(c) => insert_fixed!(c),
+            ColumnType::UInt8(c) => insert_fixed!(c),
+            ColumnType::UInt16(c) => insert_fixed!(c),
+            ColumnType::UInt32(c) => insert_fixed!(c),
+            ColumnType::UInt64(c) => insert_fixed!(c),
+            ColumnType::Float32(c) => insert_fixed!(c),
+            ColumnType::Float64(c) => insert_fixed!(c),
+            ColumnType::Text(c) => insert_variable!(c),
+            ColumnType::Binary(c) => insert_variable!(c),
+            ColumnType::LargeText(c) => insert_variable!(c),
+            ColumnType::LargeBinary(c) => insert_variable!(c),
+            ColumnType::Date32(c) => insert_fixed!(c.values()),
+            ColumnType::Time64(c) => insert_fixed!(c.values()),
+            ColumnType::Timestamp(c) => insert_fixed!(c.values()),
+            ColumnType::Duration(c) => insert_fixed!(c.values()),
+            ColumnType::FixedSizeBinary(c) => insert_variable!(c),
+            ColumnType::Decimal128(c) => {
+                for value in c.get_records().flatten() {
+                    filter.insert(&Scalar::Decimal128(value, c.scale()));
+                }
+            }
+            ColumnType::Boolean(c) => {
+                for value in c.get_records().flatten() {
+                    filter.insert(&Scalar::Boolean(value));
+                }
+            }
+            ColumnType::Dictionary(c) => {
+                for index in 0..c.len() {
+                    if let Some(Some(value)) = c.get(index) {
+                        filter.insert_hash(hash_bytes(value.as_bytes()));
+                    }
+                }
+            }
+            // labels are hashed like text, so filters can look them up by name
+            ColumnType::Enum8(c) => insert_labels!(c),
+            ColumnType::Enum16(c) => insert_labels!(c),
+            ColumnType::List(_) => unreachable!("handled above"),
+            // structs and maps never equal a scalar
+            ColumnType::Struct(_) | ColumnType::Map(_) => {}
+        }
+        filter
+    }
+
+    pub fn insert(&mut self, value: &Scalar) {
+        if let Some(hash) = hash_scalar(value) {
+            self.insert_hash(hash);
+        }
+    }
+
+    /// False means the value is definitely not in the filter.
+    pub fn might_contain(&self, value: &Scalar) -> bool {
+        match hash_scalar(value) {
+            Some(hash) => self.might_contain_hash(hash),
+            None => true,
+        }
+    }
+
+    pub fn insert_hash(&mut self, hash: u64) {
+        let block = self.block_index(hash);
+        let mask = block_mask(hash as u32);
+        for (word, bit) in self.blocks[block].iter_mut().zip(mask) {
+            *word |= bit;
+        }
+    }
+
+    pub fn might_contain_hash(&self, hash: u64) -> bool {
+        let block = self.block_index(hash);
+        let mask = block_mask(hash as u32);
+        self.blocks[block]
+            .iter()
+            .zip(mask)
+            .all(|(word, bit)| word & bit != 0)
+    }
+
+    fn block_index(&self, hash: u64) -> usize {
+        (((hash >> 32) * self.blocks.len() as u64) >> 32) as usize
+    }
+
+    pub fn byte_size(&self) -> usize {
+        self.blocks.len() * BLOCK_BYTES
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.byte_size());
+        for word in self.blocks.iter().flatten() {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<BloomFilter, &'static str> {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(BLOCK_BYTES) {
+            return Err("bloom filter size must be a multiple of 32 bytes");
+        }
+        let blocks = bytes
+            .chunks_exact(BLOCK_BYTES)
+            .map(|block| {
+                let mut words = [0u32; 8];
+                for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+                    *word = u32::from_le_bytes(bytes.try_into().unwrap());
+                }
+                words
+            })
+            .collect();
+        Ok(BloomFilter { blocks })
+    }
+}
+
+fn block_mask(hash: u32) -> [u32; 8] {
+    let mut mask = [0u32; 8];
+    for (bit, salt) in mask.iter_mut().zip(SALT) {
+        *bit = 1 << (hash.wrapping_mul(salt) >> 27);
+    }
+    mask
+}
+
+// values that compare equal must hash the same, otherwise the filter gives false negatives. A
+// literal `Int32(5)` has to find a 5 stored in an int8 column and `Float64(5.0)` too, so numbers
+// are hashed by value: as a decimal without trailing zeros, which is a plain i128 for integers.
+// Floats are turned into the decimal they equal exactly, the few that have none (too many
+// fraction digits or too large) can't equal a decimal and keep their f64 bits. NaN equals nothing
+// and isn't hashed at all.
+fn hash_scalar(value: &Scalar) -> Option<u64> {
+    match *value {
+        Scalar::Text(ref text) => Some(hash_bytes(text.as_bytes())),
+        Scalar::Binary(ref bytes) => Some(hash_bytes(bytes)),
+        Scalar::Boolean(value) => Some(hash_bytes(&[value as u8])),
+        Scalar::Decimal128(unscaled, scale) => Some(hash_decimal(unscaled, scale)),
+        Scalar::Float32(_) | Scalar::Float64(_) => {
+            let float = value.as_f64()?;
+            if float.is_nan() {
+                None
+            } else if let Some((unscaled, scale)) = float_as_decimal(float) {
+                Some(hash_decimal(unscaled, scale))
+            } else {
+                Some(hash_bytes(&float.to_bits().to_le_bytes()))
+            }
+        }
+        _ => Some(hash_decimal(value.as_i128()?, 0)),
+    }
+}
+
+fn hash_decimal(mut unscaled: i128, mut scale: u8) -> u64 {
+    while scale > 0 && unscaled % 10 == 0 {
+        unscaled /= 10;
+        scale -= 1;
+    }
+    if scale == 0 {
+        return hash_bytes(&unscaled.to_le_bytes());
+    }
+    let mut bytes = unscaled.to_le_bytes().to_vec();
+    bytes.push(scale);
+    hash_bytes(&bytes)
+}
+
+// a float is m / 2^k, which is the decimal m * 5^k / 10^k. Decimals have at most 38 fraction
+// digits. Doubling is exact, also turns -0.0 into 0.
+fn float_as_decimal(float: f64) -> Option<(i128, u8)> {
+    if !float.is_finite() {
+        return None;
+    }
+    let (mut scaled, mut scale) = (float, 0);
+    while scaled.fract() != 0.0 {
+        if scale == 38 {
+            return None;
+        }
+        scaled *= 2.0;
+        scale += 1;
+    }
+    if !(-2f64.powi(127)..2f64.powi(127)).contains(&scaled) {
+        return None;
+    }
+    let unscaled = (scaled as i128).checked_mul(5i128.pow(scale as u32))?;
+    Some((unscaled, scale))
+}
+
+#[cfg(test)]
+mod test {
+    use super::BloomFilter;
+    use crate::stats::Scalar;
+
+    #[test]
+    fn no_false_negatives() {
+        let mut filter = BloomFilter::with_capacity(1000, 0.01);
+        for i in 0..1000 {
+            filter.insert(&Scalar::Text(format!("user-{i}")));
+        }
+        for i in 0..1000 {
+            assert!(filter.might_contain(&Scalar::Text(format!("user-{i}"))));
+        }
+
+        let false_positives = (1000..11000)
+            .filter(|i| filter.might_contain(&Scalar::Text(format!("user-{i}"))))
+            .count();
+        assert!(false_positives < 300, "{false_positives}");
+    }
+
+    #[test]
+    fn numbers_hash_by_value() {
+        let mut filter = BloomFilter::with_capacity(10, 0.01);
+        filter.insert(&Scalar::Int8(5));
+        filter.insert(&Scalar::Float32(-0.0));
+        filter.insert(&Scalar::Float64(2.5));
+        assert!(filter.might_contain(&Scalar::UInt32(5)));
+        assert!(filter.might_contain(&Scalar::Float64(5.0)));
+        assert!(filter.might_contain(&Scalar::Int32(0)));
+        assert!(filter.might_contain(&Scalar::Float32(2.5)));
+
+        filter.insert(&Scalar::UInt64(10u64.pow(19)));
+        assert!(filter.might_contain(&Scalar::Float64(1e19)));
+
+        // fractions are found whatever the scale or type they are written with
+        filter.insert(&Scalar::Decimal128(1250, 3));
+        filter.insert(&Scalar::Float32(0.375));
+        filter.insert(&Scalar::Float64(0.1));
+        assert!(filter.might_contain(&Scalar::Float64(1.25)));
+        assert!(filter.might_contain(&Scalar::Decimal128(125, 2)));
+        assert!(filter.might_contain(&Scalar::Decimal128(3750, 4)));
+        assert!(filter.might_contain(&Scalar::Float64(0.1)));
+        // converting these to f64 rounds differently although they are equal
+        filter.insert(&Scalar::Decimal128(123_456_789_012_345_678_901, 1));
+        assert!(filter.might_contain(&Scalar::Decimal128(1_234_567_890_123_456_789_010, 2)));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut filter = BloomFilter::with_capacity(100, 0.05);
+        filter.insert(&Scalar::Int32(42));
+        let read = BloomFilter::from_bytes(&filter.to_bytes()).unwrap();
+        assert_eq!(read, filter);
+        assert!(BloomFilter::from_bytes(&[0; 31]).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+// a boolean is a single bit, so a column of them is just a BitVec of values next to the usual
+// validity. Unlike Fixed<bool> that would spend a byte per value, 8 values share a byte and the
+// logical kernels work on whole bytes at once.
+//
+// Nulls follow SQL's three valued (Kleene) logic: null means "unknown", so `false AND null` is
+// false and `true OR null` is true whatever the unknown value is, anything else involving a null
+// stays null.
+
+use crate::bitvec::BitVec;
+use crate::stats::{ColumnStats, Scalar};
+
+#[derive(Debug, Default)]
+pub struct BooleanArray {
+    values: BitVec,
+    validity: Option<BitVec>,
+}
+
+impl BooleanArray {
+    pub fn new() -> BooleanArray {
+        BooleanArray::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> BooleanArray {
+        BooleanArray {
+            values: BitVec::with_capacity(capacity),
+            validity: None,
+        }
+    }
+
+    // wraps existing bitmaps, the validity is taken like `Fixed::from_parts` does.
+    pub fn from_parts(values: BitVec, validity: impl Into<Option<BitVec>>) -> BooleanArray {
+        let validity = validity.into().filter(|validity| validity.null_count() > 0);
+        if let Some(validity) = &validity {
+            assert_eq!(
+                values.len(),
+                validity.len(),
+                "values and validity length missmatch"
+            );
+        }
+        BooleanArray { values, validity }
+    }
+
+    /// The values as bits, nulls are false.
+    pub fn values(&self) -> &BitVec {
+        &self.values
+    }
+
+    /// The validity bitmap, `None` when every value is valid.
+    pub fn validity(&self) -> Option<&BitVec> {
+        self.validity.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.validity
+            .as_ref()
+            .is_none_or(|validity| validity[index])
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.validity.as_ref().map_or(0, BitVec::null_count)
+    }
+
+    /// Number of valid values that are true.
+    pub fn true_count(&self) -> usize {
+        match &self.validity {
+            None => self.values.len() - self.values.null_count(),
+            Some(validity) => self
+                .values
+                .as_bytes()
+                .iter()
+                .zip(validity.as_bytes())
+                .map(|(v, m)| (v & m).count_ones() as usize)
+                .sum(),
+        }
+    }
+
+    fn validity_mut(&mut self) -> &mut BitVec {
+        let len = self.values.len();
+        self.validity.get_or_insert_with(|| BitVec::ones(len))
+    }
+
+    pub fn add_record(&mut self, record: bool) {
+        self.values.push(record);
+        if let Some(validity) = &mut self.validity {
+            validity.push(true);
+        }
+    }
+
+    pub fn add_null(&mut self) {
+        self.validity_mut().push(false);
+        self.values.push(false);
+    }
+
+    pub fn get(&self, index: usize) -> Option<Option<bool>> {
+        let value = self.values.get(index)?;
+        Some(self.is_valid(index).then_some(value))
+    }
+
+    pub fn get_records(&self) -> BooleanIter<'_> {
+        BooleanIter {
+            inner: self,
+            index: 0,
+        }
+    }
+
+    pub fn update_record(&mut self, id: usize, record: Option<bool>) -> Result<(), &'static str> {
+        if id >= self.len() {
+            return Err("bad id");
+        }
+        match record {
+            Some(record) => {
+                self.values.set(id, record);
+                if let Some(validity) = &mut self.validity {
+                    validity.set(id, true);
+                }
+            }
+            None => {
+                self.values.set(id, false);
+                self.validity_mut().set(id, false);
+            }
+        }
+        Ok(())
+    }
+
+    // same as Fixed, the last record takes the place of the deleted one.
+    pub fn delete_record(&mut self, id: usize) -> Result<(), &'static str> {
+        if id >= self.len() {
+            return Err("bad id");
+        }
+        let last_index = self.len() - 1;
+        self.values.swap(id, last_index);
+        self.values.pop();
+        if let Some(validity) = &mut self.validity {
+            validity.swap(id, last_index);
+            validity.pop();
+        }
+        Ok(())
+    }
+
+    // copies `length` records starting at `offset` into a new column.
+    pub fn slice(&self, offset: usize, length: usize) -> BooleanArray {
+        BooleanArray::from_parts(
+            self.values.slice(offset, length),
+            self.validity
+                .as_ref()
+                .map(|validity| validity.slice(offset, length)),
+        )
+    }
+
+    // adds every record of other to the end.
+    pub fn append(&mut self, other: &BooleanArray) {
+        for record in other.get_records() {
+            match record {
+                Some(record) => self.add_record(record),
+                None => self.add_null(),
+            }
+        }
+    }
+
+    // two values are all there is, so the stats are counted instead of tracked.
+    pub fn stats(&self) -> ColumnStats {
+        let true_count = self.true_count();
+        let false_count = self.len() - self.null_count() - true_count;
+        let min = if false_count > 0 {
+            Some(Scalar::Boolean(false))
+        } else {
+            (true_count > 0).then_some(Scalar::Boolean(true))
+        };
+        let max = if true_count > 0 {
+            Some(Scalar::Boolean(true))
+        } else {
+            (false_count > 0).then_some(Scalar::Boolean(false))
+        };
+        let byte_size =
+            self.values.as_bytes().len() + self.validity.as_ref().map_or(0, |v| v.as_bytes().len());
+        ColumnStats::new(
+            min,
+            max,
+            self.null_count(),
+            (true_count > 0) as u64 + (false_count > 0) as u64,
+            byte_size,
+        )
+    }
+
+    /// Kleene AND: false if either side is false, null if either side is null, true otherwise.
+    pub fn and(&self, other: &BooleanArray) -> BooleanArray {
+        // valid when both sides are known or either side is a known false
+        self.kleene(
+            other,
+            |a, b| a & b,
+            |a, va, b, vb| (va & vb) | (va & !a) | (vb & !b),
+        )
+    }
+
+    /// Kleene OR: true if either side is true, null if either side is null, false otherwise.
+    pub fn or(&self, other: &BooleanArray) -> BooleanArray {
+        // valid when both sides are known or either side is a known true
+        self.kleene(
+            other,
+            |a, b| a | b,
+            |a, va, b, vb| (va & vb) | (va & a) | (vb & b),
+        )
+    }
+
+    /// Flips every value, nulls stay null.
+    pub fn not(&self) -> BooleanArray {
+        let values = self.values.as_bytes().iter().map(|v| !v).collect();
+        BooleanArray {
+            values: BitVec::from_bytes(values, self.len()),
+            validity: self.validity.clone(),
+        }
+    }
+
+    // applies the kernel a byte (8 values) at a time. Without nulls on either side the validity
+    // doesn't need to be looked at.
+    fn kleene(
+        &self,
+        other: &BooleanArray,
+        value: impl Fn(u8, u8) -> u8,
+        valid: impl Fn(u8, u8, u8, u8) -> u8,
+    ) -> BooleanArray {
+        assert_eq!(self.len(), other.len(), "arrays must have the same length");
+        let (a, b) = (self.values.as_bytes(), other.values.as_bytes());
+        let values: Vec<u8> = a.iter().zip(b).map(|(&a, &b)| value(a, b)).collect();
+        let values = BitVec::from_bytes(values, self.len());
+        if self.validity.is_none() && other.validity.is_none() {
+            return BooleanArray {
+                values,
+                validity: None,
+            };
+        }
+        let all_valid = vec![0xFF; a.len()];
+        let va = self
+            .validity
+            .as_ref()
+            .map_or(&all_valid[..], BitVec::as_bytes);
+        let vb = other
+            .validity
+            .as_ref()
+            .map_or(&all_valid[..], BitVec::as_bytes);
+        let validity = (0..a.len())
+            .map(|i| valid(a[i], va[i], b[i], vb[i]))
+            .collect();
+        BooleanArray::from_parts(values, BitVec::from_bytes(validity, self.len()))
+    }
+}
+
+// `None` adds a null, like for `Fixed`.
+impl FromIterator<Option<bool>> for BooleanArray {
+    fn from_iter<I: IntoIterator<Item = Option<bool>>>(records: I) -> BooleanArray {
+        let records = records.into_iter();
+        let mut array = BooleanArray::with_capacity(records.size_hint().0);
+        for record in records {
+            match record {
+                Some(record) => array.add_record(record),
+                None => array.add_null(),
+            }
+        }
+        array
+    }
+}
+
+pub struct BooleanIter<'a> {
+    inner: &'a BooleanArray,
+    index: usize,
+}
+
+impl<'a> Iterator for BooleanIter<'a> {
+    type Item = Option<bool>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.inner.get(self.index)?;
+        self.index += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let size = self.inner.len() - self.index;
+        (size, Some(size))
+    }
+}
+
+impl<'a> ExactSizeIterator for BooleanIter<'a> {}
+
+#[cfg(test)]
+mod test {
+    use super::BooleanArray;
+    use crate::stats::Scalar;
+
+    fn array(values: &[Option<bool>]) -> BooleanArray {
+        values.iter().copied().collect()
+    }
+
+    #[test]
+    fn add_update_delete() {
+        let mut col = array(&[Some(true), Some(false), Some(true)]);
+        assert_eq!(col.validity(), None);
+        col.add_null();
+        col.add_record(false);
+        assert_eq!(
+            col.get_records().collect::<Vec<_>>(),
+            vec![Some(true), Some(false), Some(true), None, Some(false)]
+        );
+        assert_eq!(col.get(5), None);
+
+        col.update_record(0, None).unwrap();
+        col.update_record(3, Some(true)).unwrap();
+        col.delete_record(1).unwrap();
+        assert_eq!(
+            col.get_records().collect::<Vec<_>>(),
+            vec![None, Some(false), Some(true), Some(true)]
+        );
+        assert!(col.update_record(4, None).is_err());
+        assert_eq!(col.true_count(), 2);
+        assert_eq!(col.null_count(), 1);
+
+        let stats = col.stats();
+        assert_eq!(stats.min(), Some(&Scalar::Boolean(false)));
+        assert_eq!(stats.max(), Some(&Scalar::Boolean(true)));
+        assert_eq!(stats.distinct_count(), 2);
+        assert_eq!(col.slice(2, 2).stats().min(), Some(&Scalar::Boolean(true)));
+    }
+
+    #[test]
+    fn delete_last_valid() {
+        let mut col = array(&[None, Some(true)]);
+        col.delete_record(1).unwrap();
+        assert_eq!(col.true_count(), 0);
+        let stats = col.stats();
+        assert_eq!(stats.null_count(), 1);
+        assert_eq!(stats.min(), None);
+    }
+
+    #[test]
+    fn kleene_logic() {
+        let t = Some(true);
+        let f = Some(false);
+        // every combination of true, false and null
+        let a = array(&[t, t, t, f, f, f, None, None, None]);
+        let b = array(&[t, f, None, t, f, None, t, f, None]);
+        assert_eq!(
+            a.and(&b).get_records().collect::<Vec<_>>(),
+            vec![t, f, None, f, f, f, None, f, None]
+        );
+        assert_eq!(
+            a.or(&b).get_records().collect::<Vec<_>>(),
+            vec![t, t, t, t, f, None, t, None, None]
+        );
+        assert_eq!(
+            a.not().get_records().collect::<Vec<_>>(),
+            vec![f, f, f, t, t, t, None, None, None]
+        );
+
+        // without nulls the result has no validity either
+        let a = array(&[t, f, t, f]);
+        let b = array(&[t, t, f, f]);
+        assert_eq!(a.and(&b).validity(), None);
+        assert_eq!(a.or(&b).get_records().collect::<Vec<_>>(), vec![t, t, t, f]);
+        // a known false decides AND alone, so no null is left
+        assert_eq!(array(&[f, None]).and(&array(&[None, f])).validity(), None);
+    }
+}
@@ -0,0 +1,367 @@
+// changing the type of a column, e.g. text to integers after an import, int32 to float64 for math
+// or numbers to text for an export. A cast goes value by value through the `Array` interface and
+// adds every value to an empty column of the new type, so the column's own parsing is reused:
+// text becomes a date the same way it does in `add_rows`.
+//
+// Nulls stay null. A value that doesn't make it into the new type (an integer out of range, text
+// that isn't a number, a NaN headed for an integer) is an error, or a null when `CastOptions`
+// asks for that. Floats headed for an integer lose their fraction, like `as` does.
+
+use crate::array::{Array, Value};
+use crate::database::{ColumnType, DataType, FieldType};
+use crate::decimal::{Decimal128Array, MAX_PRECISION};
+use crate::temporal::TemporalArray;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CastOptions {
+    null_on_failure: bool,
+}
+
+impl CastOptions {
+    pub fn new() -> CastOptions {
+        CastOptions::default()
+    }
+
+    /// Values that can't be cast become null instead of failing the cast.
+    pub fn with_null_on_failure(mut self, null_on_failure: bool) -> CastOptions {
+        self.null_on_failure = null_on_failure;
+        self
+    }
+
+    pub fn null_on_failure(&self) -> bool {
+        self.null_on_failure
+    }
+}
+
+/// Whether `cast` supports going from `from` to `to`. Any type can be cast to itself.
+pub fn can_cast(from: &DataType, to: &DataType) -> bool {
+    let from_text = is_text(from) || matches!(from, DataType::Enum(_));
+    let from_formatted = matches!(
+        from,
+        DataType::Date32
+            | DataType::Time64(_)
+            | DataType::Timestamp(..)
+            | DataType::Duration(_)
+            | DataType::Decimal128(..)
+    );
+    let from_number = is_number(from) || *from == DataType::Boolean;
+    let to_parsed = matches!(
+        to,
+        DataType::Date32
+            | DataType::Time64(_)
+            | DataType::Timestamp(..)
+            | DataType::Duration(_)
+            | DataType::Decimal128(..)
+            | DataType::FixedSizeBinary(_)
+            | DataType::Enum(_)
+    );
+    from == to
+        || is_text(to) && (from_text || from_formatted || from_number)
+        || (is_number(to) || *to == DataType::Boolean) && (from_text || from_number)
+        || to_parsed && from_text
+}
+
+/// A copy of `array` as a column of type `to`, see `can_cast` for the supported types.
+pub fn cast(
+    array: &dyn Array,
+    to: DataType,
+    options: CastOptions,
+) -> Result<ColumnType, &'static str> {
+    let from = array.data_type();
+    if !can_cast(&from, &to) {
+        return Err("unsupported cast");
+    }
+    if from == to
+        && let Some(column) = array.as_any().downcast_ref::<ColumnType>()
+    {
+        return Ok(column.slice(0, column.len()));
+    }
+    // nested values can't be added back one by one
+    if from == to
+        && matches!(
+            from,
+            DataType::List(_) | DataType::Struct(_) | DataType::Map(_)
+        )
+    {
+        return Err("nested columns are only copied as a ColumnType");
+    }
+    let mut column = ColumnType::empty(&to, array.len());
+    for index in 0..array.len() {
+        let value = array.get_value(index).expect("index is below len");
+        if value.is_null() {
+            column.add_null();
+            continue;
+        }
+        // decimals are only cast to themselves, through their text to keep the digits exact
+        let formatted = is_text(&to) || matches!(value, Value::Decimal128(..));
+        let text = formatted.then(|| format_value(&value));
+        let field = match &text {
+            Some(text) => Some(FieldType::Text(text)),
+            None => to_field(&value, &to),
+        };
+        let added = match field {
+            Some(field) => column.add_field(&field).is_ok(),
+            None => false,
+        };
+        if !added {
+            if !options.null_on_failure {
+                return Err("value can't be cast");
+            }
+            column.add_null();
+        }
+    }
+    Ok(column)
+}
+
+fn is_text(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Text | DataType::LargeText | DataType::Dictionary
+    )
+}
+
+fn is_number(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
+
+// the field to add to a column of type `to`, `None` when the value doesn't fit it. Text is passed
+// on as is, the column parses it, and the other types only reach here when cast to themselves.
+fn to_field<'a>(value: &Value<'a>, to: &DataType) -> Option<FieldType<'a>> {
+    macro_rules! integer {
+        ($type:ty, $variant:ident) => {
+            <$type>::try_from(to_i128(value)?)
+                .ok()
+                .map(FieldType::$variant)
+        };
+    }
+    Some(match (value, to) {
+        (&Value::Text(text) | &Value::Enum(text), DataType::Boolean) => {
+            FieldType::Boolean(text.parse().ok()?)
+        }
+        (&Value::Text(text) | &Value::Enum(text), to) if !is_number(to) => FieldType::Text(text),
+        (_, DataType::Int8) => integer!(i8, Int8)?,
+        (_, DataType::Int16) => integer!(i16, Int16)?,
+        (_, DataType::Int32) => integer!(i32, Int32)?,
+        (_, DataType::Int64) => integer!(i64, Int64)?,
+        (_, DataType::UInt8) => integer!(u8, UInt8)?,
+        (_, DataType::UInt16) => integer!(u16, UInt16)?,
+        (_, DataType::UInt32) => integer!(u32, UInt32)?,
+        (_, DataType::UInt64) => integer!(u64, UInt64)?,
+        (_, DataType::Float32) => {
+            let float = to_f64(value)?;
+            let narrowed = float as f32;
+            // a finite value too large for f32 doesn't fit, infinities and NaN stay what they are
+            if float.is_finite() && narrowed.is_infinite() {
+                return None;
+            }
+            FieldType::Float32(narrowed)
+        }
+        (_, DataType::Float64) => FieldType::Float64(to_f64(value)?),
+        (&Value::Boolean(value), DataType::Boolean) => FieldType::Boolean(value),
+        (_, DataType::Boolean) => FieldType::Boolean(to_f64(value)? != 0.0),
+        (&Value::Binary(bytes), _) => FieldType::Binary(bytes),
+        (&Value::Date32(v), _) => FieldType::Int32(v),
+        (&Value::Time64(v, _) | &Value::Timestamp(v, ..) | &Value::Duration(v, _), _) => {
+            FieldType::Int64(v)
+        }
+        _ => return None,
+    })
+}
+
+// integers, booleans as 0 and 1, floats without their fraction and text holding an integer.
+fn to_i128(value: &Value) -> Option<i128> {
+    Some(match *value {
+        Value::Int8(v) => v as i128,
+        Value::Int16(v) => v as i128,
+        Value::Int32(v) => v as i128,
+        Value::Int64(v) => v as i128,
+        Value::UInt8(v) => v as i128,
+        Value::UInt16(v) => v as i128,
+        Value::UInt32(v) => v as i128,
+        Value::UInt64(v) => v as i128,
+        Value::Float32(v) => float_to_i128(v as f64)?,
+        Value::Float64(v) => float_to_i128(v)?,
+        Value::Boolean(v) => v as i128,
+        Value::Text(v) | Value::Enum(v) => v.parse().ok()?,
+        _ => return None,
+    })
+}
+
+// every float below 2^127 in magnitude fits, the rest and NaN don't.
+fn float_to_i128(value: f64) -> Option<i128> {
+    let value = value.trunc();
+    (value.is_finite() && value.abs() < 2f64.powi(127)).then_some(value as i128)
+}
+
+fn to_f64(value: &Value) -> Option<f64> {
+    Some(match *value {
+        Value::Float32(v) => v as f64,
+        Value::Float64(v) => v,
+        Value::Text(v) | Value::Enum(v) => v.parse().ok()?,
+        _ => to_i128(value)? as f64,
+    })
+}
+
+// the text of any value a text column can be cast from.
+fn format_value(value: &Value) -> String {
+    match *value {
+        Value::Int8(v) => v.to_string(),
+        Value::Int16(v) => v.to_string(),
+        Value::Int32(v) => v.to_string(),
+        Value::Int64(v) => v.to_string(),
+        Value::UInt8(v) => v.to_string(),
+        Value::UInt16(v) => v.to_string(),
+        Value::UInt32(v) => v.to_string(),
+        Value::UInt64(v) => v.to_string(),
+        Value::Float32(v) => v.to_string(),
+        Value::Float64(v) => v.to_string(),
+        Value::Boolean(v) => v.to_string(),
+        Value::Text(v) | Value::Enum(v) => v.to_string(),
+        Value::Date32(v) => TemporalArray::<i32>::new(DataType::Date32).format_value(v),
+        Value::Time64(v, unit) => TemporalArray::new(DataType::Time64(unit)).format_value(v),
+        Value::Timestamp(v, unit, timezone) => {
+            let data_type = DataType::Timestamp(unit, timezone.map(str::to_string));
+            TemporalArray::new(data_type).format_value(v)
+        }
+        Value::Duration(v, unit) => TemporalArray::new(DataType::Duration(unit)).format_value(v),
+        Value::Decimal128(v, scale) => Decimal128Array::new(MAX_PRECISION, scale).format_value(v),
+        _ => unreachable!("checked by can_cast"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CastOptions, can_cast, cast};
+    use crate::array::{Array, Value};
+    use crate::database::{ColumnType, DataType, FieldType};
+    use crate::fixed::Fixed;
+    use crate::temporal::TimeUnit;
+    use crate::variable::StringArray;
+
+    fn values(column: &ColumnType) -> Vec<Value<'_>> {
+        (0..column.len())
+            .map(|i| column.get_value(i).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn numbers() {
+        let mut ints = Fixed::<i32>::new();
+        for value in [1, 300, -5] {
+            ints.add_record(value);
+        }
+        ints.add_null();
+
+        let floats = cast(&ints, DataType::Float64, CastOptions::new()).unwrap();
+        let ColumnType::Float64(floats) = &floats else {
+            panic!("expected a float64 column");
+        };
+        assert_eq!(floats.data()[..3], [1.0, 300.0, -5.0]);
+        assert_eq!(floats.validity(), ints.validity());
+
+        // 300 doesn't fit an u8 and neither does -5
+        assert!(cast(&ints, DataType::UInt8, CastOptions::new()).is_err());
+        let options = CastOptions::new().with_null_on_failure(true);
+        let bytes = cast(&ints, DataType::UInt8, options).unwrap();
+        let expected = [Value::UInt8(1), Value::Null, Value::Null, Value::Null];
+        assert_eq!(values(&bytes), expected);
+
+        let mut floats = Fixed::<f64>::new();
+        for value in [2.9, -2.9, f64::NAN, 1e300] {
+            floats.add_record(value);
+        }
+        let ints = cast(&floats, DataType::Int64, options).unwrap();
+        let expected = [Value::Int64(2), Value::Int64(-2), Value::Null, Value::Null];
+        assert_eq!(values(&ints), expected);
+        let narrowed = cast(&floats, DataType::Float32, options).unwrap();
+        assert_eq!(narrowed.get_value(0), Some(Value::Float32(2.9)));
+        assert!(narrowed.is_null(3));
+
+        let flags = cast(&floats, DataType::Boolean, options).unwrap();
+        assert_eq!(flags.get_value(0), Some(Value::Boolean(true)));
+        let ones = cast(&flags, DataType::Int8, options).unwrap();
+        assert_eq!(ones.get_value(0), Some(Value::Int8(1)));
+    }
+
+    #[test]
+    fn text() {
+        let mut text = StringArray::new();
+        for value in ["42", "-7", "x", "2024-02-29"] {
+            text.add(value);
+        }
+        text.add_null();
+        let options = CastOptions::new().with_null_on_failure(true);
+        let ints = cast(&text, DataType::Int16, options).unwrap();
+        assert_eq!(
+            values(&ints),
+            [
+                Value::Int16(42),
+                Value::Int16(-7),
+                Value::Null,
+                Value::Null,
+                Value::Null
+            ]
+        );
+        assert_eq!(
+            cast(&text, DataType::Int16, CastOptions::new()).unwrap_err(),
+            "value can't be cast"
+        );
+
+        let dates = cast(&text, DataType::Date32, options).unwrap();
+        let ColumnType::Date32(dates) = &dates else {
+            panic!("expected a date32 column");
+        };
+        assert_eq!(dates.format(3), Some(Some("2024-02-29".to_string())));
+        assert_eq!(dates.null_count(), 4);
+
+        let timestamp = DataType::Timestamp(TimeUnit::Second, None);
+        let mut at = ColumnType::empty(&timestamp, 0);
+        at.add_field(&FieldType::Text("2024-01-02T03:04:05"))
+            .unwrap();
+        let text = cast(&at, DataType::Text, CastOptions::new()).unwrap();
+        assert_eq!(text.get_value(0), Some(Value::Text("2024-01-02T03:04:05")));
+        let back = cast(&text, timestamp, CastOptions::new()).unwrap();
+        assert_eq!(back.get_value(0), at.get_value(0));
+
+        let mut flags = StringArray::new();
+        flags.add("true");
+        flags.add("yes");
+        let flags = cast(&flags, DataType::Boolean, options).unwrap();
+        assert_eq!(values(&flags), [Value::Boolean(true), Value::Null]);
+
+        let exported = cast(&ints, DataType::LargeText, CastOptions::new()).unwrap();
+        assert_eq!(exported.get_value(1), Some(Value::Text("-7")));
+        assert!(exported.is_null(2));
+    }
+
+    #[test]
+    fn supported_casts() {
+        let list = DataType::List(Box::new(DataType::Int32));
+        assert!(can_cast(&list, &list));
+        assert!(!can_cast(&list, &DataType::Text));
+        assert!(!can_cast(&DataType::Date32, &DataType::Int32));
+        assert!(can_cast(&DataType::Boolean, &DataType::UInt64));
+        assert!(can_cast(&DataType::Text, &DataType::Decimal128(5, 2)));
+
+        let mut lists = ColumnType::empty(&list, 0);
+        lists
+            .add_field(&FieldType::List(vec![FieldType::Int32(1)]))
+            .unwrap();
+        let copy = cast(&lists, list, CastOptions::new()).unwrap();
+        assert_eq!(copy.get_value(0), lists.get_value(0));
+        let err = cast(&lists, DataType::Int32, CastOptions::new()).unwrap_err();
+        assert_eq!(err, "unsupported cast");
+    }
+}
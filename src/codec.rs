@@ -0,0 +1,366 @@
+// general purpose compression of page buffers. Encodings use what is known about the values
+// (repeats, small deltas), a codec squeezes whatever redundancy is left in the bytes.
+//
+// Every codec has an id that is stored next to each buffer in the footer, so the reader knows how
+// to undo it. Ids 0 and 1 are built in, id 2 is zstd behind the `zstd` cargo feature. Files
+// written without it don't change and a build without the feature reports an unknown codec
+// instead of reading garbage. Other codecs are registered at runtime, which makes them known to
+// both the writer and the reader of this process.
+
+use std::fmt::Debug;
+use std::sync::RwLock;
+
+pub const UNCOMPRESSED_ID: u8 = 0;
+pub const LZ_ID: u8 = 1;
+pub const ZSTD_ID: u8 = 2;
+
+pub trait Codec: Debug + Send + Sync {
+    /// Identifier stored in the file, unique per codec.
+    fn id(&self) -> u8;
+
+    fn name(&self) -> &'static str;
+
+    fn compress(&self, input: &[u8]) -> Vec<u8>;
+
+    /// Undoes `compress`. `length` is the size of the original input.
+    fn decompress(&self, input: &[u8], length: usize) -> Result<Vec<u8>, &'static str>;
+}
+
+static REGISTERED: RwLock<Vec<&'static dyn Codec>> = RwLock::new(Vec::new());
+
+/// Looks up a codec by the id stored in a file.
+pub fn by_id(id: u8) -> Option<&'static dyn Codec> {
+    match id {
+        UNCOMPRESSED_ID => Some(&Uncompressed),
+        LZ_ID => Some(&Lz),
+        #[cfg(feature = "zstd")]
+        ZSTD_ID => Some(&Zstd),
+        _ => REGISTERED
+            .read()
+            .unwrap()
+            .iter()
+            .find(|codec| codec.id() == id)
+            .copied(),
+    }
+}
+
+/// Makes a codec of another crate known to `by_id`, so files using it can be written and read.
+/// Fails when its id is built in or already registered, ids 2 to 15 are kept for built in codecs.
+pub fn register(codec: &'static dyn Codec) -> Result<(), &'static str> {
+    if codec.id() < 16 {
+        return Err("codec id is reserved");
+    }
+    let mut registered = REGISTERED.write().unwrap();
+    if registered.iter().any(|c| c.id() == codec.id()) {
+        return Err("codec id is already registered");
+    }
+    registered.push(codec);
+    Ok(())
+}
+
+/// Stores bytes as they are.
+#[derive(Debug, Clone, Copy)]
+pub struct Uncompressed;
+
+impl Codec for Uncompressed {
+    fn id(&self) -> u8 {
+        UNCOMPRESSED_ID
+    }
+
+    fn name(&self) -> &'static str {
+        "uncompressed"
+    }
+
+    fn compress(&self, input: &[u8]) -> Vec<u8> {
+        input.to_vec()
+    }
+
+    fn decompress(&self, input: &[u8], length: usize) -> Result<Vec<u8>, &'static str> {
+        if input.len() != length {
+            return Err("uncompressed buffer has the wrong size");
+        }
+        Ok(input.to_vec())
+    }
+}
+
+/// Zstandard, slower than `Lz` but a lot tighter.
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy)]
+pub struct Zstd;
+
+#[cfg(feature = "zstd")]
+impl Codec for Zstd {
+    fn id(&self) -> u8 {
+        ZSTD_ID
+    }
+
+    fn name(&self) -> &'static str {
+        "zstd"
+    }
+
+    fn compress(&self, input: &[u8]) -> Vec<u8> {
+        zstd::bulk::compress(input, zstd::DEFAULT_COMPRESSION_LEVEL)
+            .expect("zstd can compress any input")
+    }
+
+    fn decompress(&self, input: &[u8], length: usize) -> Result<Vec<u8>, &'static str> {
+        use std::io::Read;
+
+        // streamed so the output only grows with what the input really expands to, one byte
+        // more than `length` is enough to notice a wrong size
+        let decoder = zstd::Decoder::new(input).map_err(|_| "bad zstd frame")?;
+        let mut output = vec![];
+        decoder
+            .take(length as u64 + 1)
+            .read_to_end(&mut output)
+            .map_err(|_| "bad zstd frame")?;
+        if output.len() != length {
+            return Err("zstd buffer has the wrong size");
+        }
+        Ok(output)
+    }
+}
+
+// a small lz77 in the spirit of lz4. The output is a list of sequences, each one a run of literal
+// bytes followed by a copy of earlier output:
+//
+//   token            u8, literal length in the high nibble, match length - 4 in the low one
+//   literal length   more bytes when the nibble is 15, each adds up to 255
+//   literals
+//   offset           u16, how far back the match starts
+//   match length     more bytes when the nibble is 15
+//
+// The last sequence has no match, it ends where the input ends. Matches are found with a hash
+// table of the last position every 4 byte prefix was seen at, no search, so it is fast rather
+// than tight.
+
+const MIN_MATCH: usize = 4;
+const HASH_BITS: u32 = 12;
+const MAX_OFFSET: usize = u16::MAX as usize;
+
+/// Fast lz77 style compression, built in.
+#[derive(Debug, Clone, Copy)]
+pub struct Lz;
+
+impl Codec for Lz {
+    fn id(&self) -> u8 {
+        LZ_ID
+    }
+
+    fn name(&self) -> &'static str {
+        "lz"
+    }
+
+    fn compress(&self, input: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(input.len() / 2 + 16);
+        let mut table = vec![0u32; 1 << HASH_BITS];
+        let mut anchor = 0;
+        let mut i = 0;
+        while i + MIN_MATCH <= input.len() {
+            let prefix = u32::from_le_bytes(input[i..i + 4].try_into().unwrap());
+            let slot = (prefix.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize;
+            // positions are stored + 1 so 0 means empty
+            let candidate = table[slot] as usize;
+            table[slot] = i as u32 + 1;
+            if candidate > 0 {
+                let start = candidate - 1;
+                if i - start <= MAX_OFFSET && input[start..start + 4] == input[i..i + 4] {
+                    let mut length = MIN_MATCH;
+                    while i + length < input.len() && input[start + length] == input[i + length] {
+                        length += 1;
+                    }
+                    write_sequence(&mut out, &input[anchor..i], Some((i - start, length)));
+                    i += length;
+                    anchor = i;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+        write_sequence(&mut out, &input[anchor..], None);
+        out
+    }
+
+    fn decompress(&self, input: &[u8], length: usize) -> Result<Vec<u8>, &'static str> {
+        // the length comes from the file, so only what the input can expand to is reserved. A
+        // length byte adds at most 255 to a run, anything longer fails below.
+        let mut out = Vec::with_capacity(length.min(input.len().saturating_mul(255)));
+        let mut i = 0;
+        let mut byte = |i: &mut usize| -> Result<u8, &'static str> {
+            let b = *input.get(*i).ok_or("lz stream is truncated")?;
+            *i += 1;
+            Ok(b)
+        };
+        while i < input.len() {
+            let token = byte(&mut i)?;
+            let literals = read_length(&mut byte, &mut i, (token >> 4) as usize)?;
+            let end = i.checked_add(literals).ok_or("lz stream is truncated")?;
+            if end > input.len() || out.len() + literals > length {
+                return Err("lz literals out of bounds");
+            }
+            out.extend_from_slice(&input[i..end]);
+            i = end;
+            if i == input.len() {
+                break;
+            }
+            let offset = u16::from_le_bytes([byte(&mut i)?, byte(&mut i)?]) as usize;
+            let matched = read_length(&mut byte, &mut i, (token & 0x0f) as usize)? + MIN_MATCH;
+            if offset == 0 || offset > out.len() || out.len() + matched > length {
+                return Err("lz match out of bounds");
+            }
+            // the copy may overlap what it writes, e.g. offset 1 repeats a single byte
+            let start = out.len() - offset;
+            for k in 0..matched {
+                out.push(out[start + k]);
+            }
+        }
+        if out.len() != length {
+            return Err("lz output has the wrong size");
+        }
+        Ok(out)
+    }
+}
+
+fn write_length(out: &mut Vec<u8>, mut length: usize) {
+    while length >= 255 {
+        out.push(255);
+        length -= 255;
+    }
+    out.push(length as u8);
+}
+
+fn read_length(
+    byte: &mut impl FnMut(&mut usize) -> Result<u8, &'static str>,
+    i: &mut usize,
+    nibble: usize,
+) -> Result<usize, &'static str> {
+    let mut length = nibble;
+    if nibble == 15 {
+        loop {
+            let b = byte(i)?;
+            length += b as usize;
+            if b < 255 {
+                break;
+            }
+        }
+    }
+    Ok(length)
+}
+
+fn write_sequence(out: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+    let match_nibble = matched.map_or(0, |(_, length)| (length - MIN_MATCH).min(15));
+    out.push((literals.len().min(15) << 4) as u8 | match_nibble as u8);
+    if literals.len() >= 15 {
+        write_length(out, literals.len() - 15);
+    }
+    out.extend_from_slice(literals);
+    if let Some((offset, length)) = matched {
+        out.extend_from_slice(&(offset as u16).to_le_bytes());
+        if length - MIN_MATCH >= 15 {
+            write_length(out, length - MIN_MATCH - 15);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Codec, Lz, Uncompressed, ZSTD_ID, by_id, register};
+
+    fn round_trip(codec: &dyn Codec, input: &[u8]) -> usize {
+        let compressed = codec.compress(input);
+        assert_eq!(
+            codec.decompress(&compressed, input.len()).as_deref(),
+            Ok(input)
+        );
+        compressed.len()
+    }
+
+    #[test]
+    fn lz_round_trip() {
+        assert_eq!(round_trip(&Lz, b""), 1);
+        round_trip(&Lz, b"abc");
+        round_trip(&Lz, &[7; 1000]);
+        round_trip(&Lz, b"abcabcabcabcabcabcabcabcabcabcabcabcabcabc");
+
+        let mut noise = vec![];
+        let mut x = 1u32;
+        for _ in 0..70_000 {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            noise.push(x as u8);
+        }
+        let size = round_trip(&Lz, &noise);
+        assert!(size < noise.len() + noise.len() / 100 + 16);
+    }
+
+    #[test]
+    fn lz_shrinks_repetitive_data() {
+        let text: Vec<u8> = (0..2000)
+            .flat_map(|i| format!("user-{} status=active country=de\n", i % 50).into_bytes())
+            .collect();
+        let size = round_trip(&Lz, &text);
+        assert!(size * 10 < text.len(), "{size} of {}", text.len());
+
+        let compressed = Lz.compress(&text);
+        assert!(
+            Lz.decompress(&compressed[..compressed.len() - 3], text.len())
+                .is_err()
+        );
+        assert!(Lz.decompress(&compressed, text.len() + 1).is_err());
+        // a length no input could expand to fails instead of reserving it
+        assert!(Lz.decompress(&[0], 1 << 62).is_err());
+    }
+
+    #[test]
+    fn codecs_by_id() {
+        assert_eq!(by_id(Lz.id()).unwrap().name(), "lz");
+        assert_eq!(by_id(Uncompressed.id()).unwrap().name(), "uncompressed");
+        assert_eq!(by_id(ZSTD_ID).is_some(), cfg!(feature = "zstd"));
+        round_trip(&Uncompressed, b"plain");
+    }
+
+    #[derive(Debug)]
+    struct Reversed;
+
+    impl Codec for Reversed {
+        fn id(&self) -> u8 {
+            40
+        }
+
+        fn name(&self) -> &'static str {
+            "reversed"
+        }
+
+        fn compress(&self, input: &[u8]) -> Vec<u8> {
+            input.iter().rev().copied().collect()
+        }
+
+        fn decompress(&self, input: &[u8], _: usize) -> Result<Vec<u8>, &'static str> {
+            Ok(self.compress(input))
+        }
+    }
+
+    #[test]
+    fn registered_codecs() {
+        assert!(by_id(40).is_none());
+        register(&Reversed).unwrap();
+        assert_eq!(by_id(40).unwrap().name(), "reversed");
+        assert!(register(&Reversed).is_err());
+        assert!(register(&Lz).is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trip() {
+        use super::Zstd;
+
+        round_trip(&Zstd, b"");
+        let text: Vec<u8> = (0..2000)
+            .flat_map(|i| format!("user-{} status=active\n", i % 50).into_bytes())
+            .collect();
+        let size = round_trip(&Zstd, &text);
+        assert!(size * 10 < text.len());
+        assert!(Zstd.decompress(&Zstd.compress(&text), 1 << 62).is_err());
+    }
+}
@@ -2,73 +2,293 @@
 // but what is a database. Maybe it can be thought if collection of data with certain schema.
 // how to represent a schema? we could have a separate struct to hold schema info, but I don't
 // see the value in doing so.
-// A database is collection of columns and each column can represent it self. So I am going to make
-// a trait that column should implement.
-
-use std::time::Instant;
-
-use crate::{fixed::Fixed, variable::Variable};
-
-// for now, it just hold name and the datatype.
-// pub trait Column {
-//     fn name(&self) -> &str;
-//
-//     fn data_type(&self) -> Type;
-//
-//     fn describe(&self) -> String {
-//         format!("{}:{:?}", self.name(), self.data_type())
-//     }
-//
-//     fn get(&self, index: usize) -> Type;
-//
-//     fn add(&self, value: Type);
-// }
-//
-// #[derive(Debug)]
-// pub enum Type<'a> {
-//     Int8(i8),
-//     Int16(i16),
-//     Int32(i32),
-//     Int64(i64),
-//     UInt8(u8),
-//     UInt16(u16),
-//     UInt32(u32),
-//     UInt64(u64),
-//     Float32(f32),
-//     Float64(f64),
-//     Text(&'a str),
-//     ByteArray(&'a [u8]),
-// }
-//
-// a database is just some columns
+// A database is collection of columns and each column can represent it self. So every column
+// implements a trait, `Array` (see array.rs), for code that doesn't care which kind it is.
+
+use std::any::Any;
+use std::fmt::Display;
+
+use crate::{
+    array::{Array, Value},
+    boolean::BooleanArray,
+    decimal::Decimal128Array,
+    dictionary::DictionaryArray,
+    enum_array::{Enum8Array, Enum16Array, code_width},
+    fixed::Fixed,
+    fixed_binary::FixedSizeBinaryArray,
+    list::List,
+    map::Map,
+    stats::ColumnStats,
+    struct_array::StructArray,
+    temporal::{TemporalArray, TimeUnit},
+    variable::{StringArray, Variable},
+};
+
+// a database is just some columns, but keeping each column in one growing Vec means every append
+// may reallocate and copy the whole column. Instead, rows are split into row groups of a fixed
+// size and every row group owns its own chunk of each column. A full row group is never touched
+// again by appends, so readers can go through the table one row group at a time and workers can
+// split the work by row group.
+
+/// Default number of rows stored in a single row group.
+pub const DEFAULT_ROW_GROUP_SIZE: usize = 64 * 1024;
+
 #[derive(Debug)]
 pub struct Database {
-    fields: Vec<Column>,
+    row_groups: Vec<RowGroup>,
+    row_group_size: usize,
 }
 
 impl Database {
     pub fn new(fields: Vec<Column>) -> Database {
-        Database { fields }
+        Database::with_row_group_size(fields, DEFAULT_ROW_GROUP_SIZE)
+    }
+
+    // the given columns are the first row group and also act as the schema for the next ones.
+    pub fn with_row_group_size(fields: Vec<Column>, row_group_size: usize) -> Database {
+        assert!(row_group_size > 0, "row group size must be positive");
+        let num_rows = fields.first().map_or(0, |f| f.inner.len());
+        assert!(
+            fields.iter().all(|f| f.inner.len() == num_rows),
+            "columns have different lengths"
+        );
+        Database {
+            row_groups: vec![RowGroup {
+                columns: fields,
+                num_rows,
+            }],
+            row_group_size,
+        }
+    }
+
+    pub fn row_group_size(&self) -> usize {
+        self.row_group_size
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.row_groups.iter().map(|rg| rg.num_rows).sum()
+    }
+
+    pub fn row_groups(&self) -> &[RowGroup] {
+        &self.row_groups
+    }
+
+    pub fn row_group(&self, index: usize) -> Option<&RowGroup> {
+        self.row_groups.get(index)
     }
+
+    pub fn schema(&self) -> Vec<Field> {
+        self.row_groups[0]
+            .columns
+            .iter()
+            .map(|c| Field::new(c.name.clone(), c.inner.data_type()))
+            .collect()
+    }
+
+    // used when the row groups come from somewhere else, e.g. a file. An empty table still keeps
+    // one empty row group around so the schema is known.
+    pub(crate) fn from_row_groups(
+        schema: &[Field],
+        mut row_groups: Vec<RowGroup>,
+        row_group_size: usize,
+    ) -> Database {
+        if row_groups.is_empty() {
+            row_groups.push(RowGroup::empty(schema, 0));
+        }
+        Database {
+            row_groups,
+            row_group_size,
+        }
+    }
+
+    /// Panics when a value doesn't fit its column, see `try_add_rows`.
     pub fn add_rows(&mut self, row: &Row) {
-        for (name, f) in row.header.iter().zip(row.fields.iter()) {
-            for field in &mut self.fields {
-                if &field.name == name {
-                    match (&mut field.inner, f) {
-                        (ColumnType::Int8(c), &FieldType::Int8(v)) => {
-                            c.add_record(v);
-                        }
-                        (ColumnType::Text(c), &FieldType::Text(v)) => {
-                            c.add(v);
-                        }
-                        _ => panic!("schema miss match"),
-                    }
+        self.try_add_rows(row).expect("schema miss match");
+    }
+
+    /// Adds a row, or nothing when one of its values doesn't fit its column or a column appears
+    /// twice. Columns the row leaves out get a null.
+    pub fn try_add_rows(&mut self, row: &Row) -> Result<(), RowError> {
+        let row_group_count = self.row_groups.len();
+        let row_group = self.writable_row_group();
+        let lengths: Vec<usize> = row_group.columns.iter().map(|c| c.inner.len()).collect();
+        let mut result = Ok(());
+        'row: for (name, f) in row.header.iter().zip(row.fields.iter()) {
+            for (column, &len) in row_group.columns.iter_mut().zip(&lengths) {
+                if &column.name != name {
+                    continue;
+                }
+                if column.inner.len() > len {
+                    result = Err(RowError::Mismatch {
+                        column: column.name.clone(),
+                        message: "column appears twice in the row",
+                    });
+                    break 'row;
+                }
+                if let Err(message) = column.inner.add_field(f) {
+                    result = Err(RowError::new(column, f, message));
+                    break 'row;
+                }
+            }
+        }
+        if result.is_err() {
+            // rows rarely fail, so copying the columns that already took a value back is fine
+            for (column, &len) in row_group.columns.iter_mut().zip(&lengths) {
+                if column.inner.len() > len {
+                    column.inner = column.inner.slice(0, len);
                 }
             }
+            // a row group started for this row would stay empty
+            self.row_groups.truncate(row_group_count);
+            return result;
+        }
+        // every chunk has to keep num_rows values
+        for (column, &len) in row_group.columns.iter_mut().zip(&lengths) {
+            if column.inner.len() == len {
+                column.inner.add_null();
+            }
+        }
+        row_group.num_rows += 1;
+        Ok(())
+    }
+
+    /// A copy of the table with only the columns at `paths`, named by their path. A path is a
+    /// column name or goes into struct columns, e.g. "address.city".
+    pub fn project(&self, paths: &[&str]) -> Result<Database, &'static str> {
+        let mut row_groups = Vec::with_capacity(self.row_groups.len());
+        for rg in &self.row_groups {
+            let mut columns = Vec::with_capacity(paths.len());
+            for &path in paths {
+                let column = rg.column_by_path(path).ok_or("unknown column")?;
+                columns.push(Column::new(path.to_string(), column.slice(0, rg.num_rows)));
+            }
+            row_groups.push(RowGroup::new(columns, rg.num_rows));
+        }
+        Ok(Database {
+            row_groups,
+            row_group_size: self.row_group_size,
+        })
+    }
+
+    // returns the last row group, starting a new one when it is full. The new chunks are
+    // allocated for the whole row group upfront so appends never reallocate.
+    fn writable_row_group(&mut self) -> &mut RowGroup {
+        let last = self.row_groups.last().expect("there is always a row group");
+        if last.num_rows >= self.row_group_size {
+            let row_group = RowGroup::empty(&self.schema(), self.row_group_size);
+            self.row_groups.push(row_group);
         }
+        self.row_groups.last_mut().unwrap()
     }
 }
 
+/// A horizontal slice of the table holding one chunk per column.
+#[derive(Debug)]
+pub struct RowGroup {
+    columns: Vec<Column>,
+    num_rows: usize,
+}
+
+impl RowGroup {
+    pub(crate) fn new(columns: Vec<Column>, num_rows: usize) -> RowGroup {
+        RowGroup { columns, num_rows }
+    }
+
+    // same columns as the schema but no data.
+    pub(crate) fn empty(schema: &[Field], capacity: usize) -> RowGroup {
+        let columns = schema
+            .iter()
+            .map(|f| Column::new(f.name.clone(), ColumnType::empty(&f.data_type, capacity)))
+            .collect();
+        RowGroup {
+            columns,
+            num_rows: 0,
+        }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    /// The column at `path`, "address.city" is the field city of the struct column address. A
+    /// column whose name has a dot is found by its name first.
+    pub fn column_by_path(&self, path: &str) -> Option<&ColumnType> {
+        if let Some(column) = self.column(path) {
+            return Some(column.inner());
+        }
+        let (name, rest) = path.split_once('.')?;
+        match self.column(name)?.inner() {
+            ColumnType::Struct(c) => c.field_by_path(rest),
+            _ => None,
+        }
+    }
+}
+
+/// Why `Database::try_add_rows` rejected a row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowError {
+    /// Text that isn't one of the labels of an enum column.
+    UnknownLabel { column: String, label: String },
+    /// A value that doesn't fit its column for another reason, e.g. a type mismatch.
+    Mismatch {
+        column: String,
+        message: &'static str,
+    },
+}
+
+impl RowError {
+    fn new(column: &Column, field: &FieldType, message: &'static str) -> RowError {
+        let name = column.name.clone();
+        match (&column.inner, field) {
+            (ColumnType::Enum8(_) | ColumnType::Enum16(_), &FieldType::Text(label)) => {
+                RowError::UnknownLabel {
+                    column: name,
+                    label: label.to_string(),
+                }
+            }
+            _ => RowError::Mismatch {
+                column: name,
+                message,
+            },
+        }
+    }
+
+    /// Name of the column the value was for.
+    pub fn column(&self) -> &str {
+        match self {
+            RowError::UnknownLabel { column, .. } | RowError::Mismatch { column, .. } => column,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            RowError::UnknownLabel { .. } => "unknown enum label",
+            RowError::Mismatch { message, .. } => message,
+        }
+    }
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowError::UnknownLabel { column, label } => {
+                write!(f, "column {column}: unknown enum label {label:?}")
+            }
+            RowError::Mismatch { column, message } => write!(f, "column {column}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for RowError {}
+
 pub struct Row<'a> {
     header: Vec<String>,
     fields: Vec<FieldType<'a>>,
@@ -81,8 +301,138 @@ impl<'a> Row<'a> {
 }
 
 pub enum FieldType<'a> {
+    Null,
     Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    UInt8(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    Float32(f32),
+    Float64(f64),
     Text(&'a str),
+    Binary(&'a [u8]),
+    Boolean(bool),
+    /// The values of a list, nulls inside are null values.
+    List(Vec<FieldType<'a>>),
+    /// The value of every field of a struct, in the order of the fields.
+    Struct(Vec<FieldType<'a>>),
+    /// The key and value of every entry of a map.
+    Map(Vec<(&'a str, FieldType<'a>)>),
+}
+
+/// Name and type of a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    name: String,
+    data_type: DataType,
+}
+
+impl Field {
+    pub fn new(name: String, data_type: DataType) -> Field {
+        Field { name, data_type }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataType {
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Float32,
+    Float64,
+    Text,
+    Binary,
+    /// Text with i64 offsets, for chunks over 2 GiB.
+    LargeText,
+    /// Binary with i64 offsets, for chunks over 2 GiB.
+    LargeBinary,
+    /// Text stored as a dictionary of distinct values plus a code per row.
+    Dictionary,
+    Boolean,
+    /// Days since 1970-01-01, stored as int32.
+    Date32,
+    /// Time of day since midnight, stored as int64.
+    Time64(TimeUnit),
+    /// Instant since 1970-01-01T00:00:00 UTC, stored as int64. The timezone is used for display
+    /// and calendar kernels.
+    Timestamp(TimeUnit, Option<String>),
+    /// Elapsed time, stored as int64.
+    Duration(TimeUnit),
+    /// Fixed point number with a precision (total digits) and scale (fraction digits), stored
+    /// as i128.
+    Decimal128(u8, u8),
+    /// Binary values that all have the given number of bytes, e.g. 16 for UUIDs.
+    FixedSizeBinary(usize),
+    /// A list of values of the given type per row.
+    List(Box<DataType>),
+    /// A record of named fields per row.
+    Struct(Vec<Field>),
+    /// Text keys with values of the given type per row.
+    Map(Box<DataType>),
+    /// One of the given labels, stored as the uint8 or uint16 position of the label.
+    Enum(Vec<String>),
+}
+
+impl DataType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::Int8 => "int8",
+            DataType::Int16 => "int16",
+            DataType::Int32 => "int32",
+            DataType::Int64 => "int64",
+            DataType::UInt8 => "uint8",
+            DataType::UInt16 => "uint16",
+            DataType::UInt32 => "uint32",
+            DataType::UInt64 => "uint64",
+            DataType::Float32 => "float32",
+            DataType::Float64 => "float64",
+            DataType::Text => "text",
+            DataType::Binary => "binary",
+            DataType::LargeText => "large text",
+            DataType::LargeBinary => "large binary",
+            DataType::Dictionary => "dictionary",
+            DataType::Boolean => "boolean",
+            DataType::Date32 => "date32",
+            DataType::Time64(_) => "time64",
+            DataType::Timestamp(..) => "timestamp",
+            DataType::Duration(_) => "duration",
+            DataType::Decimal128(..) => "decimal128",
+            DataType::FixedSizeBinary(_) => "fixed size binary",
+            DataType::List(_) => "list",
+            DataType::Struct(_) => "struct",
+            DataType::Map(_) => "map",
+            DataType::Enum(_) => "enum",
+        }
+    }
+
+    /// The type values are stored as, e.g. int64 for timestamps.
+    pub fn physical(&self) -> DataType {
+        match self {
+            DataType::Date32 => DataType::Int32,
+            DataType::Time64(_) | DataType::Timestamp(..) | DataType::Duration(_) => {
+                DataType::Int64
+            }
+            DataType::Enum(labels) if code_width(labels.len()) == Some(1) => DataType::UInt8,
+            DataType::Enum(_) => DataType::UInt16,
+            data_type => data_type.clone(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -95,22 +445,551 @@ impl Column {
     pub fn new(name: String, inner: ColumnType) -> Column {
         Column { name, inner }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn inner(&self) -> &ColumnType {
+        &self.inner
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut ColumnType {
+        &mut self.inner
+    }
+
     pub fn describe(&self) -> String {
         format!("{}:{}", self.name, self.inner.type_str())
     }
+
+    pub fn stats(&self) -> ColumnStats {
+        self.inner.stats()
+    }
 }
 
 #[derive(Debug)]
 pub enum ColumnType {
     Int8(Fixed<i8>),
+    Int16(Fixed<i16>),
+    Int32(Fixed<i32>),
+    Int64(Fixed<i64>),
+    UInt8(Fixed<u8>),
+    UInt16(Fixed<u16>),
+    UInt32(Fixed<u32>),
+    UInt64(Fixed<u64>),
+    Float32(Fixed<f32>),
+    Float64(Fixed<f64>),
     Text(Variable<str>),
+    Binary(Variable<[u8]>),
+    LargeText(Variable<str, i64>),
+    LargeBinary(Variable<[u8], i64>),
+    Dictionary(DictionaryArray),
+    Boolean(BooleanArray),
+    Date32(TemporalArray<i32>),
+    Time64(TemporalArray<i64>),
+    Timestamp(TemporalArray<i64>),
+    Duration(TemporalArray<i64>),
+    Decimal128(Decimal128Array),
+    FixedSizeBinary(FixedSizeBinaryArray),
+    List(List<ColumnType>),
+    Struct(StructArray),
+    Map(Map<StringArray, ColumnType>),
+    Enum8(Enum8Array),
+    Enum16(Enum16Array),
+}
+
+// every column kind has the same basic methods, so the match over all variants is generated.
+macro_rules! dispatch {
+    ($column:expr, $c:ident => $body:expr) => {
+        match $column {
+            ColumnType::Int8($c) => $body,
+            ColumnType::Int16($c) => $body,
+            ColumnType::Int32($c) => $body,
+            ColumnType::Int64($c) => $body,
+            ColumnType::UInt8($c) => $body,
+            ColumnType::UInt16($c) => $body,
+            ColumnType::UInt32($c) => $body,
+            ColumnType::UInt64($c) => $body,
+            ColumnType::Float32($c) => $body,
+            ColumnType::Float64($c) => $body,
+            ColumnType::Text($c) => $body,
+            ColumnType::Binary($c) => $body,
+            ColumnType::LargeText($c) => $body,
+            ColumnType::LargeBinary($c) => $body,
+            ColumnType::Dictionary($c) => $body,
+            ColumnType::Boolean($c) => $body,
+            ColumnType::Date32($c) => $body,
+            ColumnType::Time64($c) => $body,
+            ColumnType::Timestamp($c) => $body,
+            ColumnType::Duration($c) => $body,
+            ColumnType::Decimal128($c) => $body,
+            ColumnType::FixedSizeBinary($c) => $body,
+            ColumnType::List($c) => $body,
+            ColumnType::Struct($c) => $body,
+            ColumnType::Map($c) => $body,
+            ColumnType::Enum8($c) => $body,
+            ColumnType::Enum16($c) => $body,
+        }
+    };
 }
 
 impl ColumnType {
+    pub fn empty(data_type: &DataType, capacity: usize) -> ColumnType {
+        match data_type {
+            DataType::Int8 => ColumnType::Int8(Fixed::with_capacity(capacity)),
+            DataType::Int16 => ColumnType::Int16(Fixed::with_capacity(capacity)),
+            DataType::Int32 => ColumnType::Int32(Fixed::with_capacity(capacity)),
+            DataType::Int64 => ColumnType::Int64(Fixed::with_capacity(capacity)),
+            DataType::UInt8 => ColumnType::UInt8(Fixed::with_capacity(capacity)),
+            DataType::UInt16 => ColumnType::UInt16(Fixed::with_capacity(capacity)),
+            DataType::UInt32 => ColumnType::UInt32(Fixed::with_capacity(capacity)),
+            DataType::UInt64 => ColumnType::UInt64(Fixed::with_capacity(capacity)),
+            DataType::Float32 => ColumnType::Float32(Fixed::with_capacity(capacity)),
+            DataType::Float64 => ColumnType::Float64(Fixed::with_capacity(capacity)),
+            DataType::Text => ColumnType::Text(Variable::with_capacity(capacity)),
+            DataType::Binary => ColumnType::Binary(Variable::with_capacity(capacity)),
+            DataType::LargeText => ColumnType::LargeText(Variable::with_capacity(capacity)),
+            DataType::LargeBinary => ColumnType::LargeBinary(Variable::with_capacity(capacity)),
+            DataType::Dictionary => {
+                ColumnType::Dictionary(DictionaryArray::with_capacity(capacity))
+            }
+            DataType::Boolean => ColumnType::Boolean(BooleanArray::with_capacity(capacity)),
+            DataType::Date32 => {
+                ColumnType::Date32(TemporalArray::with_capacity(data_type.clone(), capacity))
+            }
+            DataType::Time64(_) => {
+                ColumnType::Time64(TemporalArray::with_capacity(data_type.clone(), capacity))
+            }
+            DataType::Timestamp(..) => {
+                ColumnType::Timestamp(TemporalArray::with_capacity(data_type.clone(), capacity))
+            }
+            DataType::Duration(_) => {
+                ColumnType::Duration(TemporalArray::with_capacity(data_type.clone(), capacity))
+            }
+            &DataType::Decimal128(precision, scale) => {
+                ColumnType::Decimal128(Decimal128Array::with_capacity(precision, scale, capacity))
+            }
+            &DataType::FixedSizeBinary(width) => {
+                ColumnType::FixedSizeBinary(FixedSizeBinaryArray::with_capacity(width, capacity))
+            }
+            DataType::List(values) => {
+                ColumnType::List(List::with_capacity(ColumnType::empty(values, 0), capacity))
+            }
+            DataType::Struct(fields) => {
+                ColumnType::Struct(StructArray::with_capacity(fields, capacity))
+            }
+            DataType::Map(values) => ColumnType::Map(Map::with_capacity(
+                StringArray::new(),
+                ColumnType::empty(values, 0),
+                capacity,
+            )),
+            // panics on more labels than a u16 can tell apart
+            DataType::Enum(labels) if code_width(labels.len()) == Some(1) => {
+                ColumnType::Enum8(Enum8Array::with_capacity(labels.clone(), capacity))
+            }
+            DataType::Enum(labels) => {
+                ColumnType::Enum16(Enum16Array::with_capacity(labels.clone(), capacity))
+            }
+        }
+    }
+
+    pub fn data_type(&self) -> DataType {
+        match self {
+            ColumnType::Int8(_) => DataType::Int8,
+            ColumnType::Int16(_) => DataType::Int16,
+            ColumnType::Int32(_) => DataType::Int32,
+            ColumnType::Int64(_) => DataType::Int64,
+            ColumnType::UInt8(_) => DataType::UInt8,
+            ColumnType::UInt16(_) => DataType::UInt16,
+            ColumnType::UInt32(_) => DataType::UInt32,
+            ColumnType::UInt64(_) => DataType::UInt64,
+            ColumnType::Float32(_) => DataType::Float32,
+            ColumnType::Float64(_) => DataType::Float64,
+            ColumnType::Text(_) => DataType::Text,
+            ColumnType::Binary(_) => DataType::Binary,
+            ColumnType::LargeText(_) => DataType::LargeText,
+            ColumnType::LargeBinary(_) => DataType::LargeBinary,
+            ColumnType::Dictionary(_) => DataType::Dictionary,
+            ColumnType::Boolean(_) => DataType::Boolean,
+            ColumnType::Date32(c) => c.data_type().clone(),
+            ColumnType::Time64(c) => c.data_type().clone(),
+            ColumnType::Timestamp(c) => c.data_type().clone(),
+            ColumnType::Duration(c) => c.data_type().clone(),
+            ColumnType::Decimal128(c) => c.data_type(),
+            ColumnType::FixedSizeBinary(c) => DataType::FixedSizeBinary(c.width()),
+            ColumnType::List(c) => DataType::List(Box::new(c.values().data_type())),
+            ColumnType::Struct(c) => c.data_type(),
+            ColumnType::Map(c) => DataType::Map(Box::new(c.values().data_type())),
+            ColumnType::Enum8(c) => c.data_type(),
+            ColumnType::Enum16(c) => c.data_type(),
+        }
+    }
+
+    /// The column behind the generic `Array` interface.
+    pub fn as_array(&self) -> &dyn Array {
+        dispatch!(self, c => c)
+    }
+
     pub fn type_str(&self) -> &'static str {
+        self.data_type().name()
+    }
+
+    pub fn len(&self) -> usize {
+        dispatch!(self, c => c.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> ColumnStats {
+        dispatch!(self, c => c.stats())
+    }
+
+    pub fn add_null(&mut self) {
+        dispatch!(self, c => c.add_null())
+    }
+
+    // copies `length` values starting at `offset` into a new column of the same type.
+    pub fn slice(&self, offset: usize, length: usize) -> ColumnType {
         match self {
-            ColumnType::Int8(_) => "int8",
-            ColumnType::Text(_) => "text",
+            ColumnType::Int8(c) => ColumnType::Int8(c.slice(offset, length)),
+            ColumnType::Int16(c) => ColumnType::Int16(c.slice(offset, length)),
+            ColumnType::Int32(c) => ColumnType::Int32(c.slice(offset, length)),
+            ColumnType::Int64(c) => ColumnType::Int64(c.slice(offset, length)),
+            ColumnType::UInt8(c) => ColumnType::UInt8(c.slice(offset, length)),
+            ColumnType::UInt16(c) => ColumnType::UInt16(c.slice(offset, length)),
+            ColumnType::UInt32(c) => ColumnType::UInt32(c.slice(offset, length)),
+            ColumnType::UInt64(c) => ColumnType::UInt64(c.slice(offset, length)),
+            ColumnType::Float32(c) => ColumnType::Float32(c.slice(offset, length)),
+            ColumnType::Float64(c) => ColumnType::Float64(c.slice(offset, length)),
+            ColumnType::Text(c) => ColumnType::Text(c.slice(offset, length)),
+            ColumnType::Binary(c) => ColumnType::Binary(c.slice(offset, length)),
+            ColumnType::LargeText(c) => ColumnType::LargeText(c.slice(offset, length)),
+            ColumnType::LargeBinary(c) => ColumnType::LargeBinary(c.slice(offset, length)),
+            ColumnType::Dictionary(c) => ColumnType::Dictionary(c.slice(offset, length)),
+            ColumnType::Boolean(c) => ColumnType::Boolean(c.slice(offset, length)),
+            ColumnType::Date32(c) => ColumnType::Date32(c.slice(offset, length)),
+            ColumnType::Time64(c) => ColumnType::Time64(c.slice(offset, length)),
+            ColumnType::Timestamp(c) => ColumnType::Timestamp(c.slice(offset, length)),
+            ColumnType::Duration(c) => ColumnType::Duration(c.slice(offset, length)),
+            ColumnType::Decimal128(c) => ColumnType::Decimal128(c.slice(offset, length)),
+            ColumnType::FixedSizeBinary(c) => ColumnType::FixedSizeBinary(c.slice(offset, length)),
+            ColumnType::List(c) => ColumnType::List(c.slice(offset, length)),
+            ColumnType::Struct(c) => ColumnType::Struct(c.slice(offset, length)),
+            ColumnType::Map(c) => ColumnType::Map(c.slice(offset, length)),
+            ColumnType::Enum8(c) => ColumnType::Enum8(c.slice(offset, length)),
+            ColumnType::Enum16(c) => ColumnType::Enum16(c.slice(offset, length)),
         }
     }
+
+    pub fn append(&mut self, other: &ColumnType) -> Result<(), &'static str> {
+        match (self, other) {
+            (ColumnType::Int8(a), ColumnType::Int8(b)) => a.append(b),
+            (ColumnType::Int16(a), ColumnType::Int16(b)) => a.append(b),
+            (ColumnType::Int32(a), ColumnType::Int32(b)) => a.append(b),
+            (ColumnType::Int64(a), ColumnType::Int64(b)) => a.append(b),
+            (ColumnType::UInt8(a), ColumnType::UInt8(b)) => a.append(b),
+            (ColumnType::UInt16(a), ColumnType::UInt16(b)) => a.append(b),
+            (ColumnType::UInt32(a), ColumnType::UInt32(b)) => a.append(b),
+            (ColumnType::UInt64(a), ColumnType::UInt64(b)) => a.append(b),
+            (ColumnType::Float32(a), ColumnType::Float32(b)) => a.append(b),
+            (ColumnType::Float64(a), ColumnType::Float64(b)) => a.append(b),
+            (ColumnType::Text(a), ColumnType::Text(b)) => a.append(b),
+            (ColumnType::Binary(a), ColumnType::Binary(b)) => a.append(b),
+            (ColumnType::LargeText(a), ColumnType::LargeText(b)) => a.append(b),
+            (ColumnType::LargeBinary(a), ColumnType::LargeBinary(b)) => a.append(b),
+            (ColumnType::Dictionary(a), ColumnType::Dictionary(b)) => a.append(b),
+            (ColumnType::Boolean(a), ColumnType::Boolean(b)) => a.append(b),
+            (ColumnType::Date32(a), ColumnType::Date32(b)) if a.data_type() == b.data_type() => {
+                a.append(b)
+            }
+            (ColumnType::Time64(a), ColumnType::Time64(b))
+            | (ColumnType::Timestamp(a), ColumnType::Timestamp(b))
+            | (ColumnType::Duration(a), ColumnType::Duration(b))
+                if a.data_type() == b.data_type() =>
+            {
+                a.append(b)
+            }
+            (ColumnType::Decimal128(a), ColumnType::Decimal128(b))
+                if a.data_type() == b.data_type() =>
+            {
+                a.append(b)
+            }
+            (ColumnType::FixedSizeBinary(a), ColumnType::FixedSizeBinary(b))
+                if a.width() == b.width() =>
+            {
+                a.append(b)
+            }
+            (ColumnType::List(a), ColumnType::List(b))
+                if a.values().data_type() == b.values().data_type() =>
+            {
+                a.append(b)
+            }
+            (ColumnType::Struct(a), ColumnType::Struct(b)) => a.append(b)?,
+            (ColumnType::Map(a), ColumnType::Map(b))
+                if a.values().data_type() == b.values().data_type() =>
+            {
+                a.append(b)
+            }
+            (ColumnType::Enum8(a), ColumnType::Enum8(b)) if a.labels() == b.labels() => a.append(b),
+            (ColumnType::Enum16(a), ColumnType::Enum16(b)) if a.labels() == b.labels() => {
+                a.append(b)
+            }
+            _ => return Err("column types don't match"),
+        }
+        Ok(())
+    }
+
+    // temporal columns take their raw integers or ISO-8601 text, decimals take text, fixed size
+    // binary columns bytes or hex text, list columns a list of fields for their values, struct
+    // columns a field per struct field, map columns a key and a field per entry and enum columns
+    // one of their labels.
+    pub fn add_field(&mut self, field: &FieldType) -> Result<(), &'static str> {
+        match (self, field) {
+            (c, FieldType::Null) => c.add_null(),
+            (ColumnType::Int8(c), &FieldType::Int8(v)) => c.add_record(v),
+            (ColumnType::Int16(c), &FieldType::Int16(v)) => c.add_record(v),
+            (ColumnType::Int32(c), &FieldType::Int32(v)) => c.add_record(v),
+            (ColumnType::Int64(c), &FieldType::Int64(v)) => c.add_record(v),
+            (ColumnType::UInt8(c), &FieldType::UInt8(v)) => c.add_record(v),
+            (ColumnType::UInt16(c), &FieldType::UInt16(v)) => c.add_record(v),
+            (ColumnType::UInt32(c), &FieldType::UInt32(v)) => c.add_record(v),
+            (ColumnType::UInt64(c), &FieldType::UInt64(v)) => c.add_record(v),
+            (ColumnType::Float32(c), &FieldType::Float32(v)) => c.add_record(v),
+            (ColumnType::Float64(c), &FieldType::Float64(v)) => c.add_record(v),
+            (ColumnType::Text(c), &FieldType::Text(v)) => c.add(v),
+            (ColumnType::Binary(c), &FieldType::Binary(v)) => c.add(v),
+            (ColumnType::LargeText(c), &FieldType::Text(v)) => c.add(v),
+            (ColumnType::LargeBinary(c), &FieldType::Binary(v)) => c.add(v),
+            (ColumnType::Dictionary(c), &FieldType::Text(v)) => c.add(v),
+            (ColumnType::Boolean(c), &FieldType::Boolean(v)) => c.add_record(v),
+            (ColumnType::Date32(c), &FieldType::Int32(v)) => c.add_record(v),
+            (ColumnType::Date32(c), &FieldType::Text(v)) => c.add_text(v)?,
+            (
+                ColumnType::Time64(c) | ColumnType::Timestamp(c) | ColumnType::Duration(c),
+                &FieldType::Int64(v),
+            ) => c.add_record(v),
+            (
+                ColumnType::Time64(c) | ColumnType::Timestamp(c) | ColumnType::Duration(c),
+                &FieldType::Text(v),
+            ) => c.add_text(v)?,
+            (ColumnType::Decimal128(c), &FieldType::Text(v)) => c.add_text(v)?,
+            (ColumnType::FixedSizeBinary(c), &FieldType::Binary(v)) => {
+                if v.len() != c.width() {
+                    return Err("value has the wrong width");
+                }
+                c.add(v)
+            }
+            (ColumnType::FixedSizeBinary(c), &FieldType::Text(v)) => c.add_text(v)?,
+            (ColumnType::List(c), FieldType::List(v)) => c.add_fields(v)?,
+            (ColumnType::Struct(c), FieldType::Struct(v)) => c.add_fields(v)?,
+            (ColumnType::Map(c), FieldType::Map(v)) => c.add_fields(v)?,
+            (ColumnType::Enum8(c), &FieldType::Text(v)) => c.add(v)?,
+            (ColumnType::Enum16(c), &FieldType::Text(v)) => c.add(v)?,
+            _ => return Err("field type doesn't match column type"),
+        }
+        Ok(())
+    }
+}
+
+impl Array for ColumnType {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn data_type(&self) -> DataType {
+        self.data_type()
+    }
+
+    fn is_null(&self, index: usize) -> bool {
+        self.as_array().is_null(index)
+    }
+
+    fn get_value(&self, index: usize) -> Option<Value<'_>> {
+        self.as_array().get_value(index)
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Box<dyn Array> {
+        Box::new(self.slice(offset, length))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Column, ColumnType, DataType, Database, FieldType, Row, RowError};
+    use crate::{fixed::Fixed, stats::Scalar, variable::Variable};
+
+    fn test_db(row_group_size: usize) -> Database {
+        Database::with_row_group_size(
+            vec![
+                Column::new("id".to_string(), ColumnType::Int8(Fixed::new())),
+                Column::new("name".to_string(), ColumnType::Text(Variable::new())),
+            ],
+            row_group_size,
+        )
+    }
+
+    #[test]
+    fn add_rows_splits_row_groups() {
+        let mut db = test_db(4);
+        for i in 0..10 {
+            let row = Row::new(
+                vec!["id".to_string(), "name".to_string()],
+                vec![FieldType::Int8(i), FieldType::Text("row")],
+            );
+            db.add_rows(&row);
+        }
+
+        assert_eq!(db.num_rows(), 10);
+        let sizes: Vec<usize> = db.row_groups().iter().map(|rg| rg.num_rows()).collect();
+        assert_eq!(sizes, vec![4, 4, 2]);
+        for rg in db.row_groups() {
+            assert_eq!(rg.column("id").unwrap().inner().len(), rg.num_rows());
+            assert_eq!(rg.column("name").unwrap().inner().len(), rg.num_rows());
+        }
+
+        let ColumnType::Int8(ids) = db.row_group(2).unwrap().column("id").unwrap().inner() else {
+            panic!("expected int8 column");
+        };
+        assert_eq!(
+            ids.get_records().collect::<Vec<_>>(),
+            vec![Some(8), Some(9)]
+        );
+    }
+
+    #[test]
+    fn new_row_group_is_preallocated() {
+        let mut db = test_db(16);
+        for i in 0..17 {
+            let row = Row::new(
+                vec!["id".to_string(), "name".to_string()],
+                vec![FieldType::Int8(i), FieldType::Text("row")],
+            );
+            db.add_rows(&row);
+        }
+
+        let ColumnType::Int8(ids) = db.row_group(1).unwrap().column("id").unwrap().inner() else {
+            panic!("expected int8 column");
+        };
+        assert!(ids.capacity() >= 16);
+    }
+
+    #[test]
+    fn missing_columns_get_nulls() {
+        let mut db = test_db(16);
+        db.add_rows(&Row::new(vec!["id".to_string()], vec![FieldType::Int8(1)]));
+
+        let names = db.project(&["name"]).unwrap();
+        let name = names.row_group(0).unwrap().column("name").unwrap();
+        assert_eq!(name.inner().len(), 1);
+        assert_eq!(name.stats().null_count(), 1);
+    }
+
+    #[test]
+    fn rejected_rows_leave_no_trace() {
+        let mut db = test_db(1);
+        db.add_rows(&Row::new(vec!["id".to_string()], vec![FieldType::Int8(1)]));
+        let twice = Row::new(
+            vec!["id".to_string(), "id".to_string()],
+            vec![FieldType::Int8(2), FieldType::Int8(3)],
+        );
+        assert_eq!(
+            db.try_add_rows(&twice),
+            Err(RowError::Mismatch {
+                column: "id".to_string(),
+                message: "column appears twice in the row",
+            })
+        );
+        // the second row group was only started for the rejected row
+        assert_eq!(db.row_groups().len(), 1);
+        let id = db.row_group(0).unwrap().column("id").unwrap();
+        assert_eq!(id.inner().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "columns have different lengths")]
+    fn columns_of_different_lengths_are_rejected() {
+        let mut ids = Fixed::new();
+        ids.add_record(1i8);
+        Database::new(vec![
+            Column::new("id".to_string(), ColumnType::Int8(ids)),
+            Column::new("name".to_string(), ColumnType::Text(Variable::new())),
+        ]);
+    }
+
+    #[test]
+    fn stats_per_row_group() {
+        let mut db = test_db(2);
+        for (id, name) in [(3, "c"), (1, "a"), (9, "z")] {
+            let row = Row::new(
+                vec!["id".to_string(), "name".to_string()],
+                vec![FieldType::Int8(id), FieldType::Text(name)],
+            );
+            db.add_rows(&row);
+        }
+        let row = Row::new(
+            vec!["id".to_string(), "name".to_string()],
+            vec![FieldType::Null, FieldType::Text("b")],
+        );
+        db.add_rows(&row);
+
+        let first = db.row_group(0).unwrap().column("id").unwrap().stats();
+        assert_eq!(first.min(), Some(&Scalar::Int8(1)));
+        assert_eq!(first.max(), Some(&Scalar::Int8(3)));
+        let second = db.row_group(1).unwrap().column("id").unwrap().stats();
+        assert_eq!(second.min(), Some(&Scalar::Int8(9)));
+        assert_eq!(second.null_count(), 1);
+        let names = db.row_group(1).unwrap().column("name").unwrap().stats();
+        assert_eq!(names.min(), Some(&Scalar::Text("b".to_string())));
+        assert_eq!(names.max(), Some(&Scalar::Text("z".to_string())));
+    }
+
+    #[test]
+    fn rejected_rows_add_nothing() {
+        let status = DataType::Enum(vec!["open".to_string(), "closed".to_string()]);
+        let mut db = Database::new(vec![
+            Column::new("id".to_string(), ColumnType::Int8(Fixed::new())),
+            Column::new("status".to_string(), ColumnType::empty(&status, 0)),
+        ]);
+        let header = vec!["id".to_string(), "status".to_string()];
+        db.add_rows(&Row::new(
+            header.clone(),
+            vec![FieldType::Int8(1), FieldType::Text("closed")],
+        ));
+        let error = db
+            .try_add_rows(&Row::new(
+                header.clone(),
+                vec![FieldType::Int8(2), FieldType::Text("done")],
+            ))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            RowError::UnknownLabel {
+                column: "status".to_string(),
+                label: "done".to_string()
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "column status: unknown enum label \"done\""
+        );
+        let error = db
+            .try_add_rows(&Row::new(header, vec![FieldType::Text("3")]))
+            .unwrap_err();
+        assert_eq!(error.column(), "id");
+
+        let rg = db.row_group(0).unwrap();
+        assert_eq!(db.num_rows(), 1);
+        assert!(rg.columns().iter().all(|c| c.inner().len() == 1));
+        let ColumnType::Enum8(statuses) = rg.column("status").unwrap().inner() else {
+            panic!("expected an enum column");
+        };
+        assert_eq!(statuses.get(0), Some(Some("closed")));
+        assert_eq!(rg.column("status").unwrap().inner().data_type(), status);
+    }
 }
@@ -0,0 +1,475 @@
+// money can't live in floats: 0.1 has no exact binary representation, so sums drift by fractions of
+// a cent. A decimal stores the value as an integer number of the smallest unit instead, 12.34 with
+// scale 2 is stored as 1234. The scale (digits after the point) and the precision (total digits)
+// belong to the column's type, the values are plain i128 in a Fixed.
+//
+// Precision goes up to 38 digits, the most an i128 can always hold. Arithmetic is exact; when a
+// result doesn't fit its precision it is an error instead of a wrapped or rounded value.
+
+use crate::database::DataType;
+use crate::fixed::{Fixed, FixedViewer, Native};
+use crate::stats::{ColumnStats, Scalar, ToScalar};
+
+/// Most digits a Decimal128 can have.
+pub const MAX_PRECISION: u8 = 38;
+
+impl Native for i128 {
+    const WIDTH: usize = 16;
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        i128::from_le_bytes(bytes.try_into().expect("wrong byte width"))
+    }
+
+    fn hash_value(self) -> u64 {
+        crate::util::hash_bytes(&self.to_le_bytes())
+    }
+
+    fn raw_bits(self) -> u128 {
+        self as u128
+    }
+}
+
+// without a column there is no scale, a bare i128 is a whole number.
+impl ToScalar for i128 {
+    fn to_scalar(&self) -> Scalar {
+        Scalar::Decimal128(*self, 0)
+    }
+}
+
+// largest unscaled value with `precision` digits
+fn max_value(precision: u8) -> i128 {
+    10i128.pow(precision as u32) - 1
+}
+
+// whether `value` has at most `precision` digits. Values of other sources, e.g. `from_parts`, may
+// be i128::MIN, which has no i128 absolute value.
+fn fits(value: i128, precision: u8) -> bool {
+    value.unsigned_abs() <= max_value(precision) as u128
+}
+
+#[derive(Debug)]
+pub struct Decimal128Array {
+    precision: u8,
+    scale: u8,
+    values: Fixed<i128>,
+}
+
+impl Decimal128Array {
+    /// Panics unless `1 <= precision <= 38` and `scale <= precision`.
+    pub fn new(precision: u8, scale: u8) -> Decimal128Array {
+        Decimal128Array::from_parts(precision, scale, Fixed::new())
+    }
+
+    pub fn with_capacity(precision: u8, scale: u8, capacity: usize) -> Decimal128Array {
+        Decimal128Array::from_parts(precision, scale, Fixed::with_capacity(capacity))
+    }
+
+    // wraps existing unscaled values, e.g. when reading a file. They aren't checked against the
+    // precision.
+    pub fn from_parts(precision: u8, scale: u8, values: Fixed<i128>) -> Decimal128Array {
+        assert!(
+            (1..=MAX_PRECISION).contains(&precision),
+            "precision must be between 1 and {MAX_PRECISION}"
+        );
+        assert!(scale <= precision, "scale can't be larger than precision");
+        Decimal128Array {
+            precision,
+            scale,
+            values,
+        }
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    pub fn data_type(&self) -> DataType {
+        DataType::Decimal128(self.precision, self.scale)
+    }
+
+    /// The unscaled integers.
+    pub fn values(&self) -> &Fixed<i128> {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.values.null_count()
+    }
+
+    /// Adds an unscaled value, panics when it has more digits than the precision.
+    pub fn add_record(&mut self, value: i128) {
+        assert!(
+            fits(value, self.precision),
+            "value doesn't fit the precision"
+        );
+        self.values.add_record(value);
+    }
+
+    pub fn add_null(&mut self) {
+        self.values.add_null();
+    }
+
+    /// Parses text like "-12.30" and adds it.
+    pub fn add_text(&mut self, text: &str) -> Result<(), &'static str> {
+        let value = self.parse(text)?;
+        self.values.add_record(value);
+        Ok(())
+    }
+
+    /// The unscaled value at `index`.
+    pub fn get(&self, index: usize) -> Option<Option<i128>> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(
+            self.values
+                .is_valid(index)
+                .then(|| self.values.data()[index]),
+        )
+    }
+
+    pub fn get_records(&self) -> FixedViewer<'_, i128> {
+        self.values.get_records()
+    }
+
+    pub fn slice(&self, offset: usize, length: usize) -> Decimal128Array {
+        Decimal128Array {
+            precision: self.precision,
+            scale: self.scale,
+            values: self.values.slice(offset, length),
+        }
+    }
+
+    pub fn append(&mut self, other: &Decimal128Array) {
+        self.values.append(&other.values);
+    }
+
+    // the integers' stats with the column's scale attached.
+    pub fn stats(&self) -> ColumnStats {
+        let stats = self.values.stats();
+        let scaled = |scalar: Option<&Scalar>| match scalar {
+            Some(&Scalar::Decimal128(value, _)) => Some(Scalar::Decimal128(value, self.scale)),
+            _ => None,
+        };
+        ColumnStats::new(
+            scaled(stats.min()),
+            scaled(stats.max()),
+            stats.null_count(),
+            stats.distinct_count(),
+            stats.byte_size(),
+        )
+    }
+
+    /// Parses "[+-]digits[.digits]" into an unscaled value. Missing fraction digits are zeros,
+    /// more of them than the scale is an error rather than a rounded value.
+    pub fn parse(&self, text: &str) -> Result<i128, &'static str> {
+        let (negative, digits) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !all_digits(whole) || !all_digits(fraction)
+        {
+            return Err("expected a decimal number");
+        }
+        if fraction.len() > self.scale as usize {
+            return Err("more fraction digits than the scale");
+        }
+        let whole = whole.trim_start_matches('0');
+        if whole.len() > (self.precision - self.scale) as usize {
+            return Err("value doesn't fit the precision");
+        }
+        // at most 38 digits, which can't overflow
+        let mut value: i128 = 0;
+        for byte in whole.bytes().chain(fraction.bytes()) {
+            value = value * 10 + (byte - b'0') as i128;
+        }
+        value *= 10i128.pow((self.scale as usize - fraction.len()) as u32);
+        Ok(if negative { -value } else { value })
+    }
+
+    /// The value at `index` with exactly `scale` fraction digits, `Some(None)` for nulls.
+    pub fn format(&self, index: usize) -> Option<Option<String>> {
+        Some(self.get(index)?.map(|value| self.format_value(value)))
+    }
+
+    pub fn format_value(&self, value: i128) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        let value = value.unsigned_abs();
+        if self.scale == 0 {
+            return format!("{sign}{value}");
+        }
+        let divisor = 10u128.pow(self.scale as u32);
+        format!(
+            "{sign}{}.{:0width$}",
+            value / divisor,
+            value % divisor,
+            width = self.scale as usize
+        )
+    }
+
+    /// Sum of the valid values in the column's scale, `None` when there are none. Fails instead of
+    /// wrapping when the sum has more than 38 digits.
+    pub fn sum(&self) -> Result<Option<i128>, &'static str> {
+        let mut sum: Option<i128> = None;
+        for value in self.values.get_records().flatten() {
+            let total = sum
+                .unwrap_or(0)
+                .checked_add(value)
+                .filter(|&total| fits(total, MAX_PRECISION))
+                .ok_or("decimal overflow")?;
+            sum = Some(total);
+        }
+        Ok(sum)
+    }
+
+    /// Exact `self + other`. The result keeps the larger scale and gets one more integer digit,
+    /// capped at 38 digits.
+    pub fn add(&self, other: &Decimal128Array) -> Result<Decimal128Array, &'static str> {
+        self.add_or_sub(other, i128::checked_add)
+    }
+
+    /// Exact `self - other`, typed like [`Decimal128Array::add`].
+    pub fn sub(&self, other: &Decimal128Array) -> Result<Decimal128Array, &'static str> {
+        self.add_or_sub(other, i128::checked_sub)
+    }
+
+    /// Exact `self * other`. The scales add up and so do the precisions, capped at 38 digits.
+    pub fn mul(&self, other: &Decimal128Array) -> Result<Decimal128Array, &'static str> {
+        let scale = self.scale + other.scale;
+        if scale > MAX_PRECISION {
+            return Err("result scale is too large");
+        }
+        let precision = (self.precision + other.precision + 1).min(MAX_PRECISION);
+        self.binary(other, precision, scale, |a, b| a.checked_mul(b))
+    }
+
+    fn add_or_sub(
+        &self,
+        other: &Decimal128Array,
+        op: fn(i128, i128) -> Option<i128>,
+    ) -> Result<Decimal128Array, &'static str> {
+        let scale = self.scale.max(other.scale);
+        let integer_digits = (self.precision - self.scale).max(other.precision - other.scale);
+        let precision = (integer_digits + scale + 1).min(MAX_PRECISION);
+        // both sides are brought to the common scale first
+        let (left, right) = (
+            10i128.pow((scale - self.scale) as u32),
+            10i128.pow((scale - other.scale) as u32),
+        );
+        self.binary(other, precision, scale, |a, b| {
+            op(a.checked_mul(left)?, b.checked_mul(right)?)
+        })
+    }
+
+    // applies `op` to every pair of values, a null on either side gives null.
+    fn binary(
+        &self,
+        other: &Decimal128Array,
+        precision: u8,
+        scale: u8,
+        op: impl Fn(i128, i128) -> Option<i128>,
+    ) -> Result<Decimal128Array, &'static str> {
+        assert_eq!(self.len(), other.len(), "arrays must have the same length");
+        let mut result = Decimal128Array::with_capacity(precision, scale, self.len());
+        for (a, b) in self.values.get_records().zip(other.values.get_records()) {
+            match (a, b) {
+                (Some(a), Some(b)) => {
+                    let value = op(a, b)
+                        .filter(|&value| fits(value, precision))
+                        .ok_or("decimal overflow")?;
+                    result.values.add_record(value);
+                }
+                _ => result.values.add_null(),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Converts to another precision and scale. A smaller scale rounds half away from zero, values
+    /// that don't fit the new precision are an error.
+    pub fn rescale(&self, precision: u8, scale: u8) -> Result<Decimal128Array, &'static str> {
+        let mut result = Decimal128Array::with_capacity(precision, scale, self.len());
+        for value in self.values.get_records() {
+            let Some(value) = value else {
+                result.values.add_null();
+                continue;
+            };
+            let value = if scale >= self.scale {
+                10i128
+                    .checked_pow((scale - self.scale) as u32)
+                    .and_then(|factor| value.checked_mul(factor))
+            } else {
+                let divisor = 10i128.pow((self.scale - scale) as u32);
+                let (quotient, remainder) = (value / divisor, value % divisor);
+                // half way or more rounds away from zero, doubling the remainder could overflow
+                // when the divisor is 10^38
+                Some(match remainder.abs() >= divisor - remainder.abs() {
+                    true => quotient + value.signum(),
+                    false => quotient,
+                })
+            };
+            let value = value
+                .filter(|&value| fits(value, precision))
+                .ok_or("value doesn't fit the precision")?;
+            result.values.add_record(value);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use super::Decimal128Array;
+    use crate::fixed::Fixed;
+    use crate::stats::Scalar;
+
+    fn array(precision: u8, scale: u8, values: &[Option<&str>]) -> Decimal128Array {
+        let parser = Decimal128Array::new(precision, scale);
+        let values = values
+            .iter()
+            .map(|v| v.map(|text| parser.parse(text).unwrap()));
+        Decimal128Array::from_parts(precision, scale, values.collect())
+    }
+
+    fn formatted(array: &Decimal128Array) -> Vec<Option<String>> {
+        (0..array.len()).map(|i| array.format(i).unwrap()).collect()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let col = array(
+            7,
+            2,
+            &[
+                Some("12.5"),
+                Some("-0.07"),
+                None,
+                Some("+00042"),
+                Some(".5"),
+            ],
+        );
+        assert_eq!(
+            col.get_records().collect::<Vec<_>>(),
+            vec![Some(1250), Some(-7), None, Some(4200), Some(50)]
+        );
+        assert_eq!(
+            formatted(&col),
+            vec![
+                Some("12.50".to_string()),
+                Some("-0.07".to_string()),
+                None,
+                Some("42.00".to_string()),
+                Some("0.50".to_string())
+            ]
+        );
+        for text in ["1.005", "123456.00", "", "-", "1e3", "1.2.3", "."] {
+            assert!(col.parse(text).is_err(), "{text}");
+        }
+        assert_eq!(col.parse("99999.99"), Ok(9_999_999));
+        assert_eq!(Decimal128Array::new(3, 0).format_value(-120), "-120");
+
+        let stats = col.stats();
+        assert_eq!(stats.min(), Some(&Scalar::Decimal128(-7, 2)));
+        assert_eq!(stats.max(), Some(&Scalar::Decimal128(4200, 2)));
+        assert_eq!(
+            Scalar::Decimal128(4200, 2).partial_cmp(&Scalar::Decimal128(42, 0)),
+            Some(Ordering::Equal)
+        );
+        assert!(Scalar::Decimal128(4200, 2) > Scalar::Decimal128(41999, 3));
+        assert!(Scalar::Decimal128(-7, 2) < Scalar::Int8(0));
+        assert!(Scalar::Decimal128(1250, 2) > Scalar::Float64(12.49));
+    }
+
+    #[test]
+    fn exact_arithmetic() {
+        // 0.1 + 0.2 is exactly 0.3 here
+        let a = array(5, 1, &[Some("0.1"), Some("10.5"), None]);
+        let b = array(6, 3, &[Some("0.2"), Some("-0.125"), Some("1")]);
+        let sum = a.add(&b).unwrap();
+        assert_eq!((sum.precision(), sum.scale()), (8, 3));
+        assert_eq!(
+            formatted(&sum),
+            vec![Some("0.300".to_string()), Some("10.375".to_string()), None]
+        );
+        let difference = a.sub(&b).unwrap();
+        assert_eq!(difference.format(1), Some(Some("10.625".to_string())));
+        let product = a.mul(&b).unwrap();
+        assert_eq!((product.precision(), product.scale()), (12, 4));
+        assert_eq!(product.format(1), Some(Some("-1.3125".to_string())));
+
+        let big = array(38, 0, &[Some(&"9".repeat(38))]);
+        assert_eq!(big.add(&big).err(), Some("decimal overflow"));
+        assert_eq!(big.mul(&big).err(), Some("decimal overflow"));
+    }
+
+    #[test]
+    fn sums() {
+        let col = array(10, 2, &[Some("19.99"), None, Some("0.01"), Some("-5")]);
+        assert_eq!(col.sum(), Ok(Some(1500)));
+        assert_eq!(array(10, 2, &[None]).sum(), Ok(None));
+
+        let mut big = Decimal128Array::new(38, 0);
+        big.add_text(&"9".repeat(38)).unwrap();
+        big.add_text("1").unwrap();
+        assert_eq!(big.sum(), Err("decimal overflow"));
+    }
+
+    #[test]
+    fn unchecked_parts_fail_instead_of_panicking() {
+        let min = Decimal128Array::from_parts(38, 0, Fixed::from_parts(vec![i128::MIN], None));
+        assert_eq!(min.sum(), Err("decimal overflow"));
+        assert!(min.add(&min).is_err());
+        assert!(min.rescale(38, 0).is_err());
+    }
+
+    #[test]
+    fn rescale() {
+        let col = array(6, 3, &[Some("1.005"), Some("-1.005"), Some("2.004"), None]);
+        let rounded = col.rescale(5, 2).unwrap();
+        assert_eq!(
+            formatted(&rounded),
+            vec![
+                Some("1.01".to_string()),
+                Some("-1.01".to_string()),
+                Some("2.00".to_string()),
+                None
+            ]
+        );
+        let widened = rounded.rescale(10, 6).unwrap();
+        assert_eq!(widened.get(0), Some(Some(1_010_000)));
+        assert!(col.rescale(3, 3).is_err());
+        assert!(col.rescale(4, 3).is_ok());
+
+        let fraction = array(38, 38, &[Some("0.9"), Some("-0.5"), Some("0.4")]);
+        let whole = fraction.rescale(1, 0).unwrap();
+        assert_eq!(
+            formatted(&whole),
+            vec![
+                Some("1".to_string()),
+                Some("-1".to_string()),
+                Some("0".to_string())
+            ]
+        );
+    }
+}
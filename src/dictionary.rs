@@ -0,0 +1,424 @@
+// dictionary encoding for text columns. Columns like country or status have a handful of distinct
+// values repeated over and over, and a plain `Variable<str>` stores every copy. Here each distinct
+// value is stored once in `values` and every row only keeps the index (code) of its value.
+//
+//   rows    ["open", "closed", null, "open"]
+//   values  ["open", "closed"]
+//   codes   [0, 1, null, 0]
+//
+// Besides being smaller, comparisons only have to look at each distinct value once; the result
+// per code is then looked up for every row.
+//
+// When a column has lots of distinct values the dictionary is as large as the data and the codes
+// are pure overhead, so once it grows past `max_size` values the column falls back to plain.
+
+use std::collections::HashMap;
+
+use crate::bitvec::BitVec;
+use crate::fixed::Fixed;
+use crate::predicate::CompareOp;
+use crate::stats::ColumnStats;
+use crate::variable::{StringArray, Variable};
+
+/// Number of distinct values a dictionary holds before falling back to plain.
+pub const DEFAULT_MAX_DICTIONARY_SIZE: usize = 16 * 1024;
+
+#[derive(Debug)]
+pub struct DictionaryArray {
+    state: State,
+    max_size: usize,
+}
+
+#[derive(Debug)]
+enum State {
+    Dictionary {
+        values: StringArray,
+        codes: Fixed<u32>,
+        lookup: HashMap<String, u32>,
+    },
+    Plain(StringArray),
+}
+
+impl Default for DictionaryArray {
+    fn default() -> Self {
+        DictionaryArray::with_capacity(0)
+    }
+}
+
+impl DictionaryArray {
+    pub fn new() -> DictionaryArray {
+        DictionaryArray::default()
+    }
+
+    // the number of distinct values is unknown, only the codes are preallocated.
+    pub fn with_capacity(capacity: usize) -> DictionaryArray {
+        DictionaryArray {
+            state: State::Dictionary {
+                values: Variable::new(),
+                codes: Fixed::with_capacity(capacity),
+                lookup: HashMap::new(),
+            },
+            max_size: DEFAULT_MAX_DICTIONARY_SIZE,
+        }
+    }
+
+    /// Sets how many distinct values the dictionary may hold before falling back to plain.
+    pub fn with_max_size(mut self, max_size: usize) -> DictionaryArray {
+        self.max_size = max_size;
+        if let State::Dictionary { values, .. } = &self.state
+            && values.len() > max_size
+        {
+            self.fall_back();
+        }
+        self
+    }
+
+    // builds a column from a dictionary and codes, e.g. when reading a file. Every valid code must
+    // point into `values`.
+    pub fn from_parts(values: StringArray, codes: Fixed<u32>) -> DictionaryArray {
+        assert_eq!(values.null_count(), 0, "dictionary values can't be null");
+        let mut lookup = HashMap::with_capacity(values.len());
+        for index in 0..values.len() {
+            let value = values.get(index).flatten().unwrap();
+            lookup.insert(value.to_string(), index as u32);
+        }
+        assert!(
+            codes
+                .get_records()
+                .flatten()
+                .all(|c| (c as usize) < values.len()),
+            "code out of bounds"
+        );
+        DictionaryArray {
+            state: State::Dictionary {
+                values,
+                codes,
+                lookup,
+            },
+            max_size: DEFAULT_MAX_DICTIONARY_SIZE,
+        }
+    }
+
+    /// A column that already fell back to plain.
+    pub fn from_plain(plain: StringArray) -> DictionaryArray {
+        DictionaryArray {
+            state: State::Plain(plain),
+            max_size: DEFAULT_MAX_DICTIONARY_SIZE,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.state {
+            State::Dictionary { codes, .. } => codes.len(),
+            State::Plain(plain) => plain.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// False once the column fell back to plain.
+    pub fn is_dictionary(&self) -> bool {
+        matches!(self.state, State::Dictionary { .. })
+    }
+
+    /// The distinct values, `None` when the column is plain.
+    pub fn values(&self) -> Option<&StringArray> {
+        match &self.state {
+            State::Dictionary { values, .. } => Some(values),
+            State::Plain(_) => None,
+        }
+    }
+
+    /// Index into `values` of every row, `None` when the column is plain.
+    pub fn codes(&self) -> Option<&Fixed<u32>> {
+        match &self.state {
+            State::Dictionary { codes, .. } => Some(codes),
+            State::Plain(_) => None,
+        }
+    }
+
+    /// The values of a column that fell back to plain.
+    pub fn plain(&self) -> Option<&StringArray> {
+        match &self.state {
+            State::Plain(plain) => Some(plain),
+            State::Dictionary { .. } => None,
+        }
+    }
+
+    pub fn add(&mut self, value: &str) {
+        if let State::Dictionary { values, lookup, .. } = &self.state
+            && !lookup.contains_key(value)
+            && values.len() >= self.max_size
+        {
+            self.fall_back();
+        }
+        match &mut self.state {
+            State::Dictionary {
+                values,
+                codes,
+                lookup,
+            } => {
+                let code = *lookup.entry(value.to_string()).or_insert_with(|| {
+                    values.add(value);
+                    (values.len() - 1) as u32
+                });
+                codes.add_record(code);
+            }
+            State::Plain(plain) => plain.add(value),
+        }
+    }
+
+    pub fn add_null(&mut self) {
+        match &mut self.state {
+            State::Dictionary { codes, .. } => codes.add_null(),
+            State::Plain(plain) => plain.add_null(),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<Option<&str>> {
+        match &self.state {
+            State::Dictionary { values, codes, .. } => {
+                let code = *codes.data().get(index)?;
+                if !codes.is_valid(index) {
+                    return Some(None);
+                }
+                values.get(code as usize)
+            }
+            State::Plain(plain) => plain.get(index),
+        }
+    }
+
+    // the values decoded into a plain column.
+    pub fn to_plain(&self) -> StringArray {
+        match &self.state {
+            State::Dictionary { .. } => {
+                let mut plain = Variable::with_capacity(self.len());
+                for index in 0..self.len() {
+                    match self.get(index).flatten() {
+                        Some(value) => plain.add(value),
+                        None => plain.add_null(),
+                    }
+                }
+                plain
+            }
+            State::Plain(plain) => plain.slice(0, plain.len()),
+        }
+    }
+
+    fn fall_back(&mut self) {
+        self.state = State::Plain(self.to_plain());
+    }
+
+    // copies `length` values starting at `offset` into a new column. The copy gets its own
+    // dictionary holding only the values it uses.
+    pub fn slice(&self, offset: usize, length: usize) -> DictionaryArray {
+        let mut sliced = DictionaryArray::with_capacity(length).with_max_size(self.max_size);
+        sliced.append_range(self, offset, length);
+        sliced
+    }
+
+    // adds every value of other to the end.
+    pub fn append(&mut self, other: &DictionaryArray) {
+        self.append_range(other, 0, other.len());
+    }
+
+    fn append_range(&mut self, other: &DictionaryArray, offset: usize, length: usize) {
+        assert!(offset + length <= other.len(), "range out of bounds");
+        for index in offset..offset + length {
+            match other.get(index).flatten() {
+                Some(value) => self.add(value),
+                None => self.add_null(),
+            }
+        }
+    }
+
+    // min, max and distinct count only depend on the dictionary, not on the rows.
+    pub fn stats(&self) -> ColumnStats {
+        match &self.state {
+            State::Dictionary { values, codes, .. } => {
+                let values = values.stats();
+                let byte_size = values.byte_size()
+                    + codes.len() * 4
+                    + codes.validity().map_or(0, |v| v.as_bytes().len());
+                ColumnStats::new(
+                    values.min().cloned(),
+                    values.max().cloned(),
+                    codes.null_count(),
+                    values.distinct_count(),
+                    byte_size,
+                )
+            }
+            State::Plain(plain) => plain.stats(),
+        }
+    }
+
+    /// Compares every row with `value`. Nulls never match.
+    pub fn compare(&self, op: CompareOp, value: &str) -> BitVec {
+        match &self.state {
+            State::Dictionary {
+                values,
+                codes,
+                lookup,
+            } => {
+                // equality only needs the code of the value, no string is compared at all
+                let matches: Vec<bool> = match op {
+                    CompareOp::Eq | CompareOp::NotEq => {
+                        let code = lookup.get(value).map(|&c| c as usize);
+                        (0..values.len())
+                            .map(|c| (Some(c) == code) == (op == CompareOp::Eq))
+                            .collect()
+                    }
+                    _ => (0..values.len())
+                        .map(|c| compare_str(values.get(c).flatten().unwrap(), op, value))
+                        .collect(),
+                };
+                let mut result = BitVec::with_capacity(codes.len());
+                for code in codes.get_records() {
+                    result.push(code.is_some_and(|c| matches[c as usize]));
+                }
+                result
+            }
+            State::Plain(plain) => {
+                let mut result = BitVec::with_capacity(plain.len());
+                for index in 0..plain.len() {
+                    let row = plain.get(index).flatten();
+                    result.push(row.is_some_and(|row| compare_str(row, op, value)));
+                }
+                result
+            }
+        }
+    }
+
+    pub fn eq(&self, value: &str) -> BitVec {
+        self.compare(CompareOp::Eq, value)
+    }
+
+    /// Rows equal to any of `values`. Nulls never match.
+    pub fn is_in(&self, values: &[&str]) -> BitVec {
+        match &self.state {
+            State::Dictionary {
+                values: dictionary,
+                codes,
+                lookup,
+            } => {
+                let mut matches = vec![false; dictionary.len()];
+                for value in values {
+                    if let Some(&code) = lookup.get(*value) {
+                        matches[code as usize] = true;
+                    }
+                }
+                let mut result = BitVec::with_capacity(codes.len());
+                for code in codes.get_records() {
+                    result.push(code.is_some_and(|c| matches[c as usize]));
+                }
+                result
+            }
+            State::Plain(plain) => {
+                let mut result = BitVec::with_capacity(plain.len());
+                for index in 0..plain.len() {
+                    let row = plain.get(index).flatten();
+                    result.push(row.is_some_and(|row| values.contains(&row)));
+                }
+                result
+            }
+        }
+    }
+}
+
+fn compare_str(row: &str, op: CompareOp, value: &str) -> bool {
+    match op {
+        CompareOp::Eq => row == value,
+        CompareOp::NotEq => row != value,
+        CompareOp::Lt => row < value,
+        CompareOp::LtEq => row <= value,
+        CompareOp::Gt => row > value,
+        CompareOp::GtEq => row >= value,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DictionaryArray;
+    use crate::predicate::CompareOp;
+    use crate::stats::Scalar;
+
+    fn statuses() -> DictionaryArray {
+        let mut col = DictionaryArray::new();
+        for status in ["open", "closed", "open", "pending", "open", "closed"] {
+            col.add(status);
+        }
+        col.add_null();
+        col
+    }
+
+    #[test]
+    fn stores_each_value_once() {
+        let col = statuses();
+        assert!(col.is_dictionary());
+        assert_eq!(col.len(), 7);
+        assert_eq!(col.values().unwrap().len(), 3);
+        assert_eq!(col.get(3), Some(Some("pending")));
+        assert_eq!(col.get(6), Some(None));
+        assert_eq!(col.get(7), None);
+        assert_eq!(
+            col.codes().unwrap().get_records().collect::<Vec<_>>(),
+            vec![Some(0), Some(1), Some(0), Some(2), Some(0), Some(1), None]
+        );
+
+        let stats = col.stats();
+        assert_eq!(stats.min(), Some(&Scalar::Text("closed".to_string())));
+        assert_eq!(stats.max(), Some(&Scalar::Text("pending".to_string())));
+        assert_eq!(stats.null_count(), 1);
+        assert_eq!(stats.distinct_count(), 3);
+    }
+
+    #[test]
+    fn falls_back_to_plain() {
+        let mut col = DictionaryArray::new().with_max_size(4);
+        for i in 0..4 {
+            col.add(&format!("user-{i}"));
+        }
+        col.add("user-0");
+        assert!(col.is_dictionary());
+
+        col.add("user-4");
+        assert!(!col.is_dictionary());
+        assert_eq!(col.len(), 6);
+        assert_eq!(col.get(4), Some(Some("user-0")));
+        assert_eq!(col.get(5), Some(Some("user-4")));
+        assert_eq!(col.plain().unwrap().len(), 6);
+
+        // a slice with few values is a dictionary again
+        let sliced = col.slice(0, 2);
+        assert!(sliced.is_dictionary());
+        assert_eq!(sliced.get(1), Some(Some("user-1")));
+    }
+
+    #[test]
+    fn compare_on_codes() {
+        let col = statuses();
+        let open: Vec<bool> = col.eq("open").iter().collect();
+        assert_eq!(open, vec![true, false, true, false, true, false, false]);
+        let not_open: Vec<bool> = col.compare(CompareOp::NotEq, "open").iter().collect();
+        assert_eq!(not_open, vec![false, true, false, true, false, true, false]);
+        let before: Vec<bool> = col.compare(CompareOp::Lt, "open").iter().collect();
+        assert_eq!(before, vec![false, true, false, false, false, true, false]);
+        assert_eq!(col.eq("missing").null_count(), 7);
+
+        let some: Vec<bool> = col.is_in(&["pending", "closed"]).iter().collect();
+        assert_eq!(some, vec![false, true, false, true, false, true, false]);
+
+        let plain = DictionaryArray::from_plain(col.to_plain());
+        assert_eq!(plain.eq("open"), col.eq("open"));
+        assert_eq!(
+            plain.compare(CompareOp::GtEq, "open"),
+            col.compare(CompareOp::GtEq, "open")
+        );
+    }
+}
@@ -0,0 +1,282 @@
+// an enum column holds values from a set that is known upfront, e.g. a status that is open, closed
+// or pending. The labels are part of the data type and every row only stores the position of its
+// label as a small integer code: a u8 for up to 256 labels, a u16 for more. Text that isn't one of
+// the labels is rejected when it is added, so a typo can't sneak in a fourth status.
+//
+// Storage and encodings are the ones of the codes. Reads and stats give the labels back.
+
+use std::collections::HashMap;
+
+use crate::bitvec::BitVec;
+use crate::database::DataType;
+use crate::fixed::{Fixed, Native};
+use crate::stats::{ColumnStats, Scalar};
+
+/// Integers an enum column stores its codes as.
+pub trait EnumCode: Native {
+    /// Most labels the codes can tell apart.
+    const MAX_LABELS: usize;
+
+    fn to_usize(self) -> usize;
+
+    /// Only called with codes below `MAX_LABELS`.
+    fn from_usize(code: usize) -> Self;
+}
+
+impl EnumCode for u8 {
+    const MAX_LABELS: usize = 1 << 8;
+
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+    fn from_usize(code: usize) -> u8 {
+        code as u8
+    }
+}
+
+impl EnumCode for u16 {
+    const MAX_LABELS: usize = 1 << 16;
+
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+    fn from_usize(code: usize) -> u16 {
+        code as u16
+    }
+}
+
+/// Codes of enums with up to 256 labels.
+pub type Enum8Array = EnumArray<u8>;
+/// Codes of enums with more than 256 labels.
+pub type Enum16Array = EnumArray<u16>;
+
+/// The width of the codes of an enum with `num_labels` labels, `None` when there are too many.
+pub fn code_width(num_labels: usize) -> Option<usize> {
+    match num_labels {
+        n if n <= u8::MAX_LABELS => Some(1),
+        n if n <= u16::MAX_LABELS => Some(2),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct EnumArray<T> {
+    labels: Vec<String>,
+    // the code of every label, so adding text doesn't search the labels
+    codes_by_label: HashMap<String, T>,
+    codes: Fixed<T>,
+}
+
+impl<T: EnumCode> EnumArray<T> {
+    /// Panics when the labels repeat or need codes of another width than `T`.
+    pub fn new(labels: Vec<String>) -> EnumArray<T> {
+        EnumArray::from_parts(labels, Fixed::new())
+    }
+
+    pub fn with_capacity(labels: Vec<String>, capacity: usize) -> EnumArray<T> {
+        EnumArray::from_parts(labels, Fixed::with_capacity(capacity))
+    }
+
+    // wraps existing codes, e.g. when reading a file. Panics on codes without a label too.
+    pub fn from_parts(labels: Vec<String>, codes: Fixed<T>) -> EnumArray<T> {
+        assert_eq!(
+            code_width(labels.len()),
+            Some(T::WIDTH),
+            "wrong code width for {} labels",
+            labels.len()
+        );
+        let codes_by_label: HashMap<String, T> = labels
+            .iter()
+            .enumerate()
+            .map(|(code, label)| (label.clone(), T::from_usize(code)))
+            .collect();
+        assert_eq!(codes_by_label.len(), labels.len(), "enum labels repeat");
+        assert!(
+            codes
+                .get_records()
+                .flatten()
+                .all(|c| c.to_usize() < labels.len()),
+            "enum code without a label"
+        );
+        EnumArray {
+            labels,
+            codes_by_label,
+            codes,
+        }
+    }
+
+    pub fn data_type(&self) -> DataType {
+        DataType::Enum(self.labels.clone())
+    }
+
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// The raw codes, code i stands for label i.
+    pub fn codes(&self) -> &Fixed<T> {
+        &self.codes
+    }
+
+    /// The code of `label`, `None` when it isn't one of the labels.
+    pub fn code(&self, label: &str) -> Option<T> {
+        self.codes_by_label.get(label).copied()
+    }
+
+    pub fn validity(&self) -> Option<&BitVec> {
+        self.codes.validity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.codes.is_valid(index)
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.codes.null_count()
+    }
+
+    /// Adds `label`, nothing is added when it isn't one of the labels.
+    pub fn add(&mut self, label: &str) -> Result<(), &'static str> {
+        let code = self.code(label).ok_or("unknown enum label")?;
+        self.codes.add_record(code);
+        Ok(())
+    }
+
+    /// Adds a raw code, nothing is added when it has no label.
+    pub fn add_code(&mut self, code: T) -> Result<(), &'static str> {
+        if code.to_usize() >= self.labels.len() {
+            return Err("enum code without a label");
+        }
+        self.codes.add_record(code);
+        Ok(())
+    }
+
+    pub fn add_null(&mut self) {
+        self.codes.add_null();
+    }
+
+    /// The label at `index`, `Some(None)` for nulls.
+    pub fn get(&self, index: usize) -> Option<Option<&str>> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(
+            self.codes
+                .is_valid(index)
+                .then(|| self.labels[self.codes.data()[index].to_usize()].as_str()),
+        )
+    }
+
+    /// The labels of every row in order.
+    pub fn get_records(&self) -> impl Iterator<Item = Option<&str>> + '_ {
+        self.codes
+            .get_records()
+            .map(|code| code.map(|code| self.labels[code.to_usize()].as_str()))
+    }
+
+    // copies `length` values starting at `offset` into a new column with the same labels.
+    pub fn slice(&self, offset: usize, length: usize) -> EnumArray<T> {
+        EnumArray {
+            labels: self.labels.clone(),
+            codes_by_label: self.codes_by_label.clone(),
+            codes: self.codes.slice(offset, length),
+        }
+    }
+
+    // adds every value of other to the end, both must have the same labels.
+    pub fn append(&mut self, other: &EnumArray<T>) {
+        assert_eq!(self.labels, other.labels, "enum labels missmatch");
+        self.codes.append(&other.codes);
+    }
+
+    // min and max are labels, so filters can compare them with text. Only labels that occur
+    // count, which makes the distinct count exact.
+    pub fn stats(&self) -> ColumnStats {
+        let mut seen = vec![false; self.labels.len()];
+        for code in self.codes.get_records().flatten() {
+            seen[code.to_usize()] = true;
+        }
+        let present = || {
+            self.labels
+                .iter()
+                .zip(&seen)
+                .filter(|&(_, &seen)| seen)
+                .map(|(label, _)| label)
+        };
+        let text = |label: &String| Scalar::Text(label.clone());
+        let codes = self.codes.stats();
+        ColumnStats::new(
+            present().min().map(text),
+            present().max().map(text),
+            codes.null_count(),
+            present().count() as u64,
+            codes.byte_size(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Enum8Array, Enum16Array};
+    use crate::stats::Scalar;
+
+    fn labels(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn labels_and_codes() {
+        let mut status = Enum8Array::new(labels(&["open", "closed", "pending"]));
+        status.add("pending").unwrap();
+        status.add_null();
+        status.add("open").unwrap();
+        assert_eq!(status.add("opened"), Err("unknown enum label"));
+        assert_eq!(status.add_code(3), Err("enum code without a label"));
+        status.add_code(2).unwrap();
+
+        assert_eq!(status.len(), 4);
+        assert_eq!(status.codes().data(), &[2, 0, 0, 2]);
+        assert_eq!(status.get(0), Some(Some("pending")));
+        assert_eq!(status.get(1), Some(None));
+        assert_eq!(status.get(4), None);
+        let sliced = status.slice(1, 3);
+        let read: Vec<_> = sliced.get_records().collect();
+        assert_eq!(read, vec![None, Some("open"), Some("pending")]);
+
+        let stats = status.stats();
+        assert_eq!(stats.min(), Some(&Scalar::Text("open".to_string())));
+        assert_eq!(stats.max(), Some(&Scalar::Text("pending".to_string())));
+        assert_eq!(stats.distinct_count(), 2);
+        assert_eq!(stats.null_count(), 1);
+    }
+
+    #[test]
+    fn wide_codes() {
+        let names: Vec<String> = (0..300).map(|i| format!("label {i}")).collect();
+        let mut column = Enum16Array::new(names);
+        column.add("label 299").unwrap();
+        assert_eq!(column.codes().data(), &[299]);
+    }
+
+    #[test]
+    #[should_panic(expected = "enum labels repeat")]
+    fn labels_are_distinct() {
+        Enum8Array::new(labels(&["a", "b", "a"]));
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong code width")]
+    fn codes_fit_the_labels() {
+        Enum16Array::new(labels(&["a", "b"]));
+    }
+}
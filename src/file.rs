@@ -0,0 +1,3877 @@
+// persisting a database. The layout borrows the idea of parquet: data first, metadata last. The
+// writer can then stream row groups to disk as they fill up and only needs to remember where it
+// put things, and the reader can read the footer and fetch only the row groups it cares about.
+//
+//   "CDF1"                                magic
+//   column chunk pages                    row group by row group, column by column
+//   footer                                schema, row group metadata and statistics
+//   footer checksum                       u32
+//   footer length                         u32
+//   "CDF1"                                magic
+//
+// A column chunk is split into pages of `page_size` rows. Page boundaries are the same for every
+// column of a row group, so page i of all columns holds the same rows and a page can be skipped for
+// the whole table when its statistics rule out a predicate. Every integer is little endian. A page
+// is stored as a list of buffers:
+//   fixed:      validity, data
+//   boolean:    validity, data (a bit per value)
+//   fixed size binary: validity, data (width bytes per value, zeros for nulls)
+//   variable:   validity, offsets (i32 each, i64 for large text and binary), data
+//   dictionary: validity, codes (u32 each), offsets (i32) and data of the distinct values
+//   list:       validity, offsets (i32 each), then the buffers of the values as a plain page
+//   struct:     validity, number of buffers of every field (u8 each), then the buffers of every
+//               field as a plain page
+//   map:        validity, offsets (i32 each), number of buffers of the keys and of the values
+//               (u8 each), then the keys and the values as plain pages
+//   run length: validity of each run, length of each run (u32 each), value of each run
+//   delta:      validity, deltas bit packed in blocks of 128 (see bitpack.rs)
+//   gorilla:    validity, xor compressed floats (see gorilla.rs)
+// Validity buffers that are mostly ones are stored as runs instead of a bitmap, and a page
+// without nulls has no validity buffer at all.
+// Every page records its encoding, a dictionary column that fell back to plain writes plain
+// pages. Dates, times, timestamps and durations are written as their int32 or int64 values, the
+// schema keeps their unit and timezone. Decimals are written as their unscaled i128 values and
+// enums as their uint8 or uint16 codes, the schema keeps the labels.
+// Chunks and pages of a struct column also keep the statistics of every field below the struct,
+// so a filter on "address.city" can skip pages like one on a top level column.
+//
+// Unless `WriteOptions` forces one, the writer picks an encoding per column chunk: it encodes a
+// sample of the chunk with every encoding the type supports, scales the sizes up to the whole
+// chunk and takes the smallest, unless that is slower to decode and not enough smaller to be worth
+// it (see `EncodingPreference`). The choice is kept in the chunk's metadata, and
+// `FileWriter::encoding_report` tells what every encoding was estimated at.
+//
+// Columns picked in `WriteOptions` also get a bloom filter per chunk, written right after the
+// chunk's pages.
+//
+// Every buffer can be compressed with a codec picked per column (see codec.rs), the codec id is
+// kept in the footer next to the buffer.
+//
+// Files travel through disks, networks and object stores, so every buffer and the footer carry a
+// crc32c checksum that is checked whenever they are read.
+//
+// Checksums don't help against a crafted file, which can claim any length or count. So sizes
+// read from a file are checked against the bytes they describe before anything is allocated for
+// them: a buffer has to end before the footer, and decoders reject counts their input is too
+// short for.
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::bitpack::{self, DeltaInt};
+use crate::bitvec::BitVec;
+use crate::bloom::{self, BloomFilter};
+use crate::boolean::BooleanArray;
+use crate::codec::{self, Codec, Uncompressed};
+use crate::database::{Column, ColumnType, DataType, Database, Field, RowGroup};
+use crate::decimal::{Decimal128Array, MAX_PRECISION};
+use crate::dictionary::DictionaryArray;
+use crate::enum_array::{EnumArray, EnumCode, code_width};
+use crate::fixed::{Fixed, Native};
+use crate::fixed_binary::FixedSizeBinaryArray;
+use crate::gorilla;
+use crate::list::List;
+use crate::map::Map;
+use crate::predicate::Predicate;
+use crate::rle::{RunLengthArray, RunLengthBitVec};
+use crate::stats::{ColumnStats, Scalar};
+use crate::struct_array::StructArray;
+use crate::temporal::{TemporalArray, TimeUnit, parse_offset};
+use crate::util::crc32c;
+use crate::variable::{FromByteRef, Offset, StringArray, ToBytesRef, Variable};
+
+const MAGIC: &[u8; 4] = b"CDF1";
+const VERSION: u8 = 1;
+// how deep list types may nest in a schema
+const MAX_NESTING: usize = 32;
+
+/// Default number of rows in a page.
+pub const DEFAULT_PAGE_SIZE: usize = 8 * 1024;
+
+/// Knobs of the file writer.
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    page_size: usize,
+    bloom_filter_columns: Vec<String>,
+    bloom_filter_fpp: f64,
+    column_encodings: Vec<(String, Encoding)>,
+    column_codecs: Vec<(String, &'static dyn Codec)>,
+    encoding_preference: EncodingPreference,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            page_size: DEFAULT_PAGE_SIZE,
+            bloom_filter_columns: vec![],
+            bloom_filter_fpp: bloom::DEFAULT_FPP,
+            column_encodings: vec![],
+            column_codecs: vec![],
+            encoding_preference: EncodingPreference::default(),
+        }
+    }
+}
+
+impl WriteOptions {
+    pub fn new() -> WriteOptions {
+        WriteOptions::default()
+    }
+
+    /// Rows per page, smaller pages can be skipped more precisely but need more metadata.
+    pub fn with_page_size(mut self, page_size: usize) -> WriteOptions {
+        assert!(page_size > 0, "page size must be positive");
+        self.page_size = page_size;
+        self
+    }
+
+    /// Builds a bloom filter for every chunk of the column, worth it for high cardinality columns
+    /// that are looked up by equality.
+    pub fn with_bloom_filter(mut self, column: &str) -> WriteOptions {
+        self.bloom_filter_columns.push(column.to_string());
+        self
+    }
+
+    /// False positive probability of the bloom filters, lower means bigger filters.
+    pub fn with_bloom_filter_fpp(mut self, fpp: f64) -> WriteOptions {
+        assert!(fpp > 0.0 && fpp < 1.0, "fpp must be between 0 and 1");
+        self.bloom_filter_fpp = fpp;
+        self
+    }
+
+    /// Writes every page of the column with `encoding` instead of picking one. Writing fails when
+    /// the column's type doesn't support the encoding.
+    pub fn with_encoding(mut self, column: &str, encoding: Encoding) -> WriteOptions {
+        self.column_encodings.retain(|(c, _)| c != column);
+        self.column_encodings.push((column.to_string(), encoding));
+        self
+    }
+
+    /// Compresses the pages of the column with `codec`, e.g. `&Lz`. Columns are uncompressed
+    /// unless picked here. Panics when a reader couldn't find the codec by its id, codecs of other
+    /// crates have to be registered first, see `codec::register`.
+    pub fn with_codec(mut self, column: &str, codec: &'static dyn Codec) -> WriteOptions {
+        assert!(
+            codec::by_id(codec.id()).is_some_and(|known| known.name() == codec.name()),
+            "codec {} isn't registered",
+            codec.name()
+        );
+        self.column_codecs.retain(|(c, _)| c != column);
+        self.column_codecs.push((column.to_string(), codec));
+        self
+    }
+
+    /// How picking encodings weighs size against decode speed, balanced by default.
+    pub fn with_encoding_preference(mut self, preference: EncodingPreference) -> WriteOptions {
+        self.encoding_preference = preference;
+        self
+    }
+
+    fn codec(&self, column: &str) -> &'static dyn Codec {
+        self.column_codecs
+            .iter()
+            .find(|(c, _)| c == column)
+            .map_or(&Uncompressed, |&(_, codec)| codec)
+    }
+
+    fn encoding(&self, column: &str) -> Option<Encoding> {
+        self.column_encodings
+            .iter()
+            .find(|(c, _)| c == column)
+            .map(|&(_, encoding)| encoding)
+    }
+}
+
+/// How much smaller an encoding that is slower to decode has to be before the writer picks it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingPreference {
+    /// The smallest estimate always wins.
+    Smallest,
+    /// Every step slower to decode has to save 10%.
+    #[default]
+    Balanced,
+    /// Every step slower to decode has to save 50%.
+    FastDecode,
+}
+
+impl EncodingPreference {
+    // share of the size a slower encoding has to save per step of decode cost.
+    fn min_saving(&self) -> f64 {
+        match self {
+            EncodingPreference::Smallest => 0.0,
+            EncodingPreference::Balanced => 0.1,
+            EncodingPreference::FastDecode => 0.5,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FileError {
+    Io(io::Error),
+    /// The bytes don't follow the file format.
+    Malformed(String),
+    /// The data doesn't fit the schema of the file.
+    Schema(String),
+    /// A checksum didn't match, the bytes were damaged after they were written.
+    Corrupted(Corruption),
+}
+
+impl Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileError::Io(e) => write!(f, "io error: {e}"),
+            FileError::Malformed(msg) => write!(f, "malformed file: {msg}"),
+            FileError::Schema(msg) => write!(f, "schema error: {msg}"),
+            FileError::Corrupted(corruption) => write!(f, "{corruption}"),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+impl From<io::Error> for FileError {
+    fn from(value: io::Error) -> Self {
+        FileError::Io(value)
+    }
+}
+
+fn malformed<T>(msg: impl Into<String>) -> Result<T, FileError> {
+    Err(FileError::Malformed(msg.into()))
+}
+
+/// What a buffer holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferKind {
+    Validity,
+    Offsets,
+    Data,
+    BloomFilter,
+    /// Per row index into a dictionary.
+    Codes,
+    /// Validity stored as runs of ones and zeros.
+    ValidityRuns,
+    /// Length of every run of a run length encoded page.
+    RunLengths,
+    /// Number of buffers of every field of a struct page.
+    ChildBuffers,
+}
+
+impl BufferKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BufferKind::Validity => "validity",
+            BufferKind::Offsets => "offsets",
+            BufferKind::Data => "data",
+            BufferKind::BloomFilter => "bloom filter",
+            BufferKind::Codes => "codes",
+            BufferKind::ValidityRuns => "validity runs",
+            BufferKind::RunLengths => "run lengths",
+            BufferKind::ChildBuffers => "child buffers",
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            BufferKind::Validity => 0,
+            BufferKind::Offsets => 1,
+            BufferKind::Data => 2,
+            BufferKind::BloomFilter => 3,
+            BufferKind::Codes => 4,
+            BufferKind::ValidityRuns => 5,
+            BufferKind::RunLengths => 6,
+            BufferKind::ChildBuffers => 7,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<BufferKind, FileError> {
+        Ok(match tag {
+            0 => BufferKind::Validity,
+            1 => BufferKind::Offsets,
+            2 => BufferKind::Data,
+            3 => BufferKind::BloomFilter,
+            4 => BufferKind::Codes,
+            5 => BufferKind::ValidityRuns,
+            6 => BufferKind::RunLengths,
+            7 => BufferKind::ChildBuffers,
+            _ => return malformed(format!("unknown buffer kind {tag}")),
+        })
+    }
+}
+
+/// How the values of a page are laid out in its buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Values one after another: validity, (offsets,) data.
+    Plain,
+    /// Validity, codes, then the offsets and data of the distinct values.
+    Dictionary,
+    /// Validity, length and value of every run.
+    RunLength,
+    /// Validity and bit packed deltas, integers only.
+    Delta,
+    /// Validity and xor compressed values (see gorilla.rs), floats only.
+    Gorilla,
+}
+
+const ENCODINGS: [Encoding; 5] = [
+    Encoding::Plain,
+    Encoding::Dictionary,
+    Encoding::RunLength,
+    Encoding::Delta,
+    Encoding::Gorilla,
+];
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Plain => "plain",
+            Encoding::Dictionary => "dictionary",
+            Encoding::RunLength => "run length",
+            Encoding::Delta => "delta",
+            Encoding::Gorilla => "gorilla",
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            Encoding::Plain => 0,
+            Encoding::Dictionary => 1,
+            Encoding::RunLength => 2,
+            Encoding::Delta => 3,
+            Encoding::Gorilla => 4,
+        }
+    }
+
+    /// Relative cost of decoding, plain copies bytes and gorilla reads bit by bit.
+    pub fn decode_cost(&self) -> u8 {
+        match self {
+            Encoding::Plain => 0,
+            Encoding::Dictionary | Encoding::RunLength => 1,
+            Encoding::Delta => 2,
+            Encoding::Gorilla => 3,
+        }
+    }
+
+    /// Whether columns of `data_type` can be written with this encoding.
+    pub fn supports(&self, data_type: &DataType) -> bool {
+        // temporal columns are encoded as their integers
+        let data_type = &data_type.physical();
+        let variable = matches!(
+            data_type,
+            DataType::Text
+                | DataType::Binary
+                | DataType::LargeText
+                | DataType::LargeBinary
+                | DataType::Dictionary
+        );
+        let float = matches!(data_type, DataType::Float32 | DataType::Float64);
+        let boolean = *data_type == DataType::Boolean;
+        // deltas are 64-bit
+        let wide = matches!(data_type, DataType::Decimal128(..));
+        let fixed_binary = matches!(data_type, DataType::FixedSizeBinary(_));
+        // the values of lists and maps and the fields of structs are always written plain
+        let nested = matches!(
+            data_type,
+            DataType::List(_) | DataType::Struct(_) | DataType::Map(_)
+        );
+        match self {
+            Encoding::Plain => true,
+            Encoding::Dictionary => matches!(data_type, DataType::Text | DataType::Dictionary),
+            Encoding::RunLength => !variable && !boolean && !fixed_binary && !nested,
+            Encoding::Delta => !variable && !float && !boolean && !wide && !fixed_binary && !nested,
+            Encoding::Gorilla => float,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<Encoding, FileError> {
+        Ok(match tag {
+            0 => Encoding::Plain,
+            1 => Encoding::Dictionary,
+            2 => Encoding::RunLength,
+            3 => Encoding::Delta,
+            4 => Encoding::Gorilla,
+            _ => return malformed(format!("unknown encoding {tag}")),
+        })
+    }
+}
+
+/// Where a buffer lives in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferMeta {
+    kind: BufferKind,
+    offset: u64,
+    length: u64,
+    checksum: u32,
+    codec: u8,
+    uncompressed_length: u64,
+}
+
+impl BufferMeta {
+    pub fn kind(&self) -> BufferKind {
+        self.kind
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// crc32c of the bytes as stored, i.e. after compression.
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+
+    /// Id of the codec that compressed the buffer.
+    pub fn codec(&self) -> u8 {
+        self.codec
+    }
+
+    pub fn uncompressed_length(&self) -> u64 {
+        self.uncompressed_length
+    }
+}
+
+/// A damaged region of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corruption {
+    /// Column the buffer belongs to, `None` for the footer.
+    pub column: Option<String>,
+    pub row_group: Option<usize>,
+    pub page: Option<usize>,
+    /// Name of the buffer, e.g. "data", "validity" or "footer".
+    pub buffer: &'static str,
+    pub offset: u64,
+    pub length: u64,
+    pub expected_checksum: u32,
+    pub actual_checksum: u32,
+}
+
+impl Display for Corruption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "corrupted {} buffer", self.buffer)?;
+        if let Some(column) = &self.column {
+            write!(f, " of column {column}")?;
+        }
+        if let Some(row_group) = self.row_group {
+            write!(f, " in row group {row_group}")?;
+        }
+        if let Some(page) = self.page {
+            write!(f, " page {page}")?;
+        }
+        write!(
+            f,
+            " at bytes {}..{} (checksum {:08x}, expected {:08x})",
+            self.offset,
+            self.offset + self.length,
+            self.actual_checksum,
+            self.expected_checksum
+        )
+    }
+}
+
+// which buffer is being read, only used to describe corruption.
+#[derive(Clone, Copy)]
+struct BufferLocation<'a> {
+    column: &'a str,
+    row_group: usize,
+    page: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageMeta {
+    encoding: Encoding,
+    buffers: Vec<BufferMeta>,
+    stats: ColumnStats,
+    field_stats: Vec<ColumnStats>,
+}
+
+impl PageMeta {
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn buffers(&self) -> &[BufferMeta] {
+        &self.buffers
+    }
+
+    pub fn stats(&self) -> &ColumnStats {
+        &self.stats
+    }
+
+    /// Statistics of every field below a struct column, see `field_stats`.
+    pub fn field_stats(&self) -> &[ColumnStats] {
+        &self.field_stats
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnChunkMeta {
+    encoding: Encoding,
+    pages: Vec<PageMeta>,
+    stats: ColumnStats,
+    field_stats: Vec<ColumnStats>,
+    bloom_filter: Option<BufferMeta>,
+}
+
+impl ColumnChunkMeta {
+    /// Encoding picked for the chunk. A page may still differ, e.g. a dictionary that fell back
+    /// to plain.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn pages(&self) -> &[PageMeta] {
+        &self.pages
+    }
+
+    pub fn bloom_filter(&self) -> Option<&BufferMeta> {
+        self.bloom_filter.as_ref()
+    }
+
+    pub fn stats(&self) -> &ColumnStats {
+        &self.stats
+    }
+
+    /// Statistics of every field below a struct column, depth first: a struct field comes right
+    /// before its own fields. Empty for other columns.
+    pub fn field_stats(&self) -> &[ColumnStats] {
+        &self.field_stats
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowGroupMeta {
+    num_rows: usize,
+    columns: Vec<ColumnChunkMeta>,
+}
+
+impl RowGroupMeta {
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Chunks in the same order as the schema.
+    pub fn columns(&self) -> &[ColumnChunkMeta] {
+        &self.columns
+    }
+
+    pub fn num_pages(&self, page_size: usize) -> usize {
+        self.num_rows.div_ceil(page_size)
+    }
+}
+
+/// Everything stored in the footer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMetadata {
+    schema: Vec<Field>,
+    row_group_size: usize,
+    page_size: usize,
+    row_groups: Vec<RowGroupMeta>,
+}
+
+impl FileMetadata {
+    pub fn schema(&self) -> &[Field] {
+        &self.schema
+    }
+
+    pub fn row_group_size(&self) -> usize {
+        self.row_group_size
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    // rows in page `page` of a row group with `num_rows` rows.
+    fn page_rows(&self, num_rows: usize, page: usize) -> usize {
+        self.page_size.min(num_rows - page * self.page_size)
+    }
+
+    pub fn row_groups(&self) -> &[RowGroupMeta] {
+        &self.row_groups
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.row_groups.iter().map(|rg| rg.num_rows).sum()
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.schema.iter().position(|f| f.name() == name)
+    }
+
+    // the column a path like "address.city" points into and, for a field below a struct column,
+    // its position in the chunk's `field_stats`. A name with a dot is looked up whole first.
+    fn resolve_path(&self, path: &str) -> Option<(usize, Option<usize>)> {
+        if let Some(column) = self.column_index(path) {
+            return Some((column, None));
+        }
+        let (name, mut rest) = path.split_once('.')?;
+        let column = self.column_index(name)?;
+        let mut data_type = self.schema[column].data_type();
+        let mut position = 0;
+        loop {
+            let DataType::Struct(fields) = data_type else {
+                return None;
+            };
+            let (name, next) = match rest.split_once('.') {
+                Some((name, next)) => (name, Some(next)),
+                None => (rest, None),
+            };
+            let index = fields.iter().position(|f| f.name() == name)?;
+            position += fields[..index]
+                .iter()
+                .map(|f| 1 + nested_field_count(f.data_type()))
+                .sum::<usize>();
+            let Some(next) = next else {
+                return Some((column, Some(position)));
+            };
+            // skip the struct field itself
+            position += 1;
+            rest = next;
+            data_type = fields[index].data_type();
+        }
+    }
+}
+
+// statistics of every field below a struct column, depth first like `field_stats`.
+fn field_stats(column: &ColumnType) -> Vec<ColumnStats> {
+    let mut stats = vec![];
+    if let ColumnType::Struct(c) = column {
+        for field in c.fields() {
+            stats.push(field.stats());
+            stats.extend(field_stats(field.inner()));
+        }
+    }
+    stats
+}
+
+// number of fields below a struct type, counted like `field_stats`.
+fn nested_field_count(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::Struct(fields) => fields
+            .iter()
+            .map(|f| 1 + nested_field_count(f.data_type()))
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Writes row groups one by one, the footer is written by [`FileWriter::finish`].
+pub struct FileWriter<W: Write> {
+    inner: W,
+    position: u64,
+    schema: Vec<Field>,
+    row_group_size: usize,
+    options: WriteOptions,
+    row_groups: Vec<RowGroupMeta>,
+    encoding_report: Vec<EncodingChoice>,
+}
+
+impl<W: Write> FileWriter<W> {
+    pub fn new(
+        inner: W,
+        schema: Vec<Field>,
+        row_group_size: usize,
+    ) -> Result<FileWriter<W>, FileError> {
+        FileWriter::with_options(inner, schema, row_group_size, WriteOptions::default())
+    }
+
+    pub fn with_options(
+        mut inner: W,
+        schema: Vec<Field>,
+        row_group_size: usize,
+        options: WriteOptions,
+    ) -> Result<FileWriter<W>, FileError> {
+        inner.write_all(MAGIC)?;
+        Ok(FileWriter {
+            inner,
+            position: MAGIC.len() as u64,
+            schema,
+            row_group_size,
+            options,
+            row_groups: vec![],
+            encoding_report: vec![],
+        })
+    }
+
+    pub fn write_row_group(&mut self, row_group: &RowGroup) -> Result<(), FileError> {
+        if row_group.columns().len() != self.schema.len() {
+            return Err(FileError::Schema(format!(
+                "row group has {} columns, schema has {}",
+                row_group.columns().len(),
+                self.schema.len()
+            )));
+        }
+
+        for (field, column) in self.schema.iter().zip(row_group.columns()) {
+            if field.name() != column.name() || *field.data_type() != column.inner().data_type() {
+                return Err(FileError::Schema(format!(
+                    "column {} doesn't match schema field {}:{}",
+                    column.describe(),
+                    field.name(),
+                    field.data_type().name()
+                )));
+            }
+            if let Some(encoding) = self.options.encoding(field.name())
+                && !encoding.supports(field.data_type())
+            {
+                return Err(FileError::Schema(format!(
+                    "column {} can't be {} encoded",
+                    column.describe(),
+                    encoding.name()
+                )));
+            }
+        }
+
+        let num_rows = row_group.num_rows();
+        let page_size = self.options.page_size;
+        let mut columns = Vec::with_capacity(self.schema.len());
+        for column in row_group.columns() {
+            if column.inner().len() != num_rows {
+                return Err(FileError::Schema(format!(
+                    "column {} has {} rows, row group has {num_rows}",
+                    column.name(),
+                    column.inner().len()
+                )));
+            }
+            let choice = match self.options.encoding(column.name()) {
+                Some(encoding) => EncodingChoice {
+                    column: column.name().to_string(),
+                    row_group: self.row_groups.len(),
+                    encoding,
+                    estimates: vec![],
+                    forced: true,
+                },
+                None => choose_encoding(
+                    column.name(),
+                    self.row_groups.len(),
+                    column.inner(),
+                    self.options.encoding_preference,
+                ),
+            };
+            let mut pages = Vec::with_capacity(num_rows.div_ceil(page_size));
+            for start in (0..num_rows).step_by(page_size) {
+                let page = column.inner().slice(start, page_size.min(num_rows - start));
+                let (encoding, encoded) = encode_as(&page, choice.encoding);
+                // the footer counts buffers with a u8
+                if encoded.len() > u8::MAX as usize {
+                    return Err(FileError::Schema(format!(
+                        "column {} needs more than 255 buffers per page",
+                        column.describe()
+                    )));
+                }
+                let codec = self.options.codec(column.name());
+                let mut buffers = vec![];
+                for (kind, buffer) in encoded {
+                    buffers.push(self.write_buffer(kind, &buffer, codec)?);
+                }
+                pages.push(PageMeta {
+                    encoding,
+                    buffers,
+                    stats: page.stats(),
+                    field_stats: field_stats(&page),
+                });
+            }
+            let stats = column.stats();
+            let bloom_filter = if self
+                .options
+                .bloom_filter_columns
+                .iter()
+                .any(|c| c == column.name())
+            {
+                let filter = BloomFilter::from_column(
+                    column.inner(),
+                    stats.distinct_count(),
+                    self.options.bloom_filter_fpp,
+                );
+                let bytes = filter.to_bytes();
+                Some(self.write_buffer(BufferKind::BloomFilter, &bytes, &Uncompressed)?)
+            } else {
+                None
+            };
+            columns.push(ColumnChunkMeta {
+                encoding: choice.encoding,
+                pages,
+                stats,
+                field_stats: field_stats(column.inner()),
+                bloom_filter,
+            });
+            self.encoding_report.push(choice);
+        }
+        self.row_groups.push(RowGroupMeta {
+            num_rows: row_group.num_rows(),
+            columns,
+        });
+        Ok(())
+    }
+
+    // buffers that don't get smaller are stored uncompressed, decompressing them would be wasted
+    // work.
+    fn write_buffer(
+        &mut self,
+        kind: BufferKind,
+        buffer: &[u8],
+        codec: &dyn Codec,
+    ) -> Result<BufferMeta, FileError> {
+        let mut codec_id = codec::UNCOMPRESSED_ID;
+        let mut compressed = None;
+        if codec.id() != codec::UNCOMPRESSED_ID {
+            let bytes = codec.compress(buffer);
+            if bytes.len() < buffer.len() {
+                codec_id = codec.id();
+                compressed = Some(bytes);
+            }
+        }
+        let stored = compressed.as_deref().unwrap_or(buffer);
+        self.inner.write_all(stored)?;
+        let meta = BufferMeta {
+            kind,
+            offset: self.position,
+            length: stored.len() as u64,
+            checksum: crc32c(stored),
+            codec: codec_id,
+            uncompressed_length: buffer.len() as u64,
+        };
+        self.position += stored.len() as u64;
+        Ok(meta)
+    }
+
+    /// The encoding picked for every column chunk written so far and why.
+    pub fn encoding_report(&self) -> &[EncodingChoice] {
+        &self.encoding_report
+    }
+
+    /// Writes the footer and hands back the inner writer.
+    pub fn finish(mut self) -> Result<W, FileError> {
+        let metadata = FileMetadata {
+            schema: self.schema,
+            row_group_size: self.row_group_size,
+            page_size: self.options.page_size,
+            row_groups: self.row_groups,
+        };
+        let footer = encode_footer(&metadata);
+        let length = u32::try_from(footer.len())
+            .map_err(|_| FileError::Schema("footer is larger than 4 GiB".to_string()))?;
+        self.inner.write_all(&footer)?;
+        self.inner.write_all(&crc32c(&footer).to_le_bytes())?;
+        self.inner.write_all(&length.to_le_bytes())?;
+        self.inner.write_all(MAGIC)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Reads the footer upfront and then column chunks on demand.
+pub struct FileReader<R: Read + Seek> {
+    inner: R,
+    metadata: FileMetadata,
+    // where the footer starts, every buffer has to end before it
+    data_end: u64,
+}
+
+impl FileReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FileError> {
+        FileReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> FileReader<R> {
+    pub fn new(mut inner: R) -> Result<FileReader<R>, FileError> {
+        let file_len = inner.seek(SeekFrom::End(0))?;
+        let tail_len = (MAGIC.len() + 8) as u64;
+        if file_len < MAGIC.len() as u64 + tail_len {
+            return malformed("file is too small");
+        }
+
+        let mut head = [0u8; 4];
+        inner.seek(SeekFrom::Start(0))?;
+        inner.read_exact(&mut head)?;
+        let mut tail = [0u8; 12];
+        inner.seek(SeekFrom::End(-(tail_len as i64)))?;
+        inner.read_exact(&mut tail)?;
+        if &head != MAGIC || &tail[8..] != MAGIC {
+            return malformed("missing magic bytes");
+        }
+
+        let checksum = u32::from_le_bytes(tail[..4].try_into().unwrap());
+        let footer_len = u32::from_le_bytes(tail[4..8].try_into().unwrap()) as u64;
+        if footer_len > file_len - tail_len - MAGIC.len() as u64 {
+            return malformed("footer length is larger than the file");
+        }
+        let footer_offset = file_len - tail_len - footer_len;
+        let mut footer = vec![0; footer_len as usize];
+        inner.seek(SeekFrom::Start(footer_offset))?;
+        inner.read_exact(&mut footer)?;
+        let actual = crc32c(&footer);
+        if actual != checksum {
+            return Err(FileError::Corrupted(Corruption {
+                column: None,
+                row_group: None,
+                page: None,
+                buffer: "footer",
+                offset: footer_offset,
+                length: footer_len,
+                expected_checksum: checksum,
+                actual_checksum: actual,
+            }));
+        }
+        let metadata = decode_footer(&footer)?;
+
+        Ok(FileReader {
+            inner,
+            metadata,
+            data_end: footer_offset,
+        })
+    }
+
+    pub fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    /// Reads a single row group, only its buffers are loaded into memory.
+    pub fn read_row_group(&mut self, index: usize) -> Result<RowGroup, FileError> {
+        let Some(meta) = self.metadata.row_groups.get(index) else {
+            return Err(FileError::Schema(format!("no row group {index}")));
+        };
+        let meta = meta.clone();
+        let mut columns = Vec::with_capacity(meta.columns.len());
+        for (column, field) in self.metadata.schema.clone().iter().enumerate() {
+            let chunk = self.read_column_chunk(index, column)?;
+            columns.push(Column::new(field.name().to_string(), chunk));
+        }
+        Ok(RowGroup::new(columns, meta.num_rows))
+    }
+
+    /// Reads a single column of a single row group.
+    pub fn read_column_chunk(
+        &mut self,
+        row_group: usize,
+        column: usize,
+    ) -> Result<ColumnType, FileError> {
+        let num_pages = match self.metadata.row_groups.get(row_group) {
+            Some(rg) => rg.num_pages(self.metadata.page_size),
+            None => return Err(FileError::Schema(format!("no row group {row_group}"))),
+        };
+        let pages: Vec<usize> = (0..num_pages).collect();
+        self.read_pages(row_group, column, &pages)
+    }
+
+    /// Reads the given pages of a column chunk and puts them one after another.
+    pub fn read_pages(
+        &mut self,
+        row_group: usize,
+        column: usize,
+        pages: &[usize],
+    ) -> Result<ColumnType, FileError> {
+        let Some(rg) = self.metadata.row_groups.get(row_group) else {
+            return Err(FileError::Schema(format!("no row group {row_group}")));
+        };
+        let Some(chunk) = rg.columns.get(column) else {
+            return Err(FileError::Schema(format!("no column {column}")));
+        };
+        let num_rows = rg.num_rows;
+        let field = self.metadata.schema[column].clone();
+        let data_type = field.data_type().clone();
+        let chunk = chunk.clone();
+
+        // grows with the pages actually decoded, the footer's sizes aren't trusted for this
+        let mut result = ColumnType::empty(&data_type, 0);
+        for &page in pages {
+            let Some(meta) = chunk.pages.get(page) else {
+                return Err(FileError::Schema(format!("no page {page}")));
+            };
+            let location = BufferLocation {
+                column: field.name(),
+                row_group,
+                page: Some(page),
+            };
+            let mut buffers = Vec::with_capacity(meta.buffers.len());
+            for &buffer in &meta.buffers {
+                let bytes = self.read_buffer(buffer, location)?;
+                buffers.push((buffer.kind, decompress(buffer, bytes)?));
+            }
+            let page_rows = self.metadata.page_rows(num_rows, page);
+            let decoded = decode_chunk(&data_type, meta.encoding, page_rows, buffers)?;
+            result
+                .append(&decoded)
+                .expect("decoded page has the column type");
+        }
+        Ok(result)
+    }
+
+    /// Reads the bloom filter of a column chunk, if it was written with one.
+    pub fn read_bloom_filter(
+        &mut self,
+        row_group: usize,
+        column: usize,
+    ) -> Result<Option<BloomFilter>, FileError> {
+        let Some(rg) = self.metadata.row_groups.get(row_group) else {
+            return Err(FileError::Schema(format!("no row group {row_group}")));
+        };
+        let Some(chunk) = rg.columns.get(column) else {
+            return Err(FileError::Schema(format!("no column {column}")));
+        };
+        let Some(meta) = chunk.bloom_filter else {
+            return Ok(None);
+        };
+        let field = self.metadata.schema[column].clone();
+        let location = BufferLocation {
+            column: field.name(),
+            row_group,
+            page: None,
+        };
+        let bytes = self.read_buffer(meta, location)?;
+        match BloomFilter::from_bytes(&bytes) {
+            Ok(filter) => Ok(Some(filter)),
+            Err(e) => malformed(e),
+        }
+    }
+
+    /// Decides which row groups and pages may contain rows matching the predicate. Only the
+    /// footer and, for equality lookups, bloom filters are read.
+    pub fn plan(&mut self, predicate: &Predicate) -> Result<ScanPlan, FileError> {
+        for column in predicate.columns() {
+            if self.metadata.resolve_path(column).is_none() {
+                return Err(FileError::Schema(format!("unknown column {column}")));
+            }
+        }
+        let mut bloom_columns: Vec<usize> = predicate
+            .bloom_columns()
+            .iter()
+            .filter_map(|name| self.metadata.column_index(name))
+            .collect();
+        bloom_columns.dedup();
+
+        let mut plan = ScanPlan::default();
+        for index in 0..self.metadata.row_groups.len() {
+            let metadata = &self.metadata;
+            let rg = &metadata.row_groups[index];
+            let num_pages = rg.num_pages(metadata.page_size);
+            plan.report.row_groups_total += 1;
+            plan.report.pages_total += num_pages;
+
+            let chunk_stats = |name: &str| {
+                let (column, field) = metadata.resolve_path(name)?;
+                let chunk = &rg.columns[column];
+                match field {
+                    Some(field) => chunk.field_stats.get(field),
+                    None => Some(&chunk.stats),
+                }
+            };
+            if predicate.can_skip(&chunk_stats, rg.num_rows) {
+                plan.report.row_groups_skipped += 1;
+                plan.report.pages_skipped += num_pages;
+                continue;
+            }
+
+            let mut filters = vec![];
+            for &column in &bloom_columns {
+                if let Some(filter) = self.read_bloom_filter(index, column)? {
+                    filters.push((column, filter));
+                }
+            }
+            let metadata = &self.metadata;
+            let rg = &metadata.row_groups[index];
+            let bloom = |name: &str| {
+                let column = metadata.column_index(name)?;
+                filters.iter().find(|(c, _)| *c == column).map(|(_, f)| f)
+            };
+            if predicate.bloom_can_skip(&bloom) {
+                plan.report.row_groups_skipped += 1;
+                plan.report.row_groups_skipped_by_bloom_filter += 1;
+                plan.report.pages_skipped += num_pages;
+                continue;
+            }
+
+            let mut pages = vec![];
+            for page in 0..num_pages {
+                let page_stats = |name: &str| {
+                    let (column, field) = metadata.resolve_path(name)?;
+                    let page = rg.columns[column].pages.get(page)?;
+                    match field {
+                        Some(field) => page.field_stats.get(field),
+                        None => Some(&page.stats),
+                    }
+                };
+                if predicate.can_skip(&page_stats, metadata.page_rows(rg.num_rows, page)) {
+                    plan.report.pages_skipped += 1;
+                } else {
+                    pages.push(page);
+                }
+            }
+            if pages.is_empty() {
+                plan.report.row_groups_skipped += 1;
+            } else {
+                plan.row_groups.push((index, pages));
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Reads only the row groups and pages that may match the predicate. The rows that come back
+    /// are candidates, they still have to be filtered with the predicate.
+    pub fn scan(&mut self, predicate: &Predicate) -> Result<(Database, ScanReport), FileError> {
+        let plan = self.plan(predicate)?;
+        let schema = self.metadata.schema.clone();
+        let mut row_groups = Vec::with_capacity(plan.row_groups.len());
+        for (row_group, pages) in &plan.row_groups {
+            let mut columns = Vec::with_capacity(schema.len());
+            for (index, field) in schema.iter().enumerate() {
+                let chunk = self.read_pages(*row_group, index, pages)?;
+                columns.push(Column::new(field.name().to_string(), chunk));
+            }
+            let num_rows = columns.first().map_or(0, |c| c.inner().len());
+            row_groups.push(RowGroup::new(columns, num_rows));
+        }
+        let database = Database::from_row_groups(&schema, row_groups, self.metadata.row_group_size);
+        Ok((database, plan.report))
+    }
+
+    fn read_buffer(
+        &mut self,
+        meta: BufferMeta,
+        location: BufferLocation,
+    ) -> Result<Vec<u8>, FileError> {
+        if meta
+            .offset
+            .checked_add(meta.length)
+            .is_none_or(|end| end > self.data_end)
+        {
+            return malformed(format!(
+                "{} buffer of column {} lies outside the file",
+                meta.kind.name(),
+                location.column
+            ));
+        }
+        let mut buffer = vec![0; meta.length as usize];
+        self.inner.seek(SeekFrom::Start(meta.offset))?;
+        self.inner.read_exact(&mut buffer)?;
+        let actual = crc32c(&buffer);
+        if actual != meta.checksum {
+            return Err(FileError::Corrupted(Corruption {
+                column: Some(location.column.to_string()),
+                row_group: Some(location.row_group),
+                page: location.page,
+                buffer: meta.kind.name(),
+                offset: meta.offset,
+                length: meta.length,
+                expected_checksum: meta.checksum,
+                actual_checksum: actual,
+            }));
+        }
+        Ok(buffer)
+    }
+
+    /// Checks the checksum of every buffer in the file and returns all damaged ones. Unlike
+    /// reading, this doesn't stop at the first bad buffer.
+    pub fn verify(&mut self) -> Result<Vec<Corruption>, FileError> {
+        let schema = self.metadata.schema.clone();
+        let row_groups = self.metadata.row_groups.clone();
+        let mut corruptions = vec![];
+        for (row_group, rg) in row_groups.iter().enumerate() {
+            for (field, chunk) in schema.iter().zip(&rg.columns) {
+                let pages = chunk.pages.iter().enumerate();
+                let buffers = pages
+                    .flat_map(|(page, meta)| meta.buffers.iter().map(move |b| (Some(page), b)))
+                    .chain(chunk.bloom_filter.iter().map(|b| (None, b)));
+                for (page, &buffer) in buffers {
+                    let location = BufferLocation {
+                        column: field.name(),
+                        row_group,
+                        page,
+                    };
+                    match self.read_buffer(buffer, location) {
+                        Ok(_) => {}
+                        Err(FileError::Corrupted(corruption)) => corruptions.push(corruption),
+                        // a buffer cut short by truncation is damage too
+                        Err(FileError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                            corruptions.push(Corruption {
+                                column: Some(field.name().to_string()),
+                                row_group: Some(row_group),
+                                page,
+                                buffer: buffer.kind.name(),
+                                offset: buffer.offset,
+                                length: buffer.length,
+                                expected_checksum: buffer.checksum,
+                                actual_checksum: 0,
+                            })
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        }
+        Ok(corruptions)
+    }
+
+    /// Reads every row group.
+    pub fn read_database(&mut self) -> Result<Database, FileError> {
+        let mut row_groups = Vec::with_capacity(self.metadata.row_groups.len());
+        for index in 0..self.metadata.row_groups.len() {
+            row_groups.push(self.read_row_group(index)?);
+        }
+        Ok(Database::from_row_groups(
+            &self.metadata.schema,
+            row_groups,
+            self.metadata.row_group_size,
+        ))
+    }
+}
+
+/// Row groups and pages chosen by [`FileReader::plan`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ScanPlan {
+    row_groups: Vec<(usize, Vec<usize>)>,
+    report: ScanReport,
+}
+
+impl ScanPlan {
+    /// Row group index with the page indexes to read from it.
+    pub fn row_groups(&self) -> &[(usize, Vec<usize>)] {
+        &self.row_groups
+    }
+
+    pub fn report(&self) -> &ScanReport {
+        &self.report
+    }
+}
+
+/// How much of the file the statistics allowed to skip.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScanReport {
+    pub row_groups_total: usize,
+    pub row_groups_skipped: usize,
+    /// Row groups whose min/max allowed them but a bloom filter ruled out, included in
+    /// `row_groups_skipped`.
+    pub row_groups_skipped_by_bloom_filter: usize,
+    /// Pages of every column are skipped together, so this counts page positions, not buffers.
+    pub pages_total: usize,
+    pub pages_skipped: usize,
+}
+
+/// Encoding the writer picked for a column chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingChoice {
+    pub column: String,
+    pub row_group: usize,
+    pub encoding: Encoding,
+    /// Estimated size in bytes of the chunk under every encoding its type supports, smallest
+    /// first. Empty when the encoding was forced.
+    pub estimates: Vec<(Encoding, usize)>,
+    /// Set by `WriteOptions::with_encoding`.
+    pub forced: bool,
+}
+
+impl EncodingChoice {
+    /// Why the encoding was picked.
+    pub fn reason(&self) -> String {
+        if self.forced {
+            return "forced by write options".to_string();
+        }
+        let estimate = |encoding| {
+            self.estimates
+                .iter()
+                .find(|(e, _)| *e == encoding)
+                .map_or(0, |&(_, size)| size)
+        };
+        match self.estimates.first() {
+            Some(&(smallest, size)) if smallest != self.encoding => {
+                let chosen = estimate(self.encoding);
+                format!(
+                    "{} is {} bytes smaller but slower to decode than {}",
+                    smallest.name(),
+                    chosen - size,
+                    self.encoding.name()
+                )
+            }
+            _ => "smallest estimate".to_string(),
+        }
+    }
+}
+
+fn decompress(meta: BufferMeta, bytes: Vec<u8>) -> Result<Vec<u8>, FileError> {
+    if meta.codec == codec::UNCOMPRESSED_ID {
+        return Ok(bytes);
+    }
+    let Some(codec) = codec::by_id(meta.codec) else {
+        return malformed(format!("unknown codec {}", meta.codec));
+    };
+    codec
+        .decompress(&bytes, meta.uncompressed_length as usize)
+        .or_else(|e| malformed(format!("{} buffer: {e}", codec.name())))
+}
+
+/// Checks a whole file and returns every damaged region. A damaged footer hides where everything
+/// else is, so it is reported alone.
+pub fn verify<R: Read + Seek>(inner: R) -> Result<Vec<Corruption>, FileError> {
+    match FileReader::new(inner) {
+        Ok(mut reader) => reader.verify(),
+        Err(FileError::Corrupted(corruption)) => Ok(vec![corruption]),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes a whole database, one row group at a time.
+pub fn write_database<W: Write>(database: &Database, inner: W) -> Result<W, FileError> {
+    write_database_with_options(database, inner, WriteOptions::default())
+}
+
+pub fn write_database_with_options<W: Write>(
+    database: &Database,
+    inner: W,
+    options: WriteOptions,
+) -> Result<W, FileError> {
+    let mut writer =
+        FileWriter::with_options(inner, database.schema(), database.row_group_size(), options)?;
+    for row_group in database.row_groups() {
+        if row_group.num_rows() > 0 {
+            writer.write_row_group(row_group)?;
+        }
+    }
+    writer.finish()
+}
+
+pub fn write_to_path(database: &Database, path: impl AsRef<Path>) -> Result<(), FileError> {
+    write_database(database, BufWriter::new(File::create(path)?))?;
+    Ok(())
+}
+
+// column chunks
+
+type Buffers = Vec<(BufferKind, Vec<u8>)>;
+
+/// Rows encoded to estimate the size of a column chunk.
+pub const SAMPLE_ROWS: usize = 4096;
+
+// the sample is taken from a few places spread over the chunk, so sorted or clustered data looks
+// like it does in full. Runs and deltas only exist between neighbours, so each slice is contiguous.
+const SAMPLE_SLICES: usize = 4;
+
+fn choose_encoding(
+    name: &str,
+    row_group: usize,
+    column: &ColumnType,
+    preference: EncodingPreference,
+) -> EncodingChoice {
+    let len = column.len();
+    let slices: Vec<ColumnType> = if len <= SAMPLE_ROWS {
+        vec![column.slice(0, len)]
+    } else {
+        let slice_len = SAMPLE_ROWS / SAMPLE_SLICES;
+        let step = (len - slice_len) / (SAMPLE_SLICES - 1);
+        (0..SAMPLE_SLICES)
+            .map(|i| column.slice(i * step, slice_len))
+            .collect()
+    };
+    let sampled: usize = slices.iter().map(ColumnType::len).sum();
+    let data_type = column.data_type();
+    let mut estimates: Vec<(Encoding, usize)> = ENCODINGS
+        .into_iter()
+        .filter(|encoding| encoding.supports(&data_type))
+        .map(|encoding| {
+            let bytes: usize = slices
+                .iter()
+                .flat_map(|slice| encode_as(slice, encoding).1)
+                .map(|(_, buffer)| buffer.len())
+                .sum();
+            (encoding, bytes * len / sampled.max(1))
+        })
+        .collect();
+
+    // walk from cheap to expensive to decode, a slower encoding has to beat the current pick by
+    // the preferred saving for every step it is slower
+    let mut chosen = estimates[0];
+    for &(encoding, size) in &estimates[1..] {
+        let steps = encoding.decode_cost() - chosen.0.decode_cost();
+        let limit = chosen.1 as f64 * (1.0 - preference.min_saving()).powi(steps as i32);
+        if (size as f64) < limit || (steps == 0 && size < chosen.1) {
+            chosen = (encoding, size);
+        }
+    }
+    estimates.sort_by_key(|&(encoding, size)| (size, encoding.decode_cost()));
+    EncodingChoice {
+        column: name.to_string(),
+        row_group,
+        encoding: chosen.0,
+        estimates,
+        forced: false,
+    }
+}
+
+// encodes with the given encoding, which must support the column's type. A dictionary column that
+// fell back to plain is still written plain, a text column builds a dictionary for the page.
+fn encode_as(column: &ColumnType, encoding: Encoding) -> (Encoding, Buffers) {
+    macro_rules! fixed {
+        ($c:expr) => {
+            match encoding {
+                Encoding::RunLength => encode_run_length(&RunLengthArray::encode($c)),
+                _ => encode_fixed_plain($c),
+            }
+        };
+    }
+    macro_rules! integer {
+        ($c:expr) => {
+            match encoding {
+                Encoding::Delta => encode_delta($c),
+                _ => fixed!($c),
+            }
+        };
+    }
+    macro_rules! float {
+        ($c:expr) => {
+            match encoding {
+                Encoding::Gorilla => encode_gorilla($c),
+                _ => fixed!($c),
+            }
+        };
+    }
+    let buffers = match column {
+        ColumnType::Int8(c) => integer!(c),
+        ColumnType::Int16(c) => integer!(c),
+        ColumnType::Int32(c) => integer!(c),
+        ColumnType::Int64(c) => integer!(c),
+        ColumnType::UInt8(c) => integer!(c),
+        ColumnType::UInt16(c) => integer!(c),
+        ColumnType::UInt32(c) => integer!(c),
+        ColumnType::UInt64(c) => integer!(c),
+        ColumnType::Float32(c) => float!(c),
+        ColumnType::Float64(c) => float!(c),
+        ColumnType::Text(c) if encoding == Encoding::Dictionary => {
+            let mut dictionary = DictionaryArray::with_capacity(c.len()).with_max_size(usize::MAX);
+            for value in (0..c.len()).map(|i| c.get(i).flatten()) {
+                match value {
+                    Some(value) => dictionary.add(value),
+                    None => dictionary.add_null(),
+                }
+            }
+            return encode_dictionary(&dictionary);
+        }
+        ColumnType::Text(c) => encode_variable(c),
+        ColumnType::Binary(c) => encode_variable(c),
+        ColumnType::LargeText(c) => encode_variable(c),
+        ColumnType::LargeBinary(c) => encode_variable(c),
+        ColumnType::Dictionary(c) if encoding == Encoding::Plain => encode_variable(&c.to_plain()),
+        ColumnType::Dictionary(c) => return encode_dictionary(c),
+        ColumnType::Boolean(c) => encode_boolean(c),
+        ColumnType::Date32(c) => integer!(c.values()),
+        ColumnType::Time64(c) => integer!(c.values()),
+        ColumnType::Timestamp(c) => integer!(c.values()),
+        ColumnType::Duration(c) => integer!(c.values()),
+        ColumnType::Decimal128(c) => fixed!(c.values()),
+        ColumnType::FixedSizeBinary(c) => encode_fixed_binary(c),
+        ColumnType::List(c) => encode_list(c),
+        ColumnType::Struct(c) => encode_struct(c),
+        ColumnType::Map(c) => encode_map(c),
+        ColumnType::Enum8(c) => integer!(c.codes()),
+        ColumnType::Enum16(c) => integer!(c.codes()),
+    };
+    (encoding, buffers)
+}
+
+// nothing is stored without nulls, mostly valid bitmaps are stored as runs when that is smaller.
+fn encode_validity(validity: Option<&BitVec>) -> Buffers {
+    let Some(validity) = validity.filter(|validity| validity.null_count() > 0) else {
+        return vec![];
+    };
+    let runs = RunLengthBitVec::encode(validity);
+    if 1 + runs.num_runs() * 4 < validity.as_bytes().len() {
+        vec![(BufferKind::ValidityRuns, runs.to_bytes())]
+    } else {
+        vec![(BufferKind::Validity, validity.as_bytes().to_vec())]
+    }
+}
+
+// nulls repeat the previous value so they don't cost any bits.
+fn encode_delta<T: DeltaInt>(column: &Fixed<T>) -> Buffers {
+    let mut previous = 0;
+    let values: Vec<i64> = column
+        .get_records()
+        .map(|value| {
+            if let Some(value) = value {
+                previous = value.to_i64();
+            }
+            previous
+        })
+        .collect();
+    let mut buffers = encode_validity(column.validity());
+    buffers.push((BufferKind::Data, bitpack::encode(&values)));
+    buffers
+}
+
+// like delta, nulls repeat the previous value which costs a single bit.
+fn encode_gorilla<T: Native>(column: &Fixed<T>) -> Buffers {
+    let mut previous = T::default();
+    let values: Vec<T> = column
+        .get_records()
+        .map(|value| {
+            if let Some(value) = value {
+                previous = value;
+            }
+            previous
+        })
+        .collect();
+    let mut buffers = encode_validity(column.validity());
+    buffers.push((BufferKind::Data, gorilla::encode(&values)));
+    buffers
+}
+
+fn encode_fixed_plain<T: Native>(column: &Fixed<T>) -> Buffers {
+    let mut data = Vec::with_capacity(column.len() * T::WIDTH);
+    for &value in column.data() {
+        value.write_le(&mut data);
+    }
+    let mut buffers = encode_validity(column.validity());
+    buffers.push((BufferKind::Data, data));
+    buffers
+}
+
+// validity of every run, length of every run (u32 each), value of every run.
+fn encode_run_length<T: Native>(runs: &RunLengthArray<T>) -> Buffers {
+    let mut lengths = Vec::with_capacity(runs.num_runs() * 4);
+    let mut values = Vec::with_capacity(runs.num_runs() * T::WIDTH);
+    for (&value, (_, length)) in runs.values().iter().zip(runs.runs()) {
+        put_u32(&mut lengths, length as u32);
+        value.write_le(&mut values);
+    }
+    let mut buffers = vec![];
+    if runs.validity().null_count() > 0 {
+        buffers.push((BufferKind::Validity, runs.validity().as_bytes().to_vec()));
+    }
+    buffers.push((BufferKind::RunLengths, lengths));
+    buffers.push((BufferKind::Data, values));
+    buffers
+}
+
+fn encode_variable<T: ToBytesRef + ?Sized, O: Offset>(column: &Variable<T, O>) -> Buffers {
+    let mut offsets = Vec::with_capacity(column.offsets().len() * O::WIDTH);
+    for &offset in column.offsets() {
+        offset.write_le(&mut offsets);
+    }
+    let mut buffers = encode_validity(column.validity());
+    buffers.push((BufferKind::Offsets, offsets));
+    buffers.push((BufferKind::Data, column.data().to_vec()));
+    buffers
+}
+
+fn encode_boolean(column: &BooleanArray) -> Buffers {
+    let mut buffers = encode_validity(column.validity());
+    buffers.push((BufferKind::Data, column.values().as_bytes().to_vec()));
+    buffers
+}
+
+fn encode_list(column: &List<ColumnType>) -> Buffers {
+    let mut buffers = encode_validity(column.validity());
+    buffers.push((BufferKind::Offsets, list_offsets(column.offsets())));
+    buffers.extend(encode_as(column.values(), Encoding::Plain).1);
+    buffers
+}
+
+fn list_offsets(offsets: &[i32]) -> Vec<u8> {
+    offsets.iter().flat_map(|o| o.to_le_bytes()).collect()
+}
+
+fn encode_struct(column: &StructArray) -> Buffers {
+    let mut buffers = encode_validity(column.validity());
+    let fields = column
+        .fields()
+        .iter()
+        .map(|f| encode_as(f.inner(), Encoding::Plain).1);
+    buffers.extend(encode_children(fields));
+    buffers
+}
+
+fn encode_map(column: &Map<StringArray, ColumnType>) -> Buffers {
+    let mut buffers = encode_validity(column.validity());
+    buffers.push((BufferKind::Offsets, list_offsets(column.offsets())));
+    let keys = encode_variable(column.keys());
+    let values = encode_as(column.values(), Encoding::Plain).1;
+    buffers.extend(encode_children([keys, values]));
+    buffers
+}
+
+// the number of buffers of every child, then the buffers of all of them.
+fn encode_children(children: impl IntoIterator<Item = Buffers>) -> Buffers {
+    let children: Vec<Buffers> = children.into_iter().collect();
+    // more than 255 buffers per page is caught by the writer
+    let counts = children.iter().map(|c| c.len().min(255) as u8).collect();
+    let mut buffers = vec![(BufferKind::ChildBuffers, counts)];
+    buffers.extend(children.into_iter().flatten());
+    buffers
+}
+
+fn encode_fixed_binary(column: &FixedSizeBinaryArray) -> Buffers {
+    let mut buffers = encode_validity(column.validity());
+    buffers.push((BufferKind::Data, column.data().to_vec()));
+    buffers
+}
+
+// a dictionary chunk that fell back to plain is stored as a plain text chunk.
+fn encode_dictionary(column: &DictionaryArray) -> (Encoding, Buffers) {
+    let (Some(values), Some(codes)) = (column.values(), column.codes()) else {
+        return (Encoding::Plain, encode_variable(column.plain().unwrap()));
+    };
+    let mut buffers = encode_fixed_plain(codes);
+    buffers.last_mut().unwrap().0 = BufferKind::Codes;
+    // the distinct values are never null, so they have no validity buffer
+    buffers.extend(encode_variable(values));
+    (Encoding::Dictionary, buffers)
+}
+
+fn decode_chunk(
+    data_type: &DataType,
+    encoding: Encoding,
+    num_rows: usize,
+    mut buffers: Buffers,
+) -> Result<ColumnType, FileError> {
+    if !encoding.supports(data_type) {
+        return malformed(format!(
+            "{} column can't be {} encoded",
+            data_type.name(),
+            encoding.name()
+        ));
+    }
+
+    if encoding == Encoding::Dictionary {
+        if buffers.len() < 3 {
+            return malformed("dictionary page needs at least 3 buffers");
+        }
+        let dictionary = buffers.split_off(buffers.len() - 2);
+        let codes: Fixed<u32> = decode_fixed(num_rows, buffers)?;
+        let num_values = dictionary[0].1.len().saturating_sub(4) / 4;
+        let values: Variable<str> = decode_variable(num_values, dictionary)?;
+        if values.null_count() > 0 {
+            return malformed("dictionary values can't be null");
+        }
+        if codes
+            .get_records()
+            .flatten()
+            .any(|c| c as usize >= num_values)
+        {
+            return malformed("dictionary code out of bounds");
+        }
+        let column = DictionaryArray::from_parts(values, codes);
+        if *data_type == DataType::Text {
+            return Ok(ColumnType::Text(column.to_plain()));
+        }
+        return Ok(ColumnType::Dictionary(column));
+    }
+
+    macro_rules! fixed {
+        ($variant:ident) => {
+            ColumnType::$variant(match encoding {
+                Encoding::RunLength => decode_run_length(num_rows, buffers)?,
+                _ => decode_fixed(num_rows, buffers)?,
+            })
+        };
+    }
+    macro_rules! integer {
+        ($variant:ident) => {
+            match encoding {
+                Encoding::Delta => ColumnType::$variant(decode_delta(num_rows, buffers)?),
+                _ => fixed!($variant),
+            }
+        };
+    }
+    macro_rules! float {
+        ($variant:ident) => {
+            match encoding {
+                Encoding::Gorilla => ColumnType::$variant(decode_gorilla(num_rows, buffers)?),
+                _ => fixed!($variant),
+            }
+        };
+    }
+    // decoded as the integers they are stored as
+    macro_rules! temporal {
+        ($variant:ident, $physical:ident) => {
+            match decode_chunk(&data_type.physical(), encoding, num_rows, buffers)? {
+                ColumnType::$physical(values) => {
+                    ColumnType::$variant(TemporalArray::from_parts(data_type.clone(), values))
+                }
+                _ => unreachable!("decoded as the physical type"),
+            }
+        };
+    }
+    Ok(match data_type {
+        DataType::Int8 => integer!(Int8),
+        DataType::Int16 => integer!(Int16),
+        DataType::Int32 => integer!(Int32),
+        DataType::Int64 => integer!(Int64),
+        DataType::UInt8 => integer!(UInt8),
+        DataType::UInt16 => integer!(UInt16),
+        DataType::UInt32 => integer!(UInt32),
+        DataType::UInt64 => integer!(UInt64),
+        DataType::Float32 => float!(Float32),
+        DataType::Float64 => float!(Float64),
+        DataType::Text => ColumnType::Text(decode_variable(num_rows, buffers)?),
+        DataType::Binary => ColumnType::Binary(decode_variable(num_rows, buffers)?),
+        DataType::LargeText => ColumnType::LargeText(decode_variable(num_rows, buffers)?),
+        DataType::LargeBinary => ColumnType::LargeBinary(decode_variable(num_rows, buffers)?),
+        DataType::Dictionary => {
+            let column = decode_variable(num_rows, buffers)?;
+            ColumnType::Dictionary(DictionaryArray::from_plain(column))
+        }
+        DataType::Boolean => ColumnType::Boolean(decode_boolean(num_rows, buffers)?),
+        DataType::Date32 => temporal!(Date32, Int32),
+        DataType::Time64(_) => temporal!(Time64, Int64),
+        DataType::Timestamp(..) => temporal!(Timestamp, Int64),
+        DataType::Duration(_) => temporal!(Duration, Int64),
+        &DataType::Decimal128(precision, scale) => {
+            let values = match encoding {
+                Encoding::RunLength => decode_run_length(num_rows, buffers)?,
+                _ => decode_fixed(num_rows, buffers)?,
+            };
+            ColumnType::Decimal128(Decimal128Array::from_parts(precision, scale, values))
+        }
+        &DataType::FixedSizeBinary(width) => {
+            ColumnType::FixedSizeBinary(decode_fixed_binary(width, num_rows, buffers)?)
+        }
+        DataType::List(values) => ColumnType::List(decode_list(values, num_rows, buffers)?),
+        DataType::Struct(fields) => ColumnType::Struct(decode_struct(fields, num_rows, buffers)?),
+        DataType::Map(values) => ColumnType::Map(decode_map(values, num_rows, buffers)?),
+        DataType::Enum(labels) => {
+            match decode_chunk(&data_type.physical(), encoding, num_rows, buffers)? {
+                ColumnType::UInt8(codes) => ColumnType::Enum8(decode_enum(labels, codes)?),
+                ColumnType::UInt16(codes) => ColumnType::Enum16(decode_enum(labels, codes)?),
+                _ => unreachable!("decoded as the physical type"),
+            }
+        }
+    })
+}
+
+fn decode_enum<T: EnumCode>(labels: &[String], codes: Fixed<T>) -> Result<EnumArray<T>, FileError> {
+    if codes
+        .get_records()
+        .flatten()
+        .any(|c| c.to_usize() >= labels.len())
+    {
+        return malformed("enum code without a label");
+    }
+    Ok(EnumArray::from_parts(labels.to_vec(), codes))
+}
+
+// takes the validity off the front of a page's buffers, `None` when the page has no nulls.
+fn decode_validity(buffers: &mut Buffers, num_rows: usize) -> Result<Option<BitVec>, FileError> {
+    let validity = match buffers.first() {
+        Some((BufferKind::Validity | BufferKind::ValidityRuns, _)) => buffers.remove(0),
+        _ => return Ok(None),
+    };
+    match validity {
+        (BufferKind::Validity, bytes) => {
+            if bytes.len() != num_rows.div_ceil(8) {
+                return malformed("validity buffer has the wrong size");
+            }
+            Ok(Some(BitVec::from_bytes(bytes, num_rows)))
+        }
+        (_, bytes) => {
+            let runs = RunLengthBitVec::from_bytes(&bytes).or_else(malformed)?;
+            if runs.len() != num_rows {
+                return malformed("validity runs have the wrong length");
+            }
+            Ok(Some(runs.decode()))
+        }
+    }
+}
+
+fn decode_fixed<T: Native>(num_rows: usize, mut buffers: Buffers) -> Result<Fixed<T>, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let [(_, data)]: [(BufferKind, Vec<u8>); 1] = buffers
+        .try_into()
+        .or_else(|_| malformed("fixed column needs a data buffer"))?;
+    if data.len() != num_rows * T::WIDTH {
+        return malformed("data buffer has the wrong size");
+    }
+    let data = data.chunks_exact(T::WIDTH).map(T::read_le).collect();
+    Ok(Fixed::from_parts(data, validity))
+}
+
+fn decode_boolean(num_rows: usize, mut buffers: Buffers) -> Result<BooleanArray, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let [(_, data)]: [(BufferKind, Vec<u8>); 1] = buffers
+        .try_into()
+        .or_else(|_| malformed("boolean column needs a data buffer"))?;
+    if data.len() != num_rows.div_ceil(8) {
+        return malformed("data buffer has the wrong size");
+    }
+    Ok(BooleanArray::from_parts(
+        BitVec::from_bytes(data, num_rows),
+        validity,
+    ))
+}
+
+fn decode_list(
+    values: &DataType,
+    num_rows: usize,
+    mut buffers: Buffers,
+) -> Result<List<ColumnType>, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let offsets = decode_list_offsets(&mut buffers, num_rows, validity.as_ref())?;
+    let num_values = offsets[num_rows] as usize;
+    let values = decode_chunk(values, Encoding::Plain, num_values, buffers)?;
+    Ok(List::from_parts(offsets, values, validity))
+}
+
+fn decode_map(
+    values: &DataType,
+    num_rows: usize,
+    mut buffers: Buffers,
+) -> Result<Map<StringArray, ColumnType>, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let offsets = decode_list_offsets(&mut buffers, num_rows, validity.as_ref())?;
+    let num_entries = offsets[num_rows] as usize;
+    let [keys, values_buffers]: [Buffers; 2] = split_child_buffers(buffers, 2)?.try_into().unwrap();
+    let keys: StringArray = decode_variable(num_entries, keys)?;
+    if keys.null_count() > 0 {
+        return malformed("map keys can't be null");
+    }
+    let values = decode_chunk(values, Encoding::Plain, num_entries, values_buffers)?;
+    Ok(Map::from_parts(offsets, keys, values, validity))
+}
+
+// takes the offsets of a list or map page off the front of its buffers.
+fn decode_list_offsets(
+    buffers: &mut Buffers,
+    num_rows: usize,
+    validity: Option<&BitVec>,
+) -> Result<Vec<i32>, FileError> {
+    if buffers.is_empty() {
+        return malformed("list column needs offsets");
+    }
+    let (_, offsets) = buffers.remove(0);
+    if offsets.len() != (num_rows + 1) * 4 {
+        return malformed("offset buffer has the wrong size");
+    }
+    let offsets: Vec<i32> = offsets.chunks_exact(4).map(i32::read_le).collect();
+    if offsets[0] != 0 || offsets.windows(2).any(|w| w[0] > w[1]) {
+        return malformed("offsets are out of order");
+    }
+    if let Some(validity) = validity
+        && (0..num_rows).any(|i| !validity[i] && offsets[i] != offsets[i + 1])
+    {
+        return malformed("null lists can't have values");
+    }
+    Ok(offsets)
+}
+
+fn decode_struct(
+    fields: &[Field],
+    num_rows: usize,
+    mut buffers: Buffers,
+) -> Result<StructArray, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let mut columns = Vec::with_capacity(fields.len());
+    for (field, buffers) in fields
+        .iter()
+        .zip(split_child_buffers(buffers, fields.len())?)
+    {
+        let column = decode_chunk(field.data_type(), Encoding::Plain, num_rows, buffers)?;
+        columns.push(Column::new(field.name().to_string(), column));
+    }
+    Ok(StructArray::from_parts(columns, validity))
+}
+
+// splits the rest of a struct or map page into the buffers of each of its `num_children`
+// columns, using the number of buffers of every child stored first.
+fn split_child_buffers(
+    mut buffers: Buffers,
+    num_children: usize,
+) -> Result<Vec<Buffers>, FileError> {
+    let counts = match buffers.first() {
+        Some((BufferKind::ChildBuffers, counts)) if counts.len() == num_children => {
+            buffers.remove(0).1
+        }
+        _ => return malformed("page needs the number of buffers of every child"),
+    };
+    if counts.iter().map(|&c| c as usize).sum::<usize>() != buffers.len() {
+        return malformed("page has the wrong number of buffers");
+    }
+    let mut buffers = buffers.into_iter();
+    Ok(counts
+        .into_iter()
+        .map(|count| buffers.by_ref().take(count as usize).collect())
+        .collect())
+}
+
+fn decode_fixed_binary(
+    width: usize,
+    num_rows: usize,
+    mut buffers: Buffers,
+) -> Result<FixedSizeBinaryArray, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let [(_, data)]: [(BufferKind, Vec<u8>); 1] = buffers
+        .try_into()
+        .or_else(|_| malformed("fixed size binary column needs a data buffer"))?;
+    if num_rows.checked_mul(width) != Some(data.len()) {
+        return malformed("data buffer has the wrong size");
+    }
+    Ok(FixedSizeBinaryArray::from_parts(width, data, validity))
+}
+
+fn decode_run_length<T: Native>(
+    num_rows: usize,
+    mut buffers: Buffers,
+) -> Result<Fixed<T>, FileError> {
+    // the validity is per run, so its length is only known after the run lengths
+    let validity = match buffers.first() {
+        Some((BufferKind::Validity, _)) => Some(buffers.remove(0)),
+        _ => None,
+    };
+    let [(_, lengths), (_, values)]: [(BufferKind, Vec<u8>); 2] = buffers
+        .try_into()
+        .or_else(|_| malformed("run length column needs lengths and values"))?;
+    if !lengths.len().is_multiple_of(4) {
+        return malformed("run length buffer has the wrong size");
+    }
+    let num_runs = lengths.len() / 4;
+    if values.len() != num_runs * T::WIDTH {
+        return malformed("data buffer has the wrong size");
+    }
+    let lengths: Vec<usize> = lengths
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+        .collect();
+    if lengths.iter().sum::<usize>() != num_rows {
+        return malformed("runs don't add up to the number of rows");
+    }
+    let values = values.chunks_exact(T::WIDTH).map(T::read_le).collect();
+    let validity = match validity {
+        Some((_, bytes)) if bytes.len() != num_runs.div_ceil(8) => {
+            return malformed("validity buffer has the wrong size");
+        }
+        Some((_, bytes)) => BitVec::from_bytes(bytes, num_runs),
+        None => BitVec::ones(num_runs),
+    };
+    let runs = RunLengthArray::from_runs(values, validity, &lengths).or_else(malformed)?;
+    Ok(runs.decode())
+}
+
+fn decode_delta<T: DeltaInt>(num_rows: usize, mut buffers: Buffers) -> Result<Fixed<T>, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let [(_, data)]: [(BufferKind, Vec<u8>); 1] = buffers
+        .try_into()
+        .or_else(|_| malformed("delta column needs a data buffer"))?;
+    let values = bitpack::decode(&data, num_rows).or_else(malformed)?;
+    let data = values.into_iter().map(T::from_i64).collect();
+    Ok(Fixed::from_parts(data, validity))
+}
+
+fn decode_gorilla<T: Native>(num_rows: usize, mut buffers: Buffers) -> Result<Fixed<T>, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let [(_, data)]: [(BufferKind, Vec<u8>); 1] = buffers
+        .try_into()
+        .or_else(|_| malformed("gorilla column needs a data buffer"))?;
+    let data = gorilla::decode(&data, num_rows).or_else(malformed)?;
+    Ok(Fixed::from_parts(data, validity))
+}
+
+fn decode_variable<T: FromByteRef + ToBytesRef + ?Sized, O: Offset>(
+    num_rows: usize,
+    mut buffers: Buffers,
+) -> Result<Variable<T, O>, FileError> {
+    let validity = decode_validity(&mut buffers, num_rows)?;
+    let [(_, offsets), (_, data)]: [(BufferKind, Vec<u8>); 2] = buffers
+        .try_into()
+        .or_else(|_| malformed("variable column needs offsets and data"))?;
+    if offsets.len() != (num_rows + 1) * O::WIDTH {
+        return malformed("offset buffer has the wrong size");
+    }
+    let offsets: Vec<O> = offsets.chunks_exact(O::WIDTH).map(O::read_le).collect();
+    // checks the offsets and, for text, that every value is utf-8
+    Variable::try_from_parts(data, offsets, validity).or_else(malformed)
+}
+
+// footer
+
+fn encode_footer(metadata: &FileMetadata) -> Vec<u8> {
+    let mut out = vec![];
+    put_u8(&mut out, VERSION);
+    put_u64(&mut out, metadata.row_group_size as u64);
+    put_u64(&mut out, metadata.page_size as u64);
+    put_u32(&mut out, metadata.schema.len() as u32);
+    for field in &metadata.schema {
+        put_bytes(&mut out, field.name().as_bytes());
+        encode_data_type(&mut out, field.data_type());
+    }
+    put_u32(&mut out, metadata.row_groups.len() as u32);
+    for rg in &metadata.row_groups {
+        put_u64(&mut out, rg.num_rows as u64);
+        for chunk in &rg.columns {
+            put_u8(&mut out, chunk.encoding.tag());
+            encode_stats(&mut out, &chunk.stats);
+            for stats in &chunk.field_stats {
+                encode_stats(&mut out, stats);
+            }
+            put_u32(&mut out, chunk.pages.len() as u32);
+            for page in &chunk.pages {
+                put_u8(&mut out, page.encoding.tag());
+                put_u8(&mut out, page.buffers.len() as u8);
+                for buffer in &page.buffers {
+                    encode_buffer_meta(&mut out, buffer);
+                }
+                encode_stats(&mut out, &page.stats);
+                for stats in &page.field_stats {
+                    encode_stats(&mut out, stats);
+                }
+            }
+            match chunk.bloom_filter {
+                Some(buffer) => {
+                    put_u8(&mut out, 1);
+                    encode_buffer_meta(&mut out, &buffer);
+                }
+                None => put_u8(&mut out, 0),
+            }
+        }
+    }
+    out
+}
+
+fn decode_footer(bytes: &[u8]) -> Result<FileMetadata, FileError> {
+    let mut reader = ByteReader::new(bytes);
+    let version = reader.u8()?;
+    if version != VERSION {
+        return malformed(format!("unsupported version {version}"));
+    }
+    let row_group_size = reader.u64()? as usize;
+    let page_size = reader.u64()? as usize;
+    if page_size == 0 {
+        return malformed("page size is zero");
+    }
+
+    let num_fields = reader.u32()? as usize;
+    let mut schema = Vec::with_capacity(num_fields.min(bytes.len()));
+    for _ in 0..num_fields {
+        let name = reader.string()?;
+        let data_type = decode_data_type(&mut reader)?;
+        schema.push(Field::new(name, data_type));
+    }
+
+    let num_row_groups = reader.u32()? as usize;
+    let mut row_groups = Vec::with_capacity(num_row_groups.min(bytes.len()));
+    for _ in 0..num_row_groups {
+        let num_rows = reader.u64()? as usize;
+        let mut columns = Vec::with_capacity(num_fields);
+        for field in &schema {
+            let encoding = Encoding::from_tag(reader.u8()?)?;
+            if !encoding.supports(field.data_type()) {
+                return malformed(format!(
+                    "{} column can't be {} encoded",
+                    field.data_type().name(),
+                    encoding.name()
+                ));
+            }
+            let stats = decode_stats(&mut reader)?;
+            let num_fields = nested_field_count(field.data_type());
+            let field_stats = (0..num_fields)
+                .map(|_| decode_stats(&mut reader))
+                .collect::<Result<Vec<_>, _>>()?;
+            let num_pages = reader.u32()? as usize;
+            if num_pages != num_rows.div_ceil(page_size) {
+                return malformed("number of pages doesn't match number of rows");
+            }
+            let mut pages = Vec::with_capacity(num_pages.min(bytes.len()));
+            for _ in 0..num_pages {
+                let encoding = Encoding::from_tag(reader.u8()?)?;
+                let num_buffers = reader.u8()?;
+                let mut buffers = Vec::with_capacity(num_buffers as usize);
+                for _ in 0..num_buffers {
+                    buffers.push(decode_buffer_meta(&mut reader)?);
+                }
+                let stats = decode_stats(&mut reader)?;
+                let field_stats = (0..num_fields)
+                    .map(|_| decode_stats(&mut reader))
+                    .collect::<Result<Vec<_>, _>>()?;
+                pages.push(PageMeta {
+                    encoding,
+                    buffers,
+                    stats,
+                    field_stats,
+                });
+            }
+            let bloom_filter = match reader.u8()? {
+                0 => None,
+                1 => Some(decode_buffer_meta(&mut reader)?),
+                _ => return malformed("bad bloom filter flag"),
+            };
+            columns.push(ColumnChunkMeta {
+                encoding,
+                pages,
+                stats,
+                field_stats,
+                bloom_filter,
+            });
+        }
+        row_groups.push(RowGroupMeta { num_rows, columns });
+    }
+    if !reader.is_empty() {
+        return malformed("trailing bytes in footer");
+    }
+
+    Ok(FileMetadata {
+        schema,
+        row_group_size,
+        page_size,
+        row_groups,
+    })
+}
+
+fn encode_buffer_meta(out: &mut Vec<u8>, buffer: &BufferMeta) {
+    put_u8(out, buffer.kind.tag());
+    put_u64(out, buffer.offset);
+    put_u64(out, buffer.length);
+    put_u32(out, buffer.checksum);
+    put_u8(out, buffer.codec);
+    put_u64(out, buffer.uncompressed_length);
+}
+
+fn decode_buffer_meta(reader: &mut ByteReader) -> Result<BufferMeta, FileError> {
+    Ok(BufferMeta {
+        kind: BufferKind::from_tag(reader.u8()?)?,
+        offset: reader.u64()?,
+        length: reader.u64()?,
+        checksum: reader.u32()?,
+        codec: reader.u8()?,
+        uncompressed_length: reader.u64()?,
+    })
+}
+
+fn data_type_tag(data_type: &DataType) -> u8 {
+    match data_type {
+        DataType::Int8 => 1,
+        DataType::Int16 => 2,
+        DataType::Int32 => 3,
+        DataType::UInt8 => 4,
+        DataType::UInt16 => 5,
+        DataType::UInt32 => 6,
+        DataType::Float32 => 7,
+        DataType::Float64 => 8,
+        DataType::Text => 9,
+        DataType::Binary => 10,
+        DataType::Dictionary => 11,
+        DataType::Boolean => 12,
+        DataType::Int64 => 13,
+        DataType::UInt64 => 14,
+        DataType::LargeText => 15,
+        DataType::LargeBinary => 16,
+        DataType::Date32 => 17,
+        DataType::Time64(_) => 18,
+        DataType::Timestamp(..) => 19,
+        DataType::Duration(_) => 20,
+        DataType::Decimal128(..) => 21,
+        DataType::FixedSizeBinary(_) => 22,
+        DataType::List(_) => 23,
+        DataType::Struct(_) => 24,
+        DataType::Map(_) => 25,
+        DataType::Enum(_) => 26,
+    }
+}
+
+// the tag, followed by the unit and timezone of temporal types, the precision and scale of
+// decimals, the width of fixed size binary, the type of the values of lists and maps, the name
+// and type of every field of structs or the labels of enums. Only the schema stores those,
+// scalars of temporal columns are their integers and decimal scalars carry their own scale.
+fn encode_data_type(out: &mut Vec<u8>, data_type: &DataType) {
+    put_u8(out, data_type_tag(data_type));
+    match data_type {
+        DataType::Time64(unit) | DataType::Duration(unit) => put_u8(out, time_unit_tag(*unit)),
+        DataType::Timestamp(unit, timezone) => {
+            put_u8(out, time_unit_tag(*unit));
+            // no timezone is stored as an empty one
+            put_bytes(out, timezone.as_deref().unwrap_or("").as_bytes());
+        }
+        DataType::Decimal128(precision, scale) => {
+            put_u8(out, *precision);
+            put_u8(out, *scale);
+        }
+        DataType::FixedSizeBinary(width) => put_u32(out, *width as u32),
+        DataType::List(values) | DataType::Map(values) => encode_data_type(out, values),
+        DataType::Struct(fields) => {
+            put_u32(out, fields.len() as u32);
+            for field in fields {
+                put_bytes(out, field.name().as_bytes());
+                encode_data_type(out, field.data_type());
+            }
+        }
+        DataType::Enum(labels) => {
+            put_u32(out, labels.len() as u32);
+            for label in labels {
+                put_bytes(out, label.as_bytes());
+            }
+        }
+        _ => {}
+    }
+}
+
+fn decode_data_type(reader: &mut ByteReader) -> Result<DataType, FileError> {
+    decode_nested_data_type(reader, 0)
+}
+
+// lists and structs nest, a corrupt footer must not be able to nest deep enough to blow the stack.
+fn decode_nested_data_type(reader: &mut ByteReader, depth: usize) -> Result<DataType, FileError> {
+    let tag = reader.u8()?;
+    Ok(match tag {
+        17 => DataType::Date32,
+        18 => DataType::Time64(time_unit_from_tag(reader.u8()?)?),
+        19 => {
+            let unit = time_unit_from_tag(reader.u8()?)?;
+            let timezone = reader.string()?;
+            if !timezone.is_empty() && parse_offset(&timezone).is_none() {
+                return malformed(format!("unsupported timezone {timezone}"));
+            }
+            DataType::Timestamp(unit, Some(timezone).filter(|tz| !tz.is_empty()))
+        }
+        20 => DataType::Duration(time_unit_from_tag(reader.u8()?)?),
+        21 => {
+            let (precision, scale) = (reader.u8()?, reader.u8()?);
+            if !(1..=MAX_PRECISION).contains(&precision) || scale > precision {
+                return malformed(format!(
+                    "bad decimal precision {precision} and scale {scale}"
+                ));
+            }
+            DataType::Decimal128(precision, scale)
+        }
+        22 => match reader.u32()? {
+            0 => return malformed("fixed size binary has no width"),
+            width => DataType::FixedSizeBinary(width as usize),
+        },
+        23..=25 if depth == MAX_NESTING => return malformed("types nest too deep"),
+        23 => DataType::List(Box::new(decode_nested_data_type(reader, depth + 1)?)),
+        25 => DataType::Map(Box::new(decode_nested_data_type(reader, depth + 1)?)),
+        24 => {
+            let num_fields = reader.u32()? as usize;
+            if num_fields == 0 {
+                return malformed("struct has no fields");
+            }
+            let mut fields = vec![];
+            for _ in 0..num_fields {
+                let name = reader.string()?;
+                let data_type = decode_nested_data_type(reader, depth + 1)?;
+                fields.push(Field::new(name, data_type));
+            }
+            DataType::Struct(fields)
+        }
+        26 => {
+            let num_labels = reader.u32()? as usize;
+            if code_width(num_labels).is_none() {
+                return malformed("enum has too many labels");
+            }
+            let mut labels = vec![];
+            for _ in 0..num_labels {
+                labels.push(reader.string()?);
+            }
+            let mut sorted: Vec<&String> = labels.iter().collect();
+            sorted.sort();
+            if sorted.windows(2).any(|w| w[0] == w[1]) {
+                return malformed("enum labels repeat");
+            }
+            DataType::Enum(labels)
+        }
+        _ => data_type_from_tag(tag)?,
+    })
+}
+
+fn time_unit_tag(unit: TimeUnit) -> u8 {
+    match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::Millisecond => 1,
+        TimeUnit::Microsecond => 2,
+        TimeUnit::Nanosecond => 3,
+    }
+}
+
+fn time_unit_from_tag(tag: u8) -> Result<TimeUnit, FileError> {
+    Ok(match tag {
+        0 => TimeUnit::Second,
+        1 => TimeUnit::Millisecond,
+        2 => TimeUnit::Microsecond,
+        3 => TimeUnit::Nanosecond,
+        _ => return malformed(format!("unknown time unit {tag}")),
+    })
+}
+
+fn data_type_from_tag(tag: u8) -> Result<DataType, FileError> {
+    Ok(match tag {
+        1 => DataType::Int8,
+        2 => DataType::Int16,
+        3 => DataType::Int32,
+        4 => DataType::UInt8,
+        5 => DataType::UInt16,
+        6 => DataType::UInt32,
+        7 => DataType::Float32,
+        8 => DataType::Float64,
+        9 => DataType::Text,
+        10 => DataType::Binary,
+        11 => DataType::Dictionary,
+        12 => DataType::Boolean,
+        13 => DataType::Int64,
+        14 => DataType::UInt64,
+        15 => DataType::LargeText,
+        16 => DataType::LargeBinary,
+        _ => return malformed(format!("unknown data type {tag}")),
+    })
+}
+
+fn encode_stats(out: &mut Vec<u8>, stats: &ColumnStats) {
+    put_u64(out, stats.null_count() as u64);
+    put_u64(out, stats.distinct_count());
+    put_u64(out, stats.byte_size() as u64);
+    encode_scalar(out, stats.min());
+    encode_scalar(out, stats.max());
+}
+
+fn decode_stats(reader: &mut ByteReader) -> Result<ColumnStats, FileError> {
+    let null_count = reader.u64()? as usize;
+    let distinct_count = reader.u64()?;
+    let byte_size = reader.u64()? as usize;
+    let min = decode_scalar(reader)?;
+    let max = decode_scalar(reader)?;
+    Ok(ColumnStats::new(
+        min,
+        max,
+        null_count,
+        distinct_count,
+        byte_size,
+    ))
+}
+
+// a scalar is its data type tag followed by the value, 0 means there is no value.
+fn encode_scalar(out: &mut Vec<u8>, scalar: Option<&Scalar>) {
+    let Some(scalar) = scalar else {
+        put_u8(out, 0);
+        return;
+    };
+    match scalar {
+        Scalar::Int8(v) => scalar_payload(out, DataType::Int8, &v.to_le_bytes()),
+        Scalar::Int16(v) => scalar_payload(out, DataType::Int16, &v.to_le_bytes()),
+        Scalar::Int32(v) => scalar_payload(out, DataType::Int32, &v.to_le_bytes()),
+        Scalar::Int64(v) => scalar_payload(out, DataType::Int64, &v.to_le_bytes()),
+        Scalar::UInt8(v) => scalar_payload(out, DataType::UInt8, &v.to_le_bytes()),
+        Scalar::UInt16(v) => scalar_payload(out, DataType::UInt16, &v.to_le_bytes()),
+        Scalar::UInt32(v) => scalar_payload(out, DataType::UInt32, &v.to_le_bytes()),
+        Scalar::UInt64(v) => scalar_payload(out, DataType::UInt64, &v.to_le_bytes()),
+        Scalar::Float32(v) => scalar_payload(out, DataType::Float32, &v.to_le_bytes()),
+        Scalar::Float64(v) => scalar_payload(out, DataType::Float64, &v.to_le_bytes()),
+        Scalar::Text(v) => scalar_payload(out, DataType::Text, v.as_bytes()),
+        Scalar::Binary(v) => scalar_payload(out, DataType::Binary, v),
+        Scalar::Boolean(v) => scalar_payload(out, DataType::Boolean, &[*v as u8]),
+        Scalar::Decimal128(v, scale) => {
+            let mut bytes = v.to_le_bytes().to_vec();
+            bytes.push(*scale);
+            scalar_payload(out, DataType::Decimal128(MAX_PRECISION, *scale), &bytes)
+        }
+    }
+}
+
+fn scalar_payload(out: &mut Vec<u8>, data_type: DataType, bytes: &[u8]) {
+    put_u8(out, data_type_tag(&data_type));
+    put_bytes(out, bytes);
+}
+
+fn decode_scalar(reader: &mut ByteReader) -> Result<Option<Scalar>, FileError> {
+    let tag = reader.u8()?;
+    if tag == 0 {
+        return Ok(None);
+    }
+    let bytes = reader.bytes()?;
+    fn le<const N: usize>(bytes: &[u8]) -> Result<[u8; N], FileError> {
+        bytes
+            .try_into()
+            .or_else(|_| malformed("scalar has the wrong size"))
+    }
+    // a decimal is followed by its scale
+    if tag == data_type_tag(&DataType::Decimal128(MAX_PRECISION, 0)) {
+        let [value @ .., scale] = le::<17>(bytes)?;
+        if scale > MAX_PRECISION {
+            return malformed("decimal scalar has a bad scale");
+        }
+        return Ok(Some(Scalar::Decimal128(i128::from_le_bytes(value), scale)));
+    }
+    let data_type = data_type_from_tag(tag)?;
+    Ok(Some(match data_type {
+        DataType::Int8 => Scalar::Int8(i8::from_le_bytes(le(bytes)?)),
+        DataType::Int16 => Scalar::Int16(i16::from_le_bytes(le(bytes)?)),
+        DataType::Int32 => Scalar::Int32(i32::from_le_bytes(le(bytes)?)),
+        DataType::Int64 => Scalar::Int64(i64::from_le_bytes(le(bytes)?)),
+        DataType::UInt8 => Scalar::UInt8(u8::from_le_bytes(le(bytes)?)),
+        DataType::UInt16 => Scalar::UInt16(u16::from_le_bytes(le(bytes)?)),
+        DataType::UInt32 => Scalar::UInt32(u32::from_le_bytes(le(bytes)?)),
+        DataType::UInt64 => Scalar::UInt64(u64::from_le_bytes(le(bytes)?)),
+        DataType::Float32 => Scalar::Float32(f32::from_le_bytes(le(bytes)?)),
+        DataType::Float64 => Scalar::Float64(f64::from_le_bytes(le(bytes)?)),
+        DataType::Text => match String::from_utf8(bytes.to_vec()) {
+            Ok(text) => Scalar::Text(text),
+            Err(_) => return malformed("text scalar is not utf-8"),
+        },
+        DataType::Binary => Scalar::Binary(bytes.to_vec()),
+        // large and temporal columns share the scalars of their storage
+        DataType::Dictionary
+        | DataType::LargeText
+        | DataType::LargeBinary
+        | DataType::Date32
+        | DataType::Time64(_)
+        | DataType::Timestamp(..)
+        | DataType::Duration(_)
+        | DataType::Decimal128(..)
+        | DataType::FixedSizeBinary(_)
+        | DataType::List(_)
+        | DataType::Struct(_)
+        | DataType::Map(_)
+        | DataType::Enum(_) => {
+            return malformed(format!("{} is not a scalar type", data_type.name()));
+        }
+        DataType::Boolean => match le::<1>(bytes)? {
+            [0] => Scalar::Boolean(false),
+            [1] => Scalar::Boolean(true),
+            _ => return malformed("boolean scalar is not 0 or 1"),
+        },
+    }))
+}
+
+// primitives
+
+pub(crate) fn put_u8(out: &mut Vec<u8>, value: u8) {
+    out.push(value);
+}
+
+pub(crate) fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+// length prefixed bytes
+pub(crate) fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
+
+// reads primitives back, running out of bytes is an error instead of a panic because the bytes
+// come from disk.
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> ByteReader<'a> {
+        ByteReader { bytes }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub(crate) fn take(&mut self, n: usize) -> Result<&'a [u8], FileError> {
+        if self.bytes.len() < n {
+            return malformed("unexpected end of bytes");
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, FileError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, FileError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, FileError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn bytes(&mut self) -> Result<&'a [u8], FileError> {
+        let length = self.u32()? as usize;
+        self.take(length)
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String, FileError> {
+        match std::str::from_utf8(self.bytes()?) {
+            Ok(s) => Ok(s.to_string()),
+            Err(_) => malformed("string is not utf-8"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::{
+        BufferKind, Encoding, EncodingPreference, FileError, FileReader, FileWriter, ScanReport,
+        WriteOptions, decode_footer, encode_footer, verify, write_database,
+        write_database_with_options,
+    };
+    use crate::boolean::BooleanArray;
+    use crate::codec::{self, Codec, Lz, Uncompressed};
+    use crate::database::{
+        Column, ColumnType, DataType, Database, Field, FieldType, Row, RowGroup,
+    };
+    use crate::decimal::Decimal128Array;
+    use crate::dictionary::DictionaryArray;
+    use crate::fixed::Fixed;
+    use crate::fixed_binary::FixedSizeBinaryArray;
+    use crate::predicate::{CompareOp, Predicate};
+    use crate::stats::Scalar;
+    use crate::temporal::{TemporalArray, TimeUnit};
+    use crate::variable::Variable;
+
+    pub(crate) fn people(row_group_size: usize, rows: usize) -> Database {
+        let mut db = Database::with_row_group_size(
+            vec![
+                Column::new("age".to_string(), ColumnType::Int32(Fixed::new())),
+                Column::new("name".to_string(), ColumnType::Text(Variable::new())),
+                Column::new("score".to_string(), ColumnType::Float64(Fixed::new())),
+            ],
+            row_group_size,
+        );
+        for i in 0..rows {
+            let name = format!("person {i}");
+            let score = if i % 7 == 0 {
+                FieldType::Null
+            } else {
+                FieldType::Float64(i as f64 / 2.0)
+            };
+            let row = Row::new(
+                vec!["age".to_string(), "name".to_string(), "score".to_string()],
+                vec![FieldType::Int32(i as i32), FieldType::Text(&name), score],
+            );
+            db.add_rows(&row);
+        }
+        db
+    }
+
+    #[test]
+    fn round_trip() {
+        let db = people(10, 25);
+        let bytes = write_database(&db, vec![]).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+
+        let metadata = reader.metadata();
+        assert_eq!(metadata.schema(), db.schema().as_slice());
+        assert_eq!(metadata.num_rows(), 25);
+        assert_eq!(metadata.row_groups().len(), 3);
+
+        let read = reader.read_database().unwrap();
+        assert_eq!(read.num_rows(), 25);
+        for (expected, actual) in db.row_groups().iter().zip(read.row_groups()) {
+            assert_eq!(expected.num_rows(), actual.num_rows());
+            for (a, b) in expected.columns().iter().zip(actual.columns()) {
+                assert_eq!(a.name(), b.name());
+                assert_eq!(a.stats(), b.stats());
+            }
+        }
+
+        let ColumnType::Text(names) = read.row_group(2).unwrap().column("name").unwrap().inner()
+        else {
+            panic!("expected text column");
+        };
+        assert_eq!(names.get(4), Some(Some("person 24")));
+        let ColumnType::Float64(scores) =
+            read.row_group(2).unwrap().column("score").unwrap().inner()
+        else {
+            panic!("expected float column");
+        };
+        assert_eq!(scores.get_records().nth(1), Some(None)); // row 21
+        assert_eq!(scores.get_records().nth(2), Some(Some(11.0)));
+    }
+
+    #[test]
+    fn stats_in_footer() {
+        let db = people(10, 25);
+        let bytes = write_database(&db, vec![]).unwrap();
+        let reader = FileReader::new(Cursor::new(bytes)).unwrap();
+
+        let age = reader.metadata().column_index("age").unwrap();
+        let stats = reader.metadata().row_groups()[1].columns()[age].stats();
+        assert_eq!(stats.min(), Some(&Scalar::Int32(10)));
+        assert_eq!(stats.max(), Some(&Scalar::Int32(19)));
+        assert_eq!(stats.null_count(), 0);
+        assert_eq!(stats.distinct_count(), 10);
+        // no nulls, so no validity bitmap
+        assert_eq!(stats.byte_size(), 10 * 4);
+
+        let score = reader.metadata().column_index("score").unwrap();
+        let stats = reader.metadata().row_groups()[0].columns()[score].stats();
+        assert_eq!(stats.null_count(), 2); // rows 0 and 7
+        assert_eq!(
+            reader.metadata().schema()[score].data_type(),
+            &DataType::Float64
+        );
+    }
+
+    #[test]
+    fn empty_database() {
+        let db = people(10, 0);
+        let bytes = write_database(&db, vec![]).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let read = reader.read_database().unwrap();
+        assert_eq!(read.num_rows(), 0);
+        assert_eq!(read.schema(), db.schema());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        let result = FileReader::new(Cursor::new(b"not a columnar file".to_vec()));
+        assert!(matches!(result, Err(FileError::Malformed(_))));
+
+        let mut bytes = write_database(&people(10, 5), vec![]).unwrap();
+        let len = bytes.len();
+        bytes[len - 5] = 0xFF; // footer length
+        let result = FileReader::new(Cursor::new(bytes));
+        assert!(matches!(result, Err(FileError::Malformed(_))));
+    }
+
+    fn ages(db: &Database) -> Vec<i32> {
+        db.row_groups()
+            .iter()
+            .flat_map(|rg| {
+                let ColumnType::Int32(ages) = rg.column("age").unwrap().inner() else {
+                    panic!("expected int32 column");
+                };
+                ages.get_records().flatten().collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scan_prunes_row_groups_and_pages() {
+        // 100 rows, row groups of 40 rows, pages of 10 rows, ages are 0..100
+        let db = people(40, 100);
+        let options = WriteOptions::new().with_page_size(10);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(
+            reader.metadata().row_groups()[0].columns()[0].pages().len(),
+            4
+        );
+
+        let (result, report) = reader
+            .scan(&Predicate::gt("age", Scalar::Int32(60)))
+            .unwrap();
+        assert_eq!(
+            report,
+            ScanReport {
+                row_groups_total: 3,
+                row_groups_skipped: 1,
+                row_groups_skipped_by_bloom_filter: 0,
+                pages_total: 10,
+                pages_skipped: 6,
+            }
+        );
+        assert_eq!(ages(&result), (60..100).collect::<Vec<_>>());
+        assert!(ages(&result).iter().any(|&age| age > 60));
+
+        let predicate = Predicate::between("age", Scalar::Int32(45), Scalar::Int32(52));
+        let (result, report) = reader.scan(&predicate).unwrap();
+        assert_eq!(report.row_groups_skipped, 2);
+        assert_eq!(report.pages_skipped, 8);
+        assert_eq!(ages(&result), (40..60).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn scan_text_and_nulls() {
+        let db = people(40, 100);
+        let options = WriteOptions::new().with_page_size(10);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+
+        let predicate = Predicate::eq("name", Scalar::Text("person 42".to_string()));
+        let (result, report) = reader.scan(&predicate).unwrap();
+        // names sort as text, "person 42" can only be in pages whose range covers it
+        assert!(report.pages_skipped > 0);
+        assert!(ages(&result).contains(&42));
+
+        // scores of rows divisible by 7 are null and every page of 10 rows has one of them
+        let (_, report) = reader.scan(&Predicate::is_null("score")).unwrap();
+        assert_eq!(report.pages_skipped, 0);
+
+        let predicate = Predicate::compare("score", CompareOp::LtEq, Scalar::Float64(-1.0));
+        let (result, report) = reader.scan(&predicate).unwrap();
+        assert_eq!(report.row_groups_skipped, 3);
+        assert_eq!(result.num_rows(), 0);
+
+        let unknown = reader.scan(&Predicate::is_null("height"));
+        assert!(matches!(unknown, Err(FileError::Schema(_))));
+    }
+
+    #[test]
+    fn bloom_filter_skips_point_lookups() {
+        // ids are spread over every row group so min/max can't rule anything out
+        let mut db = Database::with_row_group_size(
+            vec![Column::new(
+                "id".to_string(),
+                ColumnType::Text(Variable::new()),
+            )],
+            50,
+        );
+        for i in 0..200 {
+            let id = format!("{:08x}", (i as u32).wrapping_mul(2654435761));
+            let id = if i % 50 == 0 { format!("0{i}") } else { id };
+            let id = if i % 50 == 1 { format!("z{i}") } else { id };
+            db.add_rows(&Row::new(
+                vec!["id".to_string()],
+                vec![FieldType::Text(&id)],
+            ));
+        }
+        let wanted = format!("{:08x}", 123u32.wrapping_mul(2654435761));
+
+        let plain = write_database(&db, vec![]).unwrap();
+        let mut reader = FileReader::new(Cursor::new(plain)).unwrap();
+        let predicate = Predicate::eq("id", Scalar::Text(wanted.clone()));
+        assert_eq!(
+            reader.plan(&predicate).unwrap().report().row_groups_skipped,
+            0
+        );
+
+        let options = WriteOptions::new().with_bloom_filter("id");
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        assert!(reader.read_bloom_filter(0, 0).unwrap().is_some());
+
+        let (result, report) = reader.scan(&predicate).unwrap();
+        assert_eq!(report.row_groups_skipped, 3);
+        assert_eq!(report.row_groups_skipped_by_bloom_filter, 3);
+        assert_eq!(result.num_rows(), 50);
+
+        let other = format!("{:08x}", 7u32.wrapping_mul(2654435761));
+        let predicate = Predicate::is_in(
+            "id",
+            vec![
+                Scalar::Text(wanted),
+                Scalar::Text(other),
+                Scalar::Text("nope".into()),
+            ],
+        );
+        let (result, report) = reader.scan(&predicate).unwrap();
+        assert_eq!(report.row_groups_skipped, 2);
+        assert_eq!(result.num_rows(), 100);
+    }
+
+    #[test]
+    fn detects_corrupted_buffers() {
+        let bytes = write_database(&people(10, 25), vec![]).unwrap();
+        let reader = FileReader::new(Cursor::new(bytes.clone())).unwrap();
+        let rg = &reader.metadata().row_groups()[1];
+        let buffer = |column: usize, kind| {
+            let buffers = rg.columns()[column].pages()[0].buffers();
+            *buffers.iter().find(|b| b.kind() == kind).unwrap()
+        };
+        let age_data = buffer(0, BufferKind::Data);
+        let name_offsets = buffer(1, BufferKind::Offsets);
+        assert_eq!(age_data.kind(), BufferKind::Data);
+        assert_eq!(name_offsets.kind(), BufferKind::Offsets);
+
+        let mut damaged = bytes.clone();
+        damaged[age_data.offset() as usize + 3] ^= 0x10;
+        damaged[name_offsets.offset() as usize] ^= 0x01;
+
+        let mut reader = FileReader::new(Cursor::new(damaged.clone())).unwrap();
+        // other row groups are still readable
+        assert_eq!(reader.read_row_group(0).unwrap().num_rows(), 10);
+        let Err(FileError::Corrupted(corruption)) = reader.read_row_group(1) else {
+            panic!("expected a corruption error");
+        };
+        assert_eq!(corruption.column.as_deref(), Some("age"));
+        assert_eq!(corruption.row_group, Some(1));
+        assert_eq!(corruption.page, Some(0));
+        assert_eq!(corruption.buffer, "data");
+        assert_eq!(corruption.offset, age_data.offset());
+        assert!(corruption.to_string().contains("data buffer of column age"));
+
+        // verify doesn't stop at the first damaged buffer
+        let corruptions = reader.verify().unwrap();
+        assert_eq!(corruptions.len(), 2);
+        assert_eq!(corruptions[1].column.as_deref(), Some("name"));
+        assert_eq!(corruptions[1].buffer, "offsets");
+
+        assert!(verify(Cursor::new(bytes)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn buffers_past_the_footer_are_rejected() {
+        let bytes = write_database(&people(10, 25), vec![]).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        // a crafted footer could claim any length, it is refused before anything is allocated
+        reader.metadata.row_groups[0].columns[0].pages[0].buffers[0].length = 1 << 60;
+        let Err(FileError::Malformed(message)) = reader.read_row_group(0) else {
+            panic!("expected a malformed file");
+        };
+        assert!(message.ends_with("buffer of column age lies outside the file"));
+    }
+
+    #[test]
+    fn page_counts_are_bounded_by_the_footer() {
+        let bytes = write_database(&people(1, 25), vec![]).unwrap();
+        let reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let mut metadata = reader.metadata().clone();
+        metadata.page_size = 1;
+        metadata.row_groups[0].num_rows = u32::MAX as usize;
+        let mut footer = encode_footer(&metadata);
+        // the page count is the first byte that differs from a footer without pages
+        let mut no_pages = metadata.clone();
+        no_pages.row_groups[0].columns[0].pages.clear();
+        let at = footer
+            .iter()
+            .zip(&encode_footer(&no_pages))
+            .position(|(a, b)| a != b)
+            .unwrap();
+        footer[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            decode_footer(&footer),
+            Err(FileError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn fixed_binary_sizes_are_checked() {
+        let buffers = vec![(BufferKind::Data, vec![0; 4])];
+        assert!(matches!(
+            super::decode_fixed_binary(u32::MAX as usize, 1 << 40, buffers),
+            Err(FileError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn detects_corrupted_footer() {
+        let mut bytes = write_database(&people(10, 25), vec![]).unwrap();
+        let len = bytes.len();
+        bytes[len - 20] ^= 0x04; // inside the footer
+        let result = FileReader::new(Cursor::new(bytes.clone()));
+        assert!(matches!(result, Err(FileError::Corrupted(ref c)) if c.buffer == "footer"));
+
+        let corruptions = verify(Cursor::new(bytes)).unwrap();
+        assert_eq!(corruptions.len(), 1);
+        assert_eq!(corruptions[0].column, None);
+    }
+
+    #[test]
+    fn dictionary_round_trip() {
+        let mut low = DictionaryArray::new();
+        let mut high = DictionaryArray::new().with_max_size(16);
+        for i in 0..100 {
+            if i % 9 == 0 {
+                low.add_null();
+                high.add_null();
+            } else {
+                low.add(["de", "fr", "nl", "se"][i % 4]);
+                high.add(&format!("user {i}"));
+            }
+        }
+        assert!(!high.is_dictionary());
+        let expected: Vec<Option<String>> = (0..100)
+            .map(|i| low.get(i).flatten().map(str::to_string))
+            .chain((0..100).map(|i| high.get(i).flatten().map(str::to_string)))
+            .collect();
+        let row_group = |c| {
+            RowGroup::new(
+                vec![Column::new("country".into(), ColumnType::Dictionary(c))],
+                100,
+            )
+        };
+        let schema = [Field::new("country".into(), DataType::Dictionary)];
+        let db = Database::from_row_groups(&schema, vec![row_group(low), row_group(high)], 100);
+
+        let options = WriteOptions::new().with_page_size(40);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let encodings: Vec<Encoding> = reader
+            .metadata()
+            .row_groups()
+            .iter()
+            .map(|rg| rg.columns()[0].pages()[0].encoding())
+            .collect();
+        assert_eq!(encodings, vec![Encoding::Dictionary, Encoding::Plain]);
+
+        let read = reader.read_database().unwrap();
+        let mut values = vec![];
+        for rg in read.row_groups() {
+            let ColumnType::Dictionary(c) = rg.columns()[0].inner() else {
+                panic!("expected dictionary column");
+            };
+            values.extend((0..c.len()).map(|i| c.get(i).flatten().map(str::to_string)));
+        }
+        assert_eq!(values, expected);
+
+        let (result, report) = reader
+            .scan(&Predicate::eq("country", Scalar::Text("user 50".into())))
+            .unwrap();
+        assert_eq!(report.row_groups_skipped, 1);
+        let ColumnType::Dictionary(c) = result.row_group(0).unwrap().columns()[0].inner() else {
+            panic!("expected dictionary column");
+        };
+        assert!((0..c.len()).any(|i| c.get(i) == Some(Some("user 50"))));
+    }
+
+    #[test]
+    fn run_length_pages() {
+        let mut db = Database::new(vec![
+            Column::new("day".to_string(), ColumnType::UInt16(Fixed::new())),
+            Column::new("value".to_string(), ColumnType::Int32(Fixed::new())),
+        ]);
+        for i in 0..10_000 {
+            let day = if i == 5000 {
+                FieldType::Null
+            } else {
+                FieldType::UInt16(i / 1000)
+            };
+            let row = Row::new(
+                vec!["day".to_string(), "value".to_string()],
+                vec![day, FieldType::Int32((i as i32).wrapping_mul(-1640531535))],
+            );
+            db.add_rows(&row);
+        }
+
+        let bytes = write_database(&db, vec![]).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let chunks = reader.metadata().row_groups()[0].columns();
+        let day = &chunks[0].pages()[0];
+        assert_eq!(day.encoding(), Encoding::RunLength);
+        assert!(day.buffers().iter().map(|b| b.length()).sum::<u64>() < 100);
+        let value = &chunks[1].pages()[0];
+        assert_eq!(value.encoding(), Encoding::Plain);
+        // no nulls, so no validity buffer
+        assert_eq!(value.buffers().len(), 1);
+        assert_eq!(value.buffers()[0].kind(), BufferKind::Data);
+
+        let read = reader.read_database().unwrap();
+        for name in ["day", "value"] {
+            let original = db.row_group(0).unwrap().column(name).unwrap().inner();
+            let read = read.row_group(0).unwrap().column(name).unwrap().inner();
+            match (original, read) {
+                (ColumnType::UInt16(a), ColumnType::UInt16(b)) => {
+                    assert!(a.get_records().eq(b.get_records()))
+                }
+                (ColumnType::Int32(a), ColumnType::Int32(b)) => {
+                    assert!(a.get_records().eq(b.get_records()))
+                }
+                _ => panic!("column types changed"),
+            }
+        }
+    }
+
+    #[test]
+    fn delta_pages() {
+        let mut db = Database::new(vec![
+            Column::new("id".to_string(), ColumnType::UInt32(Fixed::new())),
+            Column::new("time".to_string(), ColumnType::Int32(Fixed::new())),
+        ]);
+        for i in 0..20_000 {
+            let time = if i % 100 == 99 {
+                FieldType::Null
+            } else {
+                FieldType::Int32(1_700_000_000 + i * 15 + i % 4)
+            };
+            let row = Row::new(
+                vec!["id".to_string(), "time".to_string()],
+                vec![FieldType::UInt32(4_000_000_000 + i as u32), time],
+            );
+            db.add_rows(&row);
+        }
+
+        let bytes = write_database(&db, vec![]).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        for chunk in reader.metadata().row_groups()[0].columns() {
+            let page = &chunk.pages()[0];
+            assert_eq!(page.encoding(), Encoding::Delta);
+            // 4 bytes per value when plain
+            let size: u64 = page.buffers().iter().map(|b| b.length()).sum();
+            assert!(size < 8192, "{size}");
+        }
+
+        let read = reader.read_database().unwrap();
+        let rg = |db: &Database| {
+            let ColumnType::UInt32(ids) = db.row_group(0).unwrap().columns()[0].inner() else {
+                panic!("expected uint32 column");
+            };
+            let ColumnType::Int32(times) = db.row_group(0).unwrap().columns()[1].inner() else {
+                panic!("expected int32 column");
+            };
+            (
+                ids.get_records().collect::<Vec<_>>(),
+                times.get_records().collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(rg(&read), rg(&db));
+    }
+
+    #[test]
+    fn gorilla_float_columns() {
+        let mut db = Database::new(vec![
+            Column::new("temperature".to_string(), ColumnType::Float64(Fixed::new())),
+            Column::new("pressure".to_string(), ColumnType::Float32(Fixed::new())),
+        ]);
+        for i in 0..5000 {
+            let temperature = match i {
+                10 => FieldType::Null,
+                11 => FieldType::Float64(f64::from_bits(0x7ff8_0000_0000_beef)),
+                12 => FieldType::Float64(-0.0),
+                _ => FieldType::Float64((200.0 + (i as f64 / 50.0).sin() * 30.0).round() / 10.0),
+            };
+            let pressure = FieldType::Float32(1013.0 + ((i / 40) % 8) as f32 * 0.25);
+            let row = Row::new(
+                vec!["temperature".to_string(), "pressure".to_string()],
+                vec![temperature, pressure],
+            );
+            db.add_rows(&row);
+        }
+
+        let options = WriteOptions::new()
+            .with_encoding("temperature", Encoding::Plain)
+            .with_encoding("pressure", Encoding::Plain);
+        let plain = write_database_with_options(&db, vec![], options).unwrap();
+        let options = WriteOptions::new()
+            .with_encoding("temperature", Encoding::Gorilla)
+            .with_encoding("pressure", Encoding::Gorilla);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        assert!(
+            bytes.len() * 3 < plain.len(),
+            "{} vs {}",
+            bytes.len(),
+            plain.len()
+        );
+
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let chunk = &reader.metadata().row_groups()[0].columns()[0];
+        assert_eq!(chunk.pages()[0].encoding(), Encoding::Gorilla);
+        let read = reader.read_database().unwrap();
+        let column = |db: &Database| {
+            let ColumnType::Float64(c) = db.row_group(0).unwrap().columns()[0].inner() else {
+                panic!("expected float64 column");
+            };
+            c.get_records()
+                .map(|v| v.map(f64::to_bits))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(column(&read), column(&db));
+        assert_eq!(column(&read)[12], Some((-0.0f64).to_bits()));
+
+        let options = WriteOptions::new().with_encoding("temperature", Encoding::Delta);
+        let result = write_database_with_options(&db, vec![], options);
+        assert!(matches!(result, Err(FileError::Schema(_))));
+    }
+
+    #[test]
+    fn compressed_columns() {
+        let mut db = Database::new(vec![
+            Column::new("id".to_string(), ColumnType::Int32(Fixed::new())),
+            Column::new("log".to_string(), ColumnType::Text(Variable::new())),
+        ]);
+        let lines: Vec<String> = (0..3000)
+            .map(|i| format!("GET /api/users/{} 200 {}ms", i % 40, i % 7))
+            .collect();
+        for (i, line) in lines.iter().enumerate() {
+            let row = Row::new(
+                vec!["id".to_string(), "log".to_string()],
+                vec![FieldType::Int32(i as i32), FieldType::Text(line)],
+            );
+            db.add_rows(&row);
+        }
+
+        let plain = write_database_with_options(
+            &db,
+            vec![],
+            WriteOptions::new().with_encoding("log", Encoding::Plain),
+        )
+        .unwrap();
+        // plain, so the codec has to find the repeats on its own
+        let options = WriteOptions::new()
+            .with_encoding("log", Encoding::Plain)
+            .with_codec("log", &Lz);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        assert!(
+            bytes.len() * 2 < plain.len(),
+            "{} vs {}",
+            bytes.len(),
+            plain.len()
+        );
+
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let chunks = reader.metadata().row_groups()[0].columns();
+        let data = chunks[1].pages()[0].buffers()[1];
+        assert_eq!(data.kind(), BufferKind::Data);
+        assert_eq!(data.codec(), Lz.id());
+        assert!(data.length() < data.uncompressed_length());
+        assert_eq!(chunks[0].pages()[0].buffers()[0].codec(), Uncompressed.id());
+
+        let read = reader.read_database().unwrap();
+        let ColumnType::Text(logs) = read.row_group(0).unwrap().columns()[1].inner() else {
+            panic!("expected text column");
+        };
+        let read_lines: Vec<&str> = (0..logs.len())
+            .map(|i| logs.get(i).flatten().unwrap())
+            .collect();
+        assert_eq!(read_lines, lines);
+        assert_eq!(reader.verify().unwrap(), vec![]);
+
+        let mut unknown = data;
+        unknown.codec = 250;
+        assert!(matches!(
+            super::decompress(unknown, vec![0; 4]),
+            Err(FileError::Malformed(_))
+        ));
+    }
+
+    // a codec only this test knows, it flips every byte
+    #[derive(Debug)]
+    struct Inverted(u8);
+
+    impl Codec for Inverted {
+        fn id(&self) -> u8 {
+            self.0
+        }
+
+        fn name(&self) -> &'static str {
+            "inverted"
+        }
+
+        fn compress(&self, input: &[u8]) -> Vec<u8> {
+            input.iter().map(|b| !b).collect()
+        }
+
+        fn decompress(&self, input: &[u8], _: usize) -> Result<Vec<u8>, &'static str> {
+            Ok(self.compress(input))
+        }
+    }
+
+    #[test]
+    fn registered_codecs_are_read_back() {
+        static INVERTED: Inverted = Inverted(41);
+        codec::register(&INVERTED).unwrap();
+        let mut db = Database::new(vec![Column::new(
+            "id".to_string(),
+            ColumnType::Int32(Fixed::new()),
+        )]);
+        for i in 0..100 {
+            db.add_rows(&Row::new(vec!["id".to_string()], vec![FieldType::Int32(i)]));
+        }
+        let options = WriteOptions::new().with_codec("id", &INVERTED);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let read = FileReader::new(Cursor::new(bytes))
+            .unwrap()
+            .read_database()
+            .unwrap();
+        let ColumnType::Int32(ids) = read.row_group(0).unwrap().columns()[0].inner() else {
+            panic!("expected int32 column");
+        };
+        assert_eq!(ids.data(), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "codec inverted isn't registered")]
+    fn unknown_codecs_are_rejected_when_writing() {
+        static INVERTED: Inverted = Inverted(42);
+        WriteOptions::new().with_codec("id", &INVERTED);
+    }
+
+    #[test]
+    fn encoding_selection() {
+        let mut db = Database::new(vec![
+            Column::new("status".to_string(), ColumnType::Text(Variable::new())),
+            Column::new("temperature".to_string(), ColumnType::Float64(Fixed::new())),
+        ]);
+        let statuses = ["ok", "warning", "critical"];
+        for i in 0..10_000 {
+            let status = match i % 97 {
+                0 => FieldType::Null,
+                n => FieldType::Text(statuses[n % 3]),
+            };
+            let drift = ((i / 60) as f64 * 0.05).sin() * 4.0;
+            let row = Row::new(
+                vec!["status".to_string(), "temperature".to_string()],
+                vec![
+                    status,
+                    FieldType::Float64((215.0 + drift * 10.0 + (i % 3) as f64).round() / 10.0),
+                ],
+            );
+            db.add_rows(&row);
+        }
+
+        let write = |preference| {
+            let options = WriteOptions::new().with_encoding_preference(preference);
+            let mut writer =
+                FileWriter::with_options(vec![], db.schema(), db.row_group_size(), options)
+                    .unwrap();
+            writer.write_row_group(db.row_group(0).unwrap()).unwrap();
+            let report = writer.encoding_report().to_vec();
+            (report, writer.finish().unwrap())
+        };
+
+        let (report, bytes) = write(EncodingPreference::Smallest);
+        assert_eq!(report[0].column, "status");
+        assert_eq!(report[0].encoding, Encoding::Dictionary);
+        assert_eq!(
+            report[1].encoding,
+            Encoding::Gorilla,
+            "{:?}",
+            report[1].estimates
+        );
+        assert_eq!(report[1].reason(), "smallest estimate");
+        let estimates: Vec<Encoding> = report[1].estimates.iter().map(|&(e, _)| e).collect();
+        assert_eq!(estimates.len(), 3);
+        assert!(!estimates.contains(&Encoding::Dictionary));
+
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let chunks = reader.metadata().row_groups()[0].columns();
+        assert_eq!(chunks[0].encoding(), Encoding::Dictionary);
+        assert_eq!(chunks[1].encoding(), Encoding::Gorilla);
+        assert_eq!(chunks[0].pages()[1].encoding(), Encoding::Dictionary);
+        let read = reader.read_database().unwrap();
+        let (ColumnType::Text(a), ColumnType::Text(b)) = (
+            db.row_group(0).unwrap().columns()[0].inner(),
+            read.row_group(0).unwrap().columns()[0].inner(),
+        ) else {
+            panic!("expected text columns");
+        };
+        assert_eq!(b.len(), a.len());
+        assert!((0..a.len()).all(|i| a.get(i) == b.get(i)));
+
+        // gorilla has to save 50% per step over the cheaper encodings now
+        let (report, _) = write(EncodingPreference::FastDecode);
+        assert_ne!(report[1].encoding, Encoding::Gorilla);
+        assert_eq!(report[1].estimates[0].0, Encoding::Gorilla);
+        assert!(
+            report[1].reason().starts_with("gorilla is"),
+            "{}",
+            report[1].reason()
+        );
+
+        let options = WriteOptions::new().with_encoding("temperature", Encoding::Plain);
+        let mut writer =
+            FileWriter::with_options(vec![], db.schema(), db.row_group_size(), options).unwrap();
+        writer.write_row_group(db.row_group(0).unwrap()).unwrap();
+        assert!(writer.encoding_report()[1].forced);
+        assert_eq!(
+            writer.encoding_report()[1].reason(),
+            "forced by write options"
+        );
+    }
+
+    #[test]
+    fn boolean_columns() {
+        let mut db = Database::with_row_group_size(
+            vec![
+                Column::new(
+                    "active".to_string(),
+                    ColumnType::Boolean(BooleanArray::new()),
+                ),
+                Column::new(
+                    "admin".to_string(),
+                    ColumnType::Boolean(BooleanArray::new()),
+                ),
+            ],
+            100,
+        );
+        for i in 0..250 {
+            let active = if i % 7 == 0 {
+                FieldType::Null
+            } else {
+                FieldType::Boolean(i % 3 == 0)
+            };
+            let row = Row::new(
+                vec!["active".to_string(), "admin".to_string()],
+                vec![active, FieldType::Boolean(i >= 200)],
+            );
+            db.add_rows(&row);
+        }
+
+        let bytes = write_database(&db, vec![]).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let admin = &reader.metadata().row_groups()[2].columns()[1];
+        assert_eq!(admin.stats().min(), Some(&Scalar::Boolean(true)));
+        assert_eq!(admin.stats().max(), Some(&Scalar::Boolean(true)));
+        // a bit per value and no validity
+        assert_eq!(admin.pages()[0].buffers().len(), 1);
+        assert_eq!(admin.pages()[0].buffers()[0].length(), 7);
+
+        let read = reader.read_database().unwrap();
+        for (a, b) in db.row_groups().iter().zip(read.row_groups()) {
+            for (a, b) in a.columns().iter().zip(b.columns()) {
+                let (ColumnType::Boolean(a), ColumnType::Boolean(b)) = (a.inner(), b.inner())
+                else {
+                    panic!("expected boolean columns");
+                };
+                assert!(a.get_records().eq(b.get_records()));
+            }
+        }
+
+        let (result, report) = reader
+            .scan(&Predicate::eq("admin", Scalar::Boolean(true)))
+            .unwrap();
+        assert_eq!(report.row_groups_skipped, 2);
+        assert_eq!(result.num_rows(), 50);
+    }
+
+    #[test]
+    fn wide_columns() {
+        let mut db = Database::new(vec![
+            Column::new("ts".to_string(), ColumnType::Int64(Fixed::new())),
+            Column::new("id".to_string(), ColumnType::UInt64(Fixed::new())),
+            Column::new("name".to_string(), ColumnType::Text(Variable::new())),
+            Column::new("body".to_string(), ColumnType::LargeText(Variable::new())),
+        ]);
+        let names = ["text", "with", "wide", "offsets"];
+        for i in 0..300u64 {
+            // epoch nanos and ids that don't fit 32 bits
+            let body = format!("body {i}");
+            let body = if i % 5 == 0 {
+                FieldType::Null
+            } else {
+                FieldType::Text(&body)
+            };
+            let row = Row::new(
+                vec![
+                    "ts".to_string(),
+                    "id".to_string(),
+                    "name".to_string(),
+                    "body".to_string(),
+                ],
+                vec![
+                    FieldType::Int64(1_700_000_000_000_000_000 + i as i64 * 1_000_003),
+                    FieldType::UInt64(u64::MAX - i * 7),
+                    FieldType::Text(names[i as usize % 4]),
+                    body,
+                ],
+            );
+            db.add_rows(&row);
+        }
+
+        let options = WriteOptions::new()
+            .with_encoding("name", Encoding::Plain)
+            .with_encoding("body", Encoding::Plain)
+            .with_encoding("ts", Encoding::Delta);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let page_rows = reader.metadata().page_size().min(300);
+        let chunks = reader.metadata().row_groups()[0].columns();
+        let offsets = |chunk: usize| {
+            chunks[chunk].pages()[0]
+                .buffers()
+                .iter()
+                .find(|b| b.kind() == BufferKind::Offsets)
+                .unwrap()
+                .length() as usize
+        };
+        assert_eq!(offsets(2), (page_rows + 1) * 4);
+        assert_eq!(offsets(3), (page_rows + 1) * 8);
+        assert_eq!(chunks[1].stats().max(), Some(&Scalar::UInt64(u64::MAX)));
+        assert_eq!(
+            chunks[0].stats().min(),
+            Some(&Scalar::Int64(1_700_000_000_000_000_000))
+        );
+
+        let read = reader.read_database().unwrap();
+        let (a, b) = (db.row_group(0).unwrap(), read.row_group(0).unwrap());
+        match (a.columns()[0].inner(), b.columns()[0].inner()) {
+            (ColumnType::Int64(a), ColumnType::Int64(b)) => {
+                assert!(a.get_records().eq(b.get_records()))
+            }
+            _ => panic!("expected int64 columns"),
+        }
+        match (a.columns()[1].inner(), b.columns()[1].inner()) {
+            (ColumnType::UInt64(a), ColumnType::UInt64(b)) => {
+                assert!(a.get_records().eq(b.get_records()))
+            }
+            _ => panic!("expected uint64 columns"),
+        }
+        let ColumnType::LargeText(body) = b.columns()[3].inner() else {
+            panic!("expected large text column");
+        };
+        assert_eq!(body.get(0), Some(None));
+        assert_eq!(body.get(299), Some(Some("body 299")));
+        assert_eq!(body.null_count(), 60);
+    }
+
+    #[test]
+    fn temporal_columns() {
+        let timestamp = DataType::Timestamp(TimeUnit::Millisecond, Some("+02:00".to_string()));
+        let mut db = Database::new(vec![
+            Column::new(
+                "day".to_string(),
+                ColumnType::Date32(TemporalArray::new(DataType::Date32)),
+            ),
+            Column::new(
+                "at".to_string(),
+                ColumnType::Timestamp(TemporalArray::new(timestamp.clone())),
+            ),
+            Column::new(
+                "took".to_string(),
+                ColumnType::Duration(TemporalArray::new(DataType::Duration(TimeUnit::Second))),
+            ),
+        ]);
+        for i in 0..500 {
+            let day = format!("2024-01-{:02}", i % 28 + 1);
+            let at = format!("{day}T{:02}:{:02}:00.250", i % 24, i % 60);
+            let took = format!("PT{}M{}S", i % 90, i % 60);
+            let took = if i % 9 == 0 {
+                FieldType::Null
+            } else {
+                FieldType::Text(&took)
+            };
+            let row = Row::new(
+                vec!["day".to_string(), "at".to_string(), "took".to_string()],
+                vec![FieldType::Text(&day), FieldType::Text(&at), took],
+            );
+            db.add_rows(&row);
+        }
+
+        let bytes = write_database(&db, vec![]).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        let schema = reader.metadata().schema();
+        assert_eq!(schema[0].data_type(), &DataType::Date32);
+        assert_eq!(schema[1].data_type(), &timestamp);
+        assert_eq!(schema[2].data_type(), &DataType::Duration(TimeUnit::Second));
+        // stats are the stored integers
+        let chunk = &reader.metadata().row_groups()[0].columns()[0];
+        assert_eq!(chunk.stats().min(), Some(&Scalar::Int32(19723)));
+
+        let read = reader.read_database().unwrap();
+        let format = |db: &Database, column: usize| -> Vec<Option<String>> {
+            let c = &db.row_group(0).unwrap().columns()[column];
+            match c.inner() {
+                ColumnType::Date32(c) => (0..c.len()).map(|i| c.format(i).unwrap()).collect(),
+                ColumnType::Timestamp(c) | ColumnType::Duration(c) => {
+                    (0..c.len()).map(|i| c.format(i).unwrap()).collect()
+                }
+                _ => panic!("expected temporal column"),
+            }
+        };
+        for column in 0..3 {
+            assert_eq!(format(&db, column), format(&read, column));
+        }
+        assert_eq!(
+            format(&read, 1)[25],
+            Some("2024-01-26T01:25:00.250+02:00".to_string())
+        );
+        assert_eq!(format(&read, 2)[61], Some("PT1H1M1S".to_string()));
+    }
+
+    #[test]
+    fn decimal_columns() {
+        let mut db = Database::with_row_group_size(
+            vec![Column::new(
+                "price".to_string(),
+                ColumnType::Decimal128(Decimal128Array::new(12, 2)),
+            )],
+            100,
+        );
+        for i in 0..300 {
+            let price = format!("{}.{:02}", i * 3, i % 100);
+            let price = if i % 11 == 0 {
+                FieldType::Null
+            } else {
+                FieldType::Text(&price)
+            };
+            db.add_rows(&Row::new(vec!["price".to_string()], vec![price]));
+        }
+
+        let options = WriteOptions::new().with_bloom_filter("price");
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(
+            reader.metadata().schema()[0].data_type(),
+            &DataType::Decimal128(12, 2)
+        );
+        let stats = reader.metadata().row_groups()[1].columns()[0].stats();
+        assert_eq!(stats.min(), Some(&Scalar::Decimal128(30000, 2)));
+        assert_eq!(stats.max(), Some(&Scalar::Decimal128(59799, 2)));
+
+        let read = reader.read_database().unwrap();
+        let ColumnType::Decimal128(prices) = read.row_group(2).unwrap().columns()[0].inner() else {
+            panic!("expected decimal column");
+        };
+        assert_eq!(prices.format(1), Some(Some("603.01".to_string())));
+        assert_eq!(prices.sum(), Ok(Some(6_806_373)));
+
+        // 603.010 is the same value in another scale, the bloom filter must still find it
+        let (result, report) = reader
+            .scan(&Predicate::eq("price", Scalar::Decimal128(603010, 3)))
+            .unwrap();
+        assert_eq!(report.row_groups_skipped, 2);
+        assert_eq!(result.num_rows(), 100);
+        let (_, report) = reader
+            .scan(&Predicate::eq("price", Scalar::Decimal128(603015, 3)))
+            .unwrap();
+        assert_eq!(report.row_groups_skipped, 3);
+    }
+
+    #[test]
+    fn fixed_size_binary_columns() {
+        let mut db = Database::with_row_group_size(
+            vec![Column::new(
+                "id".to_string(),
+                ColumnType::FixedSizeBinary(FixedSizeBinaryArray::new(16)),
+            )],
+            64,
+        );
+        for i in 0..200u32 {
+            let id = format!(
+                "{:08x}-0000-4000-8000-{:012x}",
+                i.wrapping_mul(2654435761),
+                i
+            );
+            let id = if i % 13 == 0 {
+                FieldType::Null
+            } else {
+                FieldType::Text(&id)
+            };
+            db.add_rows(&Row::new(vec!["id".to_string()], vec![id]));
+        }
+
+        let options = WriteOptions::new().with_bloom_filter("id");
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(
+            reader.metadata().schema()[0].data_type(),
+            &DataType::FixedSizeBinary(16)
+        );
+        let chunk = &reader.metadata().row_groups()[0].columns()[0];
+        assert_eq!(chunk.encoding(), Encoding::Plain);
+        let data = chunk.pages()[0]
+            .buffers()
+            .iter()
+            .find(|b| b.kind() == BufferKind::Data)
+            .unwrap();
+        assert_eq!(data.length() as usize, 64 * 16);
+
+        let read = reader.read_database().unwrap();
+        for (a, b) in db.row_groups().iter().zip(read.row_groups()) {
+            let (ColumnType::FixedSizeBinary(a), ColumnType::FixedSizeBinary(b)) =
+                (a.columns()[0].inner(), b.columns()[0].inner())
+            else {
+                panic!("expected fixed size binary columns");
+            };
+            assert_eq!(a.data(), b.data());
+            assert_eq!(a.validity(), b.validity());
+        }
+
+        let ColumnType::FixedSizeBinary(ids) = read.row_group(2).unwrap().columns()[0].inner()
+        else {
+            panic!("expected fixed size binary column");
+        };
+        let wanted = ids.get(5).flatten().unwrap().to_vec();
+        let (result, report) = reader
+            .scan(&Predicate::eq("id", Scalar::Binary(wanted)))
+            .unwrap();
+        assert_eq!(report.row_groups_skipped, 3);
+        assert_eq!(result.num_rows(), 64);
+    }
+
+    #[test]
+    fn list_columns() {
+        let tags_type = DataType::List(Box::new(DataType::Text));
+        let matrix_type = DataType::List(Box::new(DataType::List(Box::new(DataType::Int32))));
+        let mut db = Database::with_row_group_size(
+            vec![
+                Column::new("tags".to_string(), ColumnType::empty(&tags_type, 0)),
+                Column::new("matrix".to_string(), ColumnType::empty(&matrix_type, 0)),
+            ],
+            64,
+        );
+        let header = vec!["tags".to_string(), "matrix".to_string()];
+        for i in 0..200 {
+            let names: Vec<String> = (0..i % 4).map(|j| format!("tag-{}", i + j)).collect();
+            let mut tags: Vec<FieldType> = names.iter().map(|n| FieldType::Text(n)).collect();
+            if i % 7 == 0 {
+                tags.push(FieldType::Null);
+            }
+            let rows: Vec<FieldType> = (0..i % 3).map(|j| FieldType::Int32(i * j)).collect();
+            let matrix = vec![FieldType::List(rows), FieldType::List(vec![])];
+            let row = if i % 11 == 0 {
+                vec![FieldType::Null, FieldType::Null]
+            } else {
+                vec![FieldType::List(tags), FieldType::List(matrix)]
+            };
+            db.add_rows(&Row::new(header.clone(), row));
+        }
+
+        let options = WriteOptions::new()
+            .with_page_size(16)
+            .with_bloom_filter("tags");
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.metadata().schema()[0].data_type(), &tags_type);
+        assert_eq!(reader.metadata().schema()[1].data_type(), &matrix_type);
+        let chunk = &reader.metadata().row_groups()[0].columns()[0];
+        assert_eq!(chunk.encoding(), Encoding::Plain);
+
+        let read = reader.read_database().unwrap();
+        for (a, b) in db.row_groups().iter().zip(read.row_groups()) {
+            let (ColumnType::List(a), ColumnType::List(b)) =
+                (a.columns()[0].inner(), b.columns()[0].inner())
+            else {
+                panic!("expected list columns");
+            };
+            assert_eq!(a.offsets(), b.offsets());
+            assert_eq!(a.validity(), b.validity());
+            let (ColumnType::Text(a), ColumnType::Text(b)) = (a.values(), b.values()) else {
+                panic!("expected text values");
+            };
+            assert_eq!(a.offsets(), b.offsets());
+            assert_eq!(a.data(), b.data());
+            assert_eq!(a.validity(), b.validity());
+        }
+
+        let ColumnType::List(matrix) = read.row_group(1).unwrap().columns()[1].inner() else {
+            panic!("expected a list column");
+        };
+        assert!(!matrix.is_valid(77 - 64));
+        let Some(Some(ColumnType::List(rows))) = matrix.get(65 - 64) else {
+            panic!("expected a list of lists");
+        };
+        assert_eq!(rows.len(), 2);
+        let Some(Some(ColumnType::Int32(row))) = rows.get(0) else {
+            panic!("expected int32 values");
+        };
+        let row: Vec<_> = row.get_records().collect();
+        assert_eq!(row, vec![Some(0), Some(65)]);
+
+        // the bloom filter holds the values of the lists
+        let (result, report) = reader
+            .scan(&Predicate::eq("tags", Scalar::Text("tag-131".to_string())))
+            .unwrap();
+        assert_eq!(report.row_groups_skipped, 3);
+        assert_eq!(result.num_rows(), 64);
+    }
+
+    #[test]
+    fn enum_columns() {
+        let status_type = DataType::Enum(
+            ["open", "pending", "closed"]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+        );
+        let code_type = DataType::Enum((0..300).map(|i| format!("c{i}")).collect());
+        let mut db = Database::with_row_group_size(
+            vec![
+                Column::new("status".to_string(), ColumnType::empty(&status_type, 0)),
+                Column::new("code".to_string(), ColumnType::empty(&code_type, 0)),
+            ],
+            64,
+        );
+        let header = vec!["status".to_string(), "code".to_string()];
+        for i in 0..200 {
+            let status = match (i % 13, i % 2, i < 100) {
+                (0, _, _) => FieldType::Null,
+                (_, 1, _) => FieldType::Text("pending"),
+                (_, _, true) => FieldType::Text("open"),
+                _ => FieldType::Text("closed"),
+            };
+            let code = format!("c{}", i * 7 % 300);
+            db.add_rows(&Row::new(
+                header.clone(),
+                vec![status, FieldType::Text(&code)],
+            ));
+        }
+
+        let options = WriteOptions::new()
+            .with_page_size(16)
+            .with_bloom_filter("status");
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.metadata().schema()[0].data_type(), &status_type);
+        assert_eq!(reader.metadata().schema()[1].data_type(), &code_type);
+        let read = reader.read_database().unwrap();
+        for (a, b) in db.row_groups().iter().zip(read.row_groups()) {
+            let (ColumnType::Enum8(x), ColumnType::Enum8(y)) =
+                (a.columns()[0].inner(), b.columns()[0].inner())
+            else {
+                panic!("expected enum8 columns");
+            };
+            assert_eq!(x.codes().data(), y.codes().data());
+            assert_eq!(x.validity(), y.validity());
+            let (ColumnType::Enum16(a), ColumnType::Enum16(b)) =
+                (a.columns()[1].inner(), b.columns()[1].inner())
+            else {
+                panic!("expected enum16 columns");
+            };
+            assert_eq!(a.codes().data(), b.codes().data());
+        }
+        let ColumnType::Enum16(codes) = read.row_group(3).unwrap().columns()[1].inner() else {
+            panic!("expected an enum16 column");
+        };
+        // row 199
+        assert_eq!(codes.get(7), Some(Some("c193")));
+
+        // stats and bloom filters hold the labels
+        let closed = Predicate::eq("status", Scalar::Text("closed".to_string()));
+        let (result, report) = reader.scan(&closed).unwrap();
+        assert_eq!(report.row_groups_skipped, 1);
+        assert_eq!(report.pages_skipped, 6);
+        assert_eq!(result.num_rows(), 104);
+        let open = Predicate::eq("status", Scalar::Text("open".to_string()));
+        let (result, report) = reader.scan(&open).unwrap();
+        assert_eq!(report.row_groups_skipped, 2);
+        assert_eq!(result.num_rows(), 128);
+    }
+
+    #[test]
+    fn struct_columns() {
+        let geo = DataType::Struct(vec![
+            Field::new("lat".to_string(), DataType::Float64),
+            Field::new("lon".to_string(), DataType::Float64),
+        ]);
+        let address_type = DataType::Struct(vec![
+            Field::new("city".to_string(), DataType::Text),
+            Field::new("geo".to_string(), geo),
+            Field::new("zip".to_string(), DataType::Int32),
+        ]);
+        let mut db = Database::with_row_group_size(
+            vec![
+                Column::new("id".to_string(), ColumnType::empty(&DataType::Int32, 0)),
+                Column::new("address".to_string(), ColumnType::empty(&address_type, 0)),
+            ],
+            64,
+        );
+        let header = vec!["id".to_string(), "address".to_string()];
+        let cities = ["Oslo", "Lima", "Pune"];
+        for i in 0..200 {
+            let geo = FieldType::Struct(vec![
+                FieldType::Float64(i as f64 / 2.0),
+                FieldType::Float64(-(i as f64)),
+            ]);
+            let address = if i % 10 == 0 {
+                FieldType::Null
+            } else {
+                FieldType::Struct(vec![
+                    FieldType::Text(cities[i as usize % 3]),
+                    if i % 7 == 0 { FieldType::Null } else { geo },
+                    FieldType::Int32(i),
+                ])
+            };
+            db.add_rows(&Row::new(
+                header.clone(),
+                vec![FieldType::Int32(i), address],
+            ));
+        }
+
+        let options = WriteOptions::new().with_page_size(16);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.metadata().schema()[1].data_type(), &address_type);
+        let chunk = &reader.metadata().row_groups()[1].columns()[1];
+        // city, geo, geo.lat, geo.lon and zip
+        assert_eq!(chunk.field_stats().len(), 5);
+        assert_eq!(chunk.field_stats()[4].min(), Some(&Scalar::Int32(64)));
+        assert_eq!(chunk.pages()[0].field_stats()[2].null_count(), 2);
+
+        let read = reader.read_database().unwrap();
+        for (a, b) in db.row_groups().iter().zip(read.row_groups()) {
+            let (ColumnType::Struct(a), ColumnType::Struct(b)) =
+                (a.columns()[1].inner(), b.columns()[1].inner())
+            else {
+                panic!("expected struct columns");
+            };
+            assert_eq!(a.validity(), b.validity());
+            let (Some(ColumnType::Float64(a)), Some(ColumnType::Float64(b))) =
+                (a.field_by_path("geo.lon"), b.field_by_path("geo.lon"))
+            else {
+                panic!("expected float64 columns");
+            };
+            assert_eq!(a.data(), b.data());
+            assert_eq!(a.validity(), b.validity());
+        }
+
+        // filters reach into structs through the field statistics
+        let predicate = Predicate::between("address.zip", Scalar::Int32(100), Scalar::Int32(110));
+        let (result, report) = reader.scan(&predicate).unwrap();
+        assert_eq!(report.row_groups_skipped, 3);
+        assert_eq!(report.pages_skipped, 12);
+        assert_eq!(result.num_rows(), 16);
+        let (_, report) = reader.scan(&Predicate::is_null("address.geo.lat")).unwrap();
+        assert_eq!(report.pages_skipped, 0);
+        assert!(matches!(
+            reader.scan(&Predicate::is_null("address.street")),
+            Err(FileError::Schema(_))
+        ));
+
+        let projected = result.project(&["id", "address.geo.lat"]).unwrap();
+        let rg = projected.row_group(0).unwrap();
+        assert_eq!(rg.columns()[1].name(), "address.geo.lat");
+        let Some(ColumnType::Float64(lat)) = rg.column_by_path("address.geo.lat") else {
+            panic!("expected a float64 column");
+        };
+        // rows 96 to 111, 100 and 110 have no address and 98 and 105 no geo
+        assert_eq!(lat.null_count(), 4);
+        assert_eq!(lat.data()[1], 48.5);
+        assert!(result.project(&["address.nope"]).is_err());
+    }
+
+    #[test]
+    fn map_columns() {
+        let labels_type = DataType::Map(Box::new(DataType::Int64));
+        let mut db = Database::with_row_group_size(
+            vec![
+                Column::new("id".to_string(), ColumnType::empty(&DataType::Int32, 0)),
+                Column::new("labels".to_string(), ColumnType::empty(&labels_type, 0)),
+            ],
+            64,
+        );
+        let header = vec!["id".to_string(), "labels".to_string()];
+        let keys = ["env", "zone", "team"];
+        for i in 0..200 {
+            let labels = if i % 9 == 0 {
+                FieldType::Null
+            } else {
+                let value = |k: i32| match (i + k) % 5 {
+                    0 => FieldType::Null,
+                    v => FieldType::Int64(v as i64),
+                };
+                let len = i as usize % 4;
+                FieldType::Map(
+                    keys[..len]
+                        .iter()
+                        .zip(0..)
+                        .map(|(&k, j)| (k, value(j)))
+                        .collect(),
+                )
+            };
+            db.add_rows(&Row::new(header.clone(), vec![FieldType::Int32(i), labels]));
+        }
+
+        let options = WriteOptions::new().with_page_size(16);
+        let bytes = write_database_with_options(&db, vec![], options).unwrap();
+        let mut reader = FileReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.metadata().schema()[1].data_type(), &labels_type);
+        let read = reader.read_database().unwrap();
+        for (a, b) in db.row_groups().iter().zip(read.row_groups()) {
+            let (ColumnType::Map(a), ColumnType::Map(b)) =
+                (a.columns()[1].inner(), b.columns()[1].inner())
+            else {
+                panic!("expected map columns");
+            };
+            assert_eq!(a.offsets(), b.offsets());
+            assert_eq!(a.validity(), b.validity());
+            assert_eq!(a.keys().data(), b.keys().data());
+            let (ColumnType::Int64(a), ColumnType::Int64(b)) = (a.values(), b.values()) else {
+                panic!("expected int64 values");
+            };
+            assert_eq!(a.data(), b.data());
+            assert_eq!(a.validity(), b.validity());
+        }
+
+        // scans keep whole pages, rows 0 to 15 here
+        let (result, _) = reader.scan(&Predicate::eq("id", Scalar::Int32(7))).unwrap();
+        assert_eq!(result.num_rows(), 16);
+        let Some(ColumnType::Map(labels)) = result.row_group(0).unwrap().column_by_path("labels")
+        else {
+            panic!("expected a map column");
+        };
+        let ColumnType::Int64(zones) = labels.lookup("zone") else {
+            panic!("expected an int64 column");
+        };
+        let zones: Vec<_> = zones.get_records().collect();
+        assert_eq!(zones[7], Some(3));
+        // row 9 has no labels and row 5 no zone
+        assert_eq!(zones[9], None);
+        assert_eq!(zones[5], None);
+        assert!(labels.find(7, "team").is_some());
+        assert!(labels.find(5, "team").is_none());
+    }
+}
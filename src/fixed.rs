@@ -20,79 +20,206 @@
 
 // Lets to an int and float type def.
 
-// let's write a macro for type aliasing
+// let's write a macro for type aliasing. It also teaches the native type how to turn itself into
+// bytes and into a scalar which is needed for statistics and files.
 
 macro_rules! typedef {
-    ($name:ident, $type:ty, $doc:literal) => {
+    ($name:ident, $type:ty, $scalar:ident, $doc:literal) => {
         #[doc = $doc]
         pub type $name = Fixed<$type>;
+
+        impl Native for $type {
+            const WIDTH: usize = std::mem::size_of::<$type>();
+
+            fn write_le(self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                <$type>::from_le_bytes(bytes.try_into().expect("wrong byte width"))
+            }
+
+            fn hash_value(self) -> u64 {
+                crate::util::hash_bytes(&self.to_le_bytes())
+            }
+
+            fn raw_bits(self) -> u128 {
+                let mut bits = [0u8; 16];
+                bits[..Self::WIDTH].copy_from_slice(&self.to_le_bytes());
+                u128::from_le_bytes(bits)
+            }
+        }
+
+        impl ToScalar for $type {
+            fn to_scalar(&self) -> Scalar {
+                Scalar::$scalar(*self)
+            }
+        }
     };
 }
 
-typedef!(Int8Array, i8, "8-bit signed interger");
-typedef!(Int16Array, i16, "16-bit signed interger");
-typedef!(Int32Array, i32, "32-bit signed interger");
+/// Types that can be stored in a [`Fixed`] column.
+pub trait Native: Copy + Default + PartialOrd + Debug + ToScalar + 'static {
+    /// Size of one value in bytes.
+    const WIDTH: usize;
 
-typedef!(UInt8Array, u8, "8-bit unsigned interger");
-typedef!(UInt16Array, u16, "16-bit unsigned interger");
-typedef!(UInt32Array, u32, "32-bit unsigned interger");
+    /// Appends the little endian bytes of the value.
+    fn write_le(self, out: &mut Vec<u8>);
 
-typedef!(Float32Array, f32, "32-bit float");
-typedef!(Float64Array, f64, "64-bit float");
+    /// Reads a value from exactly `WIDTH` little endian bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+
+    fn hash_value(self) -> u64;
+
+    /// The bytes of the value as an integer. Unlike `==`, equal bits mean identical values: 0.0
+    /// and -0.0 differ and a NaN matches itself.
+    fn raw_bits(self) -> u128;
+}
+
+typedef!(Int8Array, i8, Int8, "8-bit signed interger");
+typedef!(Int16Array, i16, Int16, "16-bit signed interger");
+typedef!(Int32Array, i32, Int32, "32-bit signed interger");
+typedef!(Int64Array, i64, Int64, "64-bit signed interger");
+
+typedef!(UInt8Array, u8, UInt8, "8-bit unsigned interger");
+typedef!(UInt16Array, u16, UInt16, "16-bit unsigned interger");
+typedef!(UInt32Array, u32, UInt32, "32-bit unsigned interger");
+typedef!(UInt64Array, u64, UInt64, "64-bit unsigned interger");
+
+typedef!(Float32Array, f32, Float32, "32-bit float");
+typedef!(Float64Array, f64, Float64, "64-bit float");
 
 use std::fmt::Debug;
 
 use crate::bitvec;
 use crate::bitvec::BitVec;
+use crate::stats::{ColumnStats, LazyStats, Scalar, StatsTracker, ToScalar};
 
+// most columns never see a null, so the bitmap only exists once the first one is added. Until then
+// nothing is allocated for it and readers don't have to look at it.
 #[derive(Debug)]
 pub struct Fixed<T> {
-    data: Vec<T>,  // 24 bytes
-    nulls: BitVec, // 24 bytes
+    data: Vec<T>,          // 24 bytes
+    nulls: Option<BitVec>, // 24 bytes, None when every record is valid
+    stats: LazyStats<T>,
 }
 
 impl<T> Default for Fixed<T> {
     fn default() -> Self {
         Fixed {
             data: vec![],
-            nulls: BitVec::default(),
+            nulls: None,
+            stats: LazyStats::default(),
         }
     }
 }
 
 impl Fixed<i32> {
     pub fn test_new() -> Fixed<i32> {
-        Fixed {
-            data: vec![1, 2, 3, 0, 0, 0, 4, 5, 0, 6],
-            nulls: bitvec![
+        Fixed::from_parts(
+            vec![1, 2, 3, 0, 0, 0, 4, 5, 0, 6],
+            bitvec![
                 true, true, true, false, false, false, true, true, true, true
             ],
-        }
+        )
     }
 }
 
 impl Fixed<f32> {
     pub fn test_new() -> Fixed<f32> {
-        Fixed {
-            data: vec![
+        Fixed::from_parts(
+            vec![
                 1.123f32, 2f32, 3f32, 0f32, 0f32, 0f32, 4f32, 5f32, 0f32, 6f32,
             ],
-            nulls: bitvec![
+            bitvec![
                 true, true, true, false, false, false, true, true, true, true
             ],
-        }
+        )
     }
 }
 
-impl<T> Fixed<T> {
+impl<T: Native> Fixed<T> {
     pub fn new() -> Fixed<T> {
         Default::default()
     }
 
+    // reserving upfront avoids copying the whole column when the Vec grows. The validity is only
+    // allocated when a null shows up.
+    pub fn with_capacity(capacity: usize) -> Fixed<T> {
+        Fixed {
+            data: Vec::with_capacity(capacity),
+            nulls: None,
+            stats: LazyStats::default(),
+        }
+    }
+
+    // builds a column from existing buffers, e.g. when reading a file. `None` means every record
+    // is valid and a bitmap without nulls is dropped, so only columns with nulls carry one. The
+    // other columns take their validity the same way.
+    pub fn from_parts(data: Vec<T>, nulls: impl Into<Option<BitVec>>) -> Fixed<T> {
+        let nulls = nulls.into().filter(|nulls| nulls.null_count() > 0);
+        if let Some(nulls) = &nulls {
+            assert_eq!(
+                data.len(),
+                nulls.len(),
+                "data and validity length missmatch"
+            );
+        }
+        Fixed {
+            data,
+            nulls,
+            stats: LazyStats::unknown(),
+        }
+    }
+
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// The validity bitmap, `None` when every record is valid.
+    pub fn validity(&self) -> Option<&BitVec> {
+        self.nulls.as_ref()
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.nulls.as_ref().is_none_or(|nulls| nulls[index])
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.nulls.as_ref().map_or(0, BitVec::null_count)
+    }
+
+    // the bitmap is built on first use, every record so far was valid.
+    fn nulls_mut(&mut self) -> &mut BitVec {
+        let len = self.data.len();
+        self.nulls.get_or_insert_with(|| BitVec::ones(len))
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
     // we always add record the end of data.
     pub fn add_record(&mut self, record: T) {
+        self.stats.observe(&record, record.hash_value());
         self.data.push(record);
-        self.nulls.push(true);
+        if let Some(nulls) = &mut self.nulls {
+            nulls.push(true);
+        }
+    }
+
+    // null still takes a slot in data so indexes stay the same for every column.
+    pub fn add_null(&mut self) {
+        self.nulls_mut().push(false);
+        self.data.push(T::default());
     }
 
     // somehow I have to get records, I would just allocate new buffer each time for now.
@@ -115,12 +242,18 @@ impl<T> Fixed<T> {
             return Err("bad id");
         }
 
+        if self.is_valid(id) {
+            self.stats.invalidate();
+        }
         if let Some(record) = record {
+            self.stats.observe(&record, record.hash_value());
             self.data[id] = record;
-            self.nulls.set(id, true);
+            if let Some(nulls) = &mut self.nulls {
+                nulls.set(id, true);
+            }
         } else {
             // self.data[id] = 0;
-            self.nulls.set(id, false);
+            self.nulls_mut().set(id, false);
         }
         Ok(())
     }
@@ -130,15 +263,77 @@ impl<T> Fixed<T> {
         if id > self.data.len() - 1 {
             return Err("bad id");
         }
+        if self.is_valid(id) {
+            self.stats.invalidate();
+        }
         // ASM check, what happens if I define local parameter?
         let last_index = self.data.len() - 1;
         self.data.swap(id, last_index);
         self.data.pop();
-        self.nulls.swap(id, last_index);
-        self.nulls.pop();
+        if let Some(nulls) = &mut self.nulls {
+            nulls.swap(id, last_index);
+            nulls.pop();
+        }
 
         Ok(())
     }
+
+    // copies `length` records starting at `offset` into a new column.
+    pub fn slice(&self, offset: usize, length: usize) -> Fixed<T> {
+        Fixed::from_parts(
+            self.data[offset..offset + length].to_vec(),
+            self.nulls.as_ref().map(|nulls| nulls.slice(offset, length)),
+        )
+    }
+
+    // adds every record of other to the end.
+    pub fn append(&mut self, other: &Fixed<T>) {
+        for record in other.get_records() {
+            match record {
+                Some(record) => self.add_record(record),
+                None => self.add_null(),
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ColumnStats {
+        let byte_size = self.data.len() * T::WIDTH
+            + self
+                .nulls
+                .as_ref()
+                .map_or(0, |nulls| nulls.as_bytes().len());
+        self.stats
+            .get(|| self.compute_stats())
+            .to_stats(T::to_scalar, self.null_count(), byte_size)
+    }
+
+    /// Computes statistics now if they aren't known, so the next `stats` call doesn't scan.
+    pub fn refresh_stats(&mut self) {
+        self.stats.get(|| self.compute_stats());
+    }
+
+    fn compute_stats(&self) -> StatsTracker<T> {
+        let mut stats = StatsTracker::default();
+        for value in self.get_records().flatten() {
+            stats.observe(&value, value.hash_value());
+        }
+        stats
+    }
+}
+
+// `None` adds a null, handy for building columns in tests and kernels.
+impl<T: Native> FromIterator<Option<T>> for Fixed<T> {
+    fn from_iter<I: IntoIterator<Item = Option<T>>>(records: I) -> Fixed<T> {
+        let records = records.into_iter();
+        let mut column = Fixed::with_capacity(records.size_hint().0);
+        for record in records {
+            match record {
+                Some(record) => column.add_record(record),
+                None => column.add_null(),
+            }
+        }
+        column
+    }
 }
 
 pub struct FixedViewer<'a, T> {
@@ -153,13 +348,10 @@ impl<'a, T: Copy> Iterator for FixedViewer<'a, T> {
             return None;
         }
 
-        let not_null = self.inner.nulls[self.index];
-        let value = {
-            if not_null {
-                Some(self.inner.data[self.index])
-            } else {
-                None
-            }
+        // no bitmap, no need to look up every record
+        let value = match &self.inner.nulls {
+            None => Some(self.inner.data[self.index]),
+            Some(nulls) => nulls[self.index].then_some(self.inner.data[self.index]),
         };
         self.index += 1;
         Some(value)
@@ -182,11 +374,10 @@ impl<'a, T: Copy> ExactSizeIterator for FixedViewer<'a, T> {}
 // lets have debug way of seeing the column for dev
 impl<'a> Debug for FixedViewer<'a, f32> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (index, not_null) in self.inner.nulls.iter().enumerate() {
-            if not_null {
-                writeln!(f, "{:3.3}", &self.inner.data[index])?;
-            } else {
-                writeln!(f, "<NULL>")?;
+        for record in self.inner.get_records() {
+            match record {
+                Some(record) => writeln!(f, "{record:3.3}")?,
+                None => writeln!(f, "<NULL>")?,
             }
         }
         Ok(())
@@ -196,11 +387,10 @@ impl<'a> Debug for FixedViewer<'a, f32> {
 // lets have debug way of seeing the column for dev
 impl<'a> Debug for FixedViewer<'a, i32> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (index, not_null) in self.inner.nulls.iter().enumerate() {
-            if not_null {
-                writeln!(f, "{}", &self.inner.data[index])?;
-            } else {
-                writeln!(f, "<NULL>")?;
+        for record in self.inner.get_records() {
+            match record {
+                Some(record) => writeln!(f, "{record}")?,
+                None => writeln!(f, "<NULL>")?,
             }
         }
         Ok(())
@@ -209,7 +399,7 @@ impl<'a> Debug for FixedViewer<'a, i32> {
 
 #[cfg(test)]
 mod test {
-    use crate::{bitvec, bitvec::BitVec};
+    use crate::{bitvec, bitvec::BitVec, stats::Scalar};
 
     use super::Fixed;
 
@@ -222,47 +412,124 @@ mod test {
         col.add_record(80);
 
         assert_eq!(col.data, vec![5, 10, 40, 80]);
-        assert_eq!(col.nulls, bitvec![true, true, true, true]);
+        assert_eq!(col.nulls, None);
+    }
+
+    #[test]
+    fn validity_is_built_on_first_null() {
+        let mut col = Fixed::new();
+        col.add_record(1);
+        col.add_record(2);
+        assert_eq!(col.validity(), None);
+        assert_eq!(col.null_count(), 0);
+
+        col.add_null();
+        col.add_record(3);
+        assert_eq!(col.validity(), Some(&bitvec![true, true, false, true]));
+        assert_eq!(
+            col.get_records().collect::<Vec<_>>(),
+            vec![Some(1), Some(2), None, Some(3)]
+        );
+
+        // slices and buffers without nulls don't keep a bitmap
+        assert_eq!(col.slice(0, 2).validity(), None);
+        assert_eq!(col.slice(1, 2).null_count(), 1);
+        let col = Fixed::from_parts(vec![1, 2], bitvec![true, true]);
+        assert_eq!(col.validity(), None);
+
+        let mut col = Fixed::from_parts(vec![4, 5, 6], None);
+        col.update_record(1, None).unwrap();
+        assert_eq!(col.validity(), Some(&bitvec![true, false, true]));
+        assert!(!col.is_valid(1));
     }
 
     #[test]
     fn delete_record() {
-        let mut col = Fixed {
-            data: vec![1, 2, 3, 0, 0, 0, 4, 5, 0, 6],
-            nulls: bitvec![
+        let mut col = Fixed::from_parts(
+            vec![1, 2, 3, 0, 0, 0, 4, 5, 0, 6],
+            bitvec![
                 true, true, true, false, false, false, true, true, false, true
             ],
-        };
+        );
 
         col.delete_record(3).unwrap();
 
         assert_eq!(col.data, vec![1, 2, 3, 6, 0, 0, 4, 5, 0]);
         assert_eq!(
             col.nulls,
-            bitvec![true, true, true, true, false, false, true, true, false]
+            Some(bitvec![
+                true, true, true, true, false, false, true, true, false
+            ])
         );
     }
 
     #[test]
     fn update_record() {
-        let mut col = Fixed {
-            data: vec![1, 2, 3, 0, 0, 0, 4, 5, 0, 6],
-            nulls: bitvec![
+        let mut col = Fixed::from_parts(
+            vec![1, 2, 3, 0, 0, 0, 4, 5, 0, 6],
+            bitvec![
                 true, true, true, false, false, false, true, true, false, true
             ],
-        };
+        );
 
         col.update_record(3, Some(9)).unwrap();
 
         assert_eq!(col.data, vec![1, 2, 3, 9, 0, 0, 4, 5, 0, 6]);
         assert_eq!(
             col.nulls,
-            bitvec![
+            Some(bitvec![
                 true, true, true, true, false, false, true, true, false, true
-            ]
+            ])
         );
     }
 
+    #[test]
+    fn stats_follow_changes() {
+        let mut col = Fixed::new();
+        col.add_record(7);
+        col.add_record(-3);
+        col.add_null();
+        col.add_record(12);
+        col.add_record(7);
+
+        let stats = col.stats();
+        assert_eq!(stats.min(), Some(&Scalar::Int32(-3)));
+        assert_eq!(stats.max(), Some(&Scalar::Int32(12)));
+        assert_eq!(stats.null_count(), 1);
+        assert_eq!(stats.distinct_count(), 3);
+        assert_eq!(stats.byte_size(), 5 * 4 + 1);
+
+        col.update_record(3, None).unwrap();
+        col.delete_record(1).unwrap();
+        let stats = col.stats();
+        assert_eq!(stats.min(), Some(&Scalar::Int32(7)));
+        assert_eq!(stats.max(), Some(&Scalar::Int32(7)));
+        assert_eq!(stats.null_count(), 2);
+        assert_eq!(stats.distinct_count(), 1);
+    }
+
+    #[test]
+    fn stats_skip_nan() {
+        let mut col = Fixed::new();
+        col.add_record(f64::NAN);
+        col.add_record(1.5);
+        assert_eq!(col.stats().min(), Some(&Scalar::Float64(1.5)));
+        assert_eq!(col.stats().max(), Some(&Scalar::Float64(1.5)));
+    }
+
+    #[test]
+    fn stats_of_built_columns() {
+        // nothing is tracked until stats are asked for, appends before that are still counted
+        let mut col = Fixed::from_parts(vec![4, 9], None);
+        col.add_record(1);
+        assert_eq!(col.stats().min(), Some(&Scalar::Int32(1)));
+        col.add_record(20);
+        assert_eq!(col.stats().max(), Some(&Scalar::Int32(20)));
+        col.delete_record(3).unwrap();
+        assert_eq!(col.stats().max(), Some(&Scalar::Int32(9)));
+        assert_eq!(col.stats().distinct_count(), 3);
+    }
+
     // #[test]
     // fn get_record() {
     //     let col = Fixed {
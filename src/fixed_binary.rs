@@ -0,0 +1,307 @@
+// UUIDs are always 16 bytes and a SHA-256 digest always 32, so storing them in a Variable spends
+// an offset per value on a length that never changes. A fixed size binary column knows the width
+// from its type and packs the values back to back: value i is data[i * width..(i + 1) * width].
+// Like Fixed, a null still takes its slot (filled with zeros) so indexes stay simple.
+//
+// Values order like byte strings, which for UUIDs and digests is the order of their hex text.
+
+use std::cmp::Ordering;
+
+use crate::bitvec::BitVec;
+use crate::stats::{ColumnStats, LazyStats, Scalar, StatsTracker};
+use crate::util::hash_bytes;
+
+/// Width of a UUID, values of this width are shown in the 8-4-4-4-12 form.
+pub const UUID_WIDTH: usize = 16;
+
+#[derive(Debug)]
+pub struct FixedSizeBinaryArray {
+    width: usize,
+    data: Vec<u8>,
+    validity: Option<BitVec>,
+    stats: LazyStats<Vec<u8>>,
+}
+
+impl FixedSizeBinaryArray {
+    pub fn new(width: usize) -> FixedSizeBinaryArray {
+        FixedSizeBinaryArray::with_capacity(width, 0)
+    }
+
+    pub fn with_capacity(width: usize, capacity: usize) -> FixedSizeBinaryArray {
+        assert!(width > 0, "width must be positive");
+        FixedSizeBinaryArray {
+            width,
+            // only a hint, one too large to address reserves nothing
+            data: Vec::with_capacity(width.checked_mul(capacity).unwrap_or(0)),
+            validity: None,
+            stats: LazyStats::default(),
+        }
+    }
+
+    // wraps values stored back to back, `width` bytes each. Validity as in `Fixed::from_parts`.
+    pub fn from_parts(
+        width: usize,
+        data: Vec<u8>,
+        validity: impl Into<Option<BitVec>>,
+    ) -> FixedSizeBinaryArray {
+        assert!(width > 0, "width must be positive");
+        assert_eq!(data.len() % width, 0, "data is not a multiple of the width");
+        let validity = validity.into().filter(|validity| validity.null_count() > 0);
+        if let Some(validity) = &validity {
+            assert_eq!(
+                data.len() / width,
+                validity.len(),
+                "data and validity length missmatch"
+            );
+        }
+        FixedSizeBinaryArray {
+            width,
+            data,
+            validity,
+            stats: LazyStats::unknown(),
+        }
+    }
+
+    /// Bytes per value.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Every value back to back, nulls are zeros.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The validity bitmap, `None` when every value is valid.
+    pub fn validity(&self) -> Option<&BitVec> {
+        self.validity.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / self.width
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.validity
+            .as_ref()
+            .is_none_or(|validity| validity[index])
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.validity.as_ref().map_or(0, BitVec::null_count)
+    }
+
+    /// Panics when `value` isn't exactly `width` bytes.
+    pub fn add(&mut self, value: &[u8]) {
+        assert_eq!(value.len(), self.width, "value has the wrong width");
+        self.stats.observe(value, hash_bytes(value));
+        self.data.extend_from_slice(value);
+        if let Some(validity) = &mut self.validity {
+            validity.push(true);
+        }
+    }
+
+    pub fn add_null(&mut self) {
+        let len = self.len();
+        self.validity
+            .get_or_insert_with(|| BitVec::ones(len))
+            .push(false);
+        self.data.resize(self.data.len() + self.width, 0);
+    }
+
+    /// Parses hex text (or a UUID for 16 byte columns) and adds it.
+    pub fn add_text(&mut self, text: &str) -> Result<(), &'static str> {
+        let value = self.parse(text)?;
+        self.add(&value);
+        Ok(())
+    }
+
+    pub fn get(&self, index: usize) -> Option<Option<&[u8]>> {
+        if index >= self.len() {
+            return None;
+        }
+        if !self.is_valid(index) {
+            return Some(None);
+        }
+        Some(Some(
+            &self.data[index * self.width..(index + 1) * self.width],
+        ))
+    }
+
+    // copies `length` values starting at `offset` into a new column.
+    pub fn slice(&self, offset: usize, length: usize) -> FixedSizeBinaryArray {
+        assert!(offset + length <= self.len(), "range out of bounds");
+        FixedSizeBinaryArray::from_parts(
+            self.width,
+            self.data[offset * self.width..(offset + length) * self.width].to_vec(),
+            self.validity
+                .as_ref()
+                .map(|validity| validity.slice(offset, length)),
+        )
+    }
+
+    // adds every value of other to the end.
+    pub fn append(&mut self, other: &FixedSizeBinaryArray) {
+        assert_eq!(self.width, other.width, "widths don't match");
+        for index in 0..other.len() {
+            match other.get(index).unwrap() {
+                Some(value) => self.add(value),
+                None => self.add_null(),
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ColumnStats {
+        let byte_size = self.data.len() + self.validity.as_ref().map_or(0, |v| v.as_bytes().len());
+        let compute = || {
+            let mut stats = StatsTracker::default();
+            for (index, value) in self.data.chunks_exact(self.width).enumerate() {
+                if self.is_valid(index) {
+                    stats.observe(value, hash_bytes(value));
+                }
+            }
+            stats
+        };
+        self.stats.get(compute).to_stats(
+            |bytes| Scalar::Binary(bytes.clone()),
+            self.null_count(),
+            byte_size,
+        )
+    }
+
+    /// Indexes of the values in ascending byte order, nulls last. Equal values keep their order.
+    pub fn sort_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        indices.sort_by(
+            |&a, &b| match (self.get(a).unwrap(), self.get(b).unwrap()) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        );
+        indices
+    }
+
+    /// Reads `width * 2` hex digits in either case. A 16 byte column also takes UUIDs with
+    /// dashes, e.g. "67e55044-10b1-426f-9247-bb680e5fe0c8".
+    pub fn parse(&self, text: &str) -> Result<Vec<u8>, &'static str> {
+        let dashes = [8, 13, 18, 23];
+        let hex: Vec<u8> = if self.width == UUID_WIDTH && text.len() == 36 {
+            let bytes = text.as_bytes();
+            if dashes.iter().any(|&i| bytes[i] != b'-') {
+                return Err("expected a UUID");
+            }
+            bytes
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !dashes.contains(i))
+                .map(|(_, b)| *b)
+                .collect()
+        } else {
+            text.as_bytes().to_vec()
+        };
+        if hex.len() != self.width * 2 {
+            return Err("wrong number of hex digits");
+        }
+        let digit = |b: u8| (b as char).to_digit(16).ok_or("expected a hex digit");
+        hex.chunks_exact(2)
+            .map(|pair| Ok((digit(pair[0])? * 16 + digit(pair[1])?) as u8))
+            .collect()
+    }
+
+    /// The value at `index` as lowercase hex, UUIDs with dashes. `Some(None)` for nulls.
+    pub fn format(&self, index: usize) -> Option<Option<String>> {
+        Some(self.get(index)?.map(|value| self.format_value(value)))
+    }
+
+    pub fn format_value(&self, value: &[u8]) -> String {
+        let mut text = String::with_capacity(value.len() * 2 + 4);
+        for (i, byte) in value.iter().enumerate() {
+            if value.len() == UUID_WIDTH && matches!(i, 4 | 6 | 8 | 10) {
+                text.push('-');
+            }
+            text += &format!("{byte:02x}");
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FixedSizeBinaryArray;
+    use crate::stats::Scalar;
+
+    #[test]
+    fn uuids() {
+        let mut col = FixedSizeBinaryArray::new(16);
+        col.add_text("67E55044-10B1-426F-9247-BB680E5FE0C8")
+            .unwrap();
+        col.add_null();
+        col.add_text("00000000000000000000000000000001").unwrap();
+        assert_eq!(col.len(), 3);
+        assert_eq!(col.data().len(), 48);
+        assert_eq!(col.get(0).unwrap().unwrap()[..2], [0x67, 0xe5]);
+        assert_eq!(col.get(1), Some(None));
+        assert_eq!(col.get(3), None);
+        assert_eq!(
+            col.format(0),
+            Some(Some("67e55044-10b1-426f-9247-bb680e5fe0c8".to_string()))
+        );
+        assert_eq!(
+            col.format(2),
+            Some(Some("00000000-0000-0000-0000-000000000001".to_string()))
+        );
+        for text in [
+            "67e55044-10b1-426f-9247-bb680e5fe0c",
+            "67e55044_10b1_426f_9247_bb680e5fe0c8",
+            "g7e5504410b1426f9247bb680e5fe0c8",
+            "",
+        ] {
+            assert!(col.parse(text).is_err(), "{text}");
+        }
+
+        assert_eq!(col.sort_indices(), vec![2, 0, 1]);
+        let stats = col.stats();
+        assert_eq!(
+            stats.min(),
+            Some(&Scalar::Binary(col.parse(&("0".repeat(31) + "1")).unwrap()))
+        );
+        assert_eq!(stats.null_count(), 1);
+        assert_eq!(stats.byte_size(), 48 + 1);
+
+        let sliced = col.slice(1, 2);
+        assert_eq!(sliced.get(0), Some(None));
+        let mut appended = FixedSizeBinaryArray::new(16);
+        appended.append(&sliced);
+        assert_eq!(appended.format(1), col.format(2));
+    }
+
+    #[test]
+    fn digests() {
+        let mut col = FixedSizeBinaryArray::new(32);
+        let digest = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        col.add_text(digest).unwrap();
+        col.add(&[0xff; 32]);
+        assert_eq!(col.format(0), Some(Some(digest.to_string())));
+        assert!(col.add_text(&digest[..62]).is_err());
+        assert_eq!(col.validity(), None);
+        assert_eq!(col.stats().max(), Some(&Scalar::Binary(vec![0xff; 32])));
+    }
+
+    #[test]
+    fn oversized_capacity() {
+        let col = FixedSizeBinaryArray::with_capacity(u32::MAX as usize, 1 << 40);
+        assert!(col.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong width")]
+    fn rejects_other_widths() {
+        FixedSizeBinaryArray::new(16).add(&[0; 15]);
+    }
+}
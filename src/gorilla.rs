@@ -0,0 +1,213 @@
+// float compression from facebook's gorilla paper. Sensor readings and metrics change slowly, so
+// two neighbouring floats share their sign, exponent and the top of the mantissa. XOR-ing a value
+// with the previous one leaves a word that is mostly zeros, and only the bits in the middle that
+// differ are stored:
+//
+//   xor == 0                             '0'
+//   xor fits the previous window         '10' + meaningful bits
+//   otherwise                            '11' + 5 bits leading zeros + 6 bits length - 1 + bits
+//
+// The window is the leading and trailing zero count of the last xor written with '11'. Values are
+// handled as raw bits so every float, NaN payloads and -0.0 included, comes back exactly.
+
+use crate::fixed::Native;
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            bytes: vec![],
+            bits: 0,
+        }
+    }
+
+    // writes the lowest `count` bits of value, most significant first.
+    fn write(&mut self, value: u64, count: u32) {
+        for bit in (0..count).rev() {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> bit) & 1 == 1 {
+                let last = self.bytes.len() - 1;
+                self.bytes[last] |= 0x80 >> (self.bits % 8);
+            }
+            self.bits += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, count: u32) -> Result<u64, &'static str> {
+        if self.position + count as usize > self.bytes.len() * 8 {
+            return Err("xor stream is truncated");
+        }
+        let mut value = 0u64;
+        for _ in 0..count {
+            let byte = self.bytes[self.position / 8];
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u64;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// XOR compresses `values`.
+pub fn encode<T: Native>(values: &[T]) -> Vec<u8> {
+    let width = (T::WIDTH * 8) as u32;
+    let mut writer = BitWriter::new();
+    let Some(first) = values.first() else {
+        return writer.bytes;
+    };
+    // floats, so the bits fit a u64
+    writer.write(first.raw_bits() as u64, width);
+    let mut previous = first.raw_bits() as u64;
+    // window of the last '11' block, (leading zeros, meaningful bits)
+    let mut window: Option<(u32, u32)> = None;
+    for value in &values[1..] {
+        let bits = value.raw_bits() as u64;
+        let xor = bits ^ previous;
+        previous = bits;
+        if xor == 0 {
+            writer.write(0, 1);
+            continue;
+        }
+        // zeros counted within the float width, f32 values live in the low half of the word
+        let leading = (xor.leading_zeros() - (64 - width)).min(31);
+        let trailing = xor.trailing_zeros();
+        match window {
+            Some((window_leading, window_len))
+                if leading >= window_leading && trailing >= width - window_leading - window_len =>
+            {
+                let shift = width - window_leading - window_len;
+                writer.write(0b10, 2);
+                writer.write(xor >> shift, window_len);
+            }
+            _ => {
+                let len = width - leading - trailing;
+                writer.write(0b11, 2);
+                writer.write(leading as u64, 5);
+                writer.write((len - 1) as u64, 6);
+                writer.write(xor >> trailing, len);
+                window = Some((leading, len));
+            }
+        }
+    }
+    writer.bytes
+}
+
+/// Decodes `count` values written by [`encode`].
+pub fn decode<T: Native>(bytes: &[u8], count: usize) -> Result<Vec<T>, &'static str> {
+    let width = (T::WIDTH * 8) as u32;
+    // every value after the first takes at least a bit.
+    if count > 1 + bytes.len().saturating_mul(8) {
+        return Err("xor stream is truncated");
+    }
+    let mut values = Vec::with_capacity(count);
+    if count == 0 {
+        return Ok(values);
+    }
+    let from_bits = |bits: u64| T::read_le(&bits.to_le_bytes()[..T::WIDTH]);
+    let mut reader = BitReader { bytes, position: 0 };
+    let mut previous = reader.read(width)?;
+    values.push(from_bits(previous));
+    let mut window: Option<(u32, u32)> = None;
+    while values.len() < count {
+        if reader.read(1)? == 1 {
+            let (leading, len) = if reader.read(1)? == 1 {
+                let leading = reader.read(5)? as u32;
+                let len = reader.read(6)? as u32 + 1;
+                if leading + len > width {
+                    return Err("xor block is wider than the value");
+                }
+                window = Some((leading, len));
+                (leading, len)
+            } else {
+                window.ok_or("xor block refers to a missing window")?
+            };
+            let meaningful = reader.read(len)?;
+            previous ^= meaningful << (width - leading - len);
+        }
+        values.push(from_bits(previous));
+    }
+    // only padding of the last byte may be left
+    if bytes.len() != reader.position.div_ceil(8) {
+        return Err("trailing bytes after xor stream");
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, encode};
+
+    fn bits<T: Copy>(values: &[T], to_bits: fn(T) -> u64) -> Vec<u64> {
+        values.iter().map(|&v| to_bits(v)).collect()
+    }
+
+    #[test]
+    fn exact_round_trip() {
+        let values = [
+            0.0,
+            -0.0,
+            f64::NAN,
+            f64::from_bits(0x7ff8_dead_beef_0001),
+            -f64::NAN,
+            f64::INFINITY,
+            f64::MIN_POSITIVE,
+            f64::MAX,
+            1.5,
+            1.5,
+            -1.25e-300,
+        ];
+        let read: Vec<f64> = decode(&encode(&values), values.len()).unwrap();
+        assert_eq!(bits(&read, f64::to_bits), bits(&values, f64::to_bits));
+
+        let values = [0.0f32, -0.0, f32::from_bits(0x7fc0_1234), 3.25, 3.5, -7.0];
+        let read: Vec<f32> = decode(&encode(&values), values.len()).unwrap();
+        let f32_bits = |v: f32| v.to_bits() as u64;
+        assert_eq!(bits(&read, f32_bits), bits(&values, f32_bits));
+
+        assert_eq!(decode::<f64>(&encode::<f64>(&[]), 0), Ok(vec![]));
+        assert!(decode::<f64>(&[0; 4], 1).is_err());
+        assert_eq!(
+            decode::<f64>(&[0; 8], 1 << 62),
+            Err("xor stream is truncated")
+        );
+    }
+
+    #[test]
+    fn sensor_data_shrinks() {
+        // a temperature sensor sampled every second: slow drift with a 0.1 degree resolution,
+        // often the same reading several times in a row
+        let temperatures: Vec<f64> = (0..10_000)
+            .map(|i| {
+                let drift = ((i / 60) as f64 * 0.05).sin() * 4.0;
+                (215.0 + drift * 10.0).round() / 10.0
+            })
+            .collect();
+        let encoded = encode(&temperatures);
+        let raw = temperatures.len() * 8;
+        assert!(encoded.len() * 4 < raw, "{} of {raw} bytes", encoded.len());
+        let read: Vec<f64> = decode(&encoded, temperatures.len()).unwrap();
+        assert_eq!(read, temperatures);
+
+        // humidity as f32 with small changes between samples
+        let humidity: Vec<f32> = (0..10_000)
+            .map(|i| 40.0 + ((i / 30) % 20) as f32 * 0.5)
+            .collect();
+        let encoded = encode(&humidity);
+        assert!(encoded.len() * 4 < humidity.len() * 4, "{}", encoded.len());
+        let read: Vec<f32> = decode(&encoded, humidity.len()).unwrap();
+        assert_eq!(read, humidity);
+    }
+}
@@ -0,0 +1,535 @@
+// importing JSON. Semi-structured sources usually hand out one JSON object per line, so that is
+// what `read_json_lines` takes, along with the schema the table should have. Every top level key
+// is looked up in the schema, nested objects go into struct or map columns and arrays into list
+// columns. A key missing from an object is null and keys the schema doesn't know are ignored, but
+// a map column keeps every key of its objects.
+//
+// Numbers are kept as the text they were written as until the column type is known: a decimal
+// column parses the digits exactly and an int64 column isn't limited to what a f64 can hold.
+
+use std::fmt::Display;
+
+use crate::database::{Column, ColumnType, DataType, Database, Field, FieldType, Row, RowError};
+
+// arrays and objects nest, a hostile document must not be able to blow the stack.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Boolean(bool),
+    /// The number as written, e.g. "-1.5e3".
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    /// Keys in the order they were written.
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// The value of `key` when this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    line: usize,
+    message: &'static str,
+    // set when the table rejected the row, it knows the column and the label
+    row: Option<RowError>,
+}
+
+impl JsonError {
+    /// Line of the input the error is on, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &'static str {
+        self.message
+    }
+
+    /// Why the table rejected the row, when the JSON itself was fine.
+    pub fn row_error(&self) -> Option<&RowError> {
+        self.row.as_ref()
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.row {
+            Some(row) => write!(f, "line {}: {row}", self.line),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.row.as_ref().map(|row| row as _)
+    }
+}
+
+/// Parses a single JSON document.
+pub fn parse(text: &str) -> Result<JsonValue, &'static str> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        position: 0,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position != parser.bytes.len() {
+        return Err("trailing characters after the value");
+    }
+    Ok(value)
+}
+
+/// Builds a table with the given schema from one JSON object per line. Blank lines are skipped.
+pub fn read_json_lines(
+    text: &str,
+    schema: &[Field],
+    row_group_size: usize,
+) -> Result<Database, JsonError> {
+    let columns = schema
+        .iter()
+        .map(|f| Column::new(f.name().to_string(), ColumnType::empty(f.data_type(), 0)))
+        .collect();
+    let mut database = Database::with_row_group_size(columns, row_group_size);
+    let header: Vec<String> = schema.iter().map(|f| f.name().to_string()).collect();
+    for (index, line) in text.lines().enumerate() {
+        let error = |message| JsonError {
+            line: index + 1,
+            message,
+            row: None,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = parse(line).map_err(error)?;
+        let JsonValue::Object(_) = value else {
+            return Err(error("expected an object"));
+        };
+        let fields = schema
+            .iter()
+            .map(|f| to_field(value.get(f.name()), f.data_type()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error)?;
+        database
+            .try_add_rows(&Row::new(header.clone(), fields))
+            .map_err(|row| JsonError {
+                line: index + 1,
+                message: row.message(),
+                row: Some(row),
+            })?;
+    }
+    Ok(database)
+}
+
+// the field to add to a column of `data_type`, whether the value fits is up to the column.
+fn to_field<'a>(
+    value: Option<&'a JsonValue>,
+    data_type: &DataType,
+) -> Result<FieldType<'a>, &'static str> {
+    let Some(value) = value else {
+        return Ok(FieldType::Null);
+    };
+    Ok(match (value, data_type) {
+        (JsonValue::Null, _) => FieldType::Null,
+        (&JsonValue::Boolean(v), _) => FieldType::Boolean(v),
+        (JsonValue::Number(n), _) => number(n, data_type)?,
+        (JsonValue::String(s), DataType::Binary | DataType::LargeBinary) => {
+            FieldType::Binary(s.as_bytes())
+        }
+        (JsonValue::String(s), _) => FieldType::Text(s),
+        (JsonValue::Array(values), DataType::List(inner)) => FieldType::List(
+            values
+                .iter()
+                .map(|v| to_field(Some(v), inner))
+                .collect::<Result<_, _>>()?,
+        ),
+        (JsonValue::Array(_), _) => return Err("unexpected array"),
+        (value @ JsonValue::Object(_), DataType::Struct(fields)) => FieldType::Struct(
+            fields
+                .iter()
+                .map(|f| to_field(value.get(f.name()), f.data_type()))
+                .collect::<Result<_, _>>()?,
+        ),
+        (JsonValue::Object(entries), DataType::Map(values)) => FieldType::Map(
+            entries
+                .iter()
+                .map(|(k, v)| Ok((k.as_str(), to_field(Some(v), values)?)))
+                .collect::<Result<_, _>>()?,
+        ),
+        (JsonValue::Object(_), _) => return Err("unexpected object"),
+    })
+}
+
+fn number<'a>(text: &'a str, data_type: &DataType) -> Result<FieldType<'a>, &'static str> {
+    fn parse<T: std::str::FromStr>(text: &str) -> Result<T, &'static str> {
+        text.parse().map_err(|_| "number doesn't fit the column")
+    }
+    Ok(match data_type.physical() {
+        DataType::Int8 => FieldType::Int8(parse(text)?),
+        DataType::Int16 => FieldType::Int16(parse(text)?),
+        DataType::Int32 => FieldType::Int32(parse(text)?),
+        DataType::Int64 => FieldType::Int64(parse(text)?),
+        DataType::UInt8 => FieldType::UInt8(parse(text)?),
+        DataType::UInt16 => FieldType::UInt16(parse(text)?),
+        DataType::UInt32 => FieldType::UInt32(parse(text)?),
+        DataType::UInt64 => FieldType::UInt64(parse(text)?),
+        DataType::Float32 => FieldType::Float32(parse(text)?),
+        DataType::Float64 => FieldType::Float64(parse(text)?),
+        // decimals parse the digits themselves, text columns keep them
+        _ => FieldType::Text(text),
+    })
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8, message: &'static str) -> Result<(), &'static str> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(message);
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, &'static str> {
+        if !self.bytes[self.position..].starts_with(word.as_bytes()) {
+            return Err("unexpected character");
+        }
+        self.position += word.len();
+        Ok(value)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<JsonValue, &'static str> {
+        if depth == MAX_DEPTH {
+            return Err("nested too deep");
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err("unexpected end of input"),
+            Some(b'n') => self.literal("null", JsonValue::Null),
+            Some(b't') => self.literal("true", JsonValue::Boolean(true)),
+            Some(b'f') => self.literal("false", JsonValue::Boolean(false)),
+            Some(b'"') => Ok(JsonValue::String(self.string()?)),
+            Some(b'[') => {
+                self.position += 1;
+                let mut values = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Ok(JsonValue::Array(values));
+                }
+                loop {
+                    values.push(self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(JsonValue::Array(values));
+                        }
+                        _ => return Err("expected ',' or ']'"),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut entries = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Ok(JsonValue::Object(entries));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some(b'"') {
+                        return Err("expected a key");
+                    }
+                    let key = self.string()?;
+                    self.expect(b':', "expected ':'")?;
+                    entries.push((key, self.value(depth + 1)?));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(JsonValue::Object(entries));
+                        }
+                        _ => return Err("expected ',' or '}'"),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err("unexpected character"),
+        }
+    }
+
+    // -?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?
+    fn number(&mut self) -> Result<JsonValue, &'static str> {
+        let start = self.position;
+        let digits = |parser: &mut Parser| {
+            let start = parser.position;
+            while let Some(b'0'..=b'9') = parser.peek() {
+                parser.position += 1;
+            }
+            parser.position - start
+        };
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        match digits(self) {
+            0 => return Err("expected a digit"),
+            n if n > 1 && self.bytes[self.position - n] == b'0' => {
+                return Err("numbers can't have leading zeros");
+            }
+            _ => {}
+        }
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            if digits(self) == 0 {
+                return Err("expected a digit");
+            }
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.position += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.position += 1;
+            }
+            if digits(self) == 0 {
+                return Err("expected a digit");
+            }
+        }
+        // only ascii was consumed
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+        Ok(JsonValue::Number(text.to_string()))
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        // the opening quote
+        self.position += 1;
+        let mut out = String::new();
+        loop {
+            // everything up to the next quote or escape is copied as is, it is valid utf-8
+            // since the input is a str and quotes and backslashes are never inside a character
+            let start = self.position;
+            while let Some(byte) = self.peek() {
+                match byte {
+                    b'"' | b'\\' => break,
+                    0..=0x1f => return Err("control character in string"),
+                    _ => self.position += 1,
+                }
+            }
+            out += std::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+            match self.peek() {
+                None => return Err("unterminated string"),
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(out);
+                }
+                _ => {}
+            }
+            self.position += 1;
+            let escape = self.peek().ok_or("unterminated string")?;
+            self.position += 1;
+            match escape {
+                b'"' => out.push('"'),
+                b'\\' => out.push('\\'),
+                b'/' => out.push('/'),
+                b'b' => out.push('\u{8}'),
+                b'f' => out.push('\u{c}'),
+                b'n' => out.push('\n'),
+                b'r' => out.push('\r'),
+                b't' => out.push('\t'),
+                b'u' => out.push(self.unicode_escape()?),
+                _ => return Err("unknown escape"),
+            }
+        }
+    }
+
+    // the part after \u, characters outside the basic plane are a pair of surrogates.
+    fn unicode_escape(&mut self) -> Result<char, &'static str> {
+        let high = self.hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or("bad unicode escape");
+        }
+        if !self.bytes[self.position..].starts_with(b"\\u") {
+            return Err("unpaired surrogate");
+        }
+        self.position += 2;
+        let low = self.hex4()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err("unpaired surrogate");
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or("bad unicode escape")
+    }
+
+    fn hex4(&mut self) -> Result<u32, &'static str> {
+        let digits = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .ok_or("bad unicode escape")?;
+        self.position += 4;
+        digits.iter().try_fold(0, |value, &b| {
+            let digit = (b as char).to_digit(16).ok_or("bad unicode escape")?;
+            Ok(value * 16 + digit)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{JsonValue, parse, read_json_lines};
+    use crate::database::{ColumnType, DataType, Field};
+
+    #[test]
+    fn values() {
+        let value =
+            parse(r#" {"a": [1, -2.5e3, true, null], "b": {"c": "x\"y\u00e9\ud83d\ude00"}} "#)
+                .unwrap();
+        let JsonValue::Array(a) = value.get("a").unwrap() else {
+            panic!("expected an array");
+        };
+        assert_eq!(a[1], JsonValue::Number("-2.5e3".to_string()));
+        assert_eq!(a[2], JsonValue::Boolean(true));
+        assert_eq!(a[3], JsonValue::Null);
+        assert_eq!(
+            value.get("b").unwrap().get("c"),
+            Some(&JsonValue::String("x\"yé😀".to_string()))
+        );
+        assert_eq!(parse("[]"), Ok(JsonValue::Array(vec![])));
+
+        for text in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "1.",
+            "-",
+            "tru",
+            "\"a",
+            "\"\\x\"",
+            "\"\\ud800\"",
+            "1 2",
+            "{a: 1}",
+            "\"\t\"",
+        ] {
+            assert!(parse(text).is_err(), "{text}");
+        }
+        assert!(parse(&"[".repeat(200)).is_err());
+    }
+
+    #[test]
+    fn json_lines() {
+        let schema = vec![
+            Field::new("id".to_string(), DataType::Int64),
+            Field::new("price".to_string(), DataType::Decimal128(10, 2)),
+            Field::new("tags".to_string(), DataType::List(Box::new(DataType::Text))),
+            Field::new(
+                "address".to_string(),
+                DataType::Struct(vec![
+                    Field::new("city".to_string(), DataType::Text),
+                    Field::new("zip".to_string(), DataType::Int32),
+                ]),
+            ),
+        ];
+        let text = r#"
+{"id": 9007199254740993, "price": "1.10", "tags": ["a", null], "address": {"city": "Oslo", "zip": 150}}
+{"id": 2, "price": 2.5, "unknown": [1], "address": null}
+
+{"id": 3, "address": {"zip": 7}}
+"#;
+        let database = read_json_lines(text, &schema, 2).unwrap();
+        assert_eq!(database.num_rows(), 3);
+        assert_eq!(database.schema(), schema);
+        let rg = database.row_group(0).unwrap();
+        let Some(ColumnType::Int64(ids)) = rg.column_by_path("id") else {
+            panic!("expected an int64 column");
+        };
+        assert_eq!(ids.data()[0], 9007199254740993);
+        let Some(ColumnType::Decimal128(prices)) = rg.column_by_path("price") else {
+            panic!("expected a decimal column");
+        };
+        assert_eq!(prices.format(1), Some(Some("2.50".to_string())));
+        let Some(ColumnType::Text(cities)) = rg.column_by_path("address.city") else {
+            panic!("expected a text column");
+        };
+        assert_eq!(cities.get(0), Some(Some("Oslo")));
+        assert_eq!(cities.get(1), Some(None));
+        let Some(ColumnType::List(tags)) = rg.column_by_path("tags") else {
+            panic!("expected a list column");
+        };
+        assert_eq!(tags.value_range(0), Some(Some(0..2)));
+        assert_eq!(tags.value_range(1), Some(None));
+
+        let Some(ColumnType::Int32(zips)) =
+            database.row_group(1).unwrap().column_by_path("address.zip")
+        else {
+            panic!("expected an int32 column");
+        };
+        assert_eq!(zips.data(), &[7]);
+
+        let error = read_json_lines("{\"id\": 1}\n{\"id\": 1.5}", &schema, 2).unwrap_err();
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.to_string(), "line 2: number doesn't fit the column");
+        let error = read_json_lines("[1]", &schema, 2).unwrap_err();
+        assert_eq!(error.message(), "expected an object");
+        let error = read_json_lines(r#"{"address": [1]}"#, &schema, 2).unwrap_err();
+        assert_eq!(error.message(), "unexpected array");
+    }
+
+    #[test]
+    fn json_maps() {
+        let labels = DataType::Map(Box::new(DataType::Int32));
+        let schema = vec![Field::new("labels".to_string(), labels)];
+        let text = "{\"labels\": {\"a\": 1, \"b\": null}}\n{\"labels\": {}}\n{}";
+        let database = read_json_lines(text, &schema, 8).unwrap();
+        let Some(ColumnType::Map(labels)) = database.row_group(0).unwrap().column_by_path("labels")
+        else {
+            panic!("expected a map column");
+        };
+        assert_eq!(labels.offsets(), &[0, 2, 2, 2]);
+        assert!(!labels.is_valid(2));
+        assert_eq!(labels.find(0, "b"), Some(1));
+
+        let error = read_json_lines("{\"labels\": {\"a\": \"x\"}}", &schema, 8).unwrap_err();
+        assert_eq!(error.line(), 1);
+    }
+
+    #[test]
+    fn rejected_rows_keep_the_column() {
+        let colors = DataType::Enum(vec!["red".to_string(), "green".to_string()]);
+        let schema = vec![Field::new("color".to_string(), colors)];
+        let error =
+            read_json_lines("{\"color\": \"red\"}\n{\"color\": \"blue\"}", &schema, 8).unwrap_err();
+        assert_eq!(error.message(), "unknown enum label");
+        assert_eq!(
+            error.to_string(),
+            "line 2: column color: unknown enum label \"blue\""
+        );
+        assert_eq!(error.row_error().map(|e| e.column()), Some("color"));
+    }
+}
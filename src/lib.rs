@@ -0,0 +1,28 @@
+pub mod arithmetic;
+pub mod array;
+pub mod bitpack;
+pub mod bitvec;
+pub mod bloom;
+pub mod boolean;
+pub mod cast;
+pub mod codec;
+pub mod database;
+pub mod decimal;
+pub mod dictionary;
+pub mod enum_array;
+pub mod file;
+pub mod fixed;
+pub mod fixed_binary;
+pub mod gorilla;
+pub mod json;
+pub mod list;
+pub mod map;
+pub mod predicate;
+pub mod rle;
+pub mod stats;
+pub mod storage;
+pub mod strings;
+pub mod struct_array;
+pub mod temporal;
+pub mod util;
+pub mod variable;
@@ -0,0 +1,546 @@
+// A list column holds a list of values per row, e.g. the tags of an event or the measurements
+// taken during it. The values of every list are stored back to back in a child column and an
+// offset buffer tells where each list starts and ends, the same scheme Variable uses for bytes:
+// list i is child[offsets[i]..offsets[i + 1]]. For example offsets [0, 2, 2, 5] are three lists,
+// the first has the values 0 and 1 of the child, the second is empty and the third has 2, 3 and 4.
+//
+// Nulls exist on both levels. A null list has its own validity bit and no values (its two offsets
+// are equal), while a null inside a list is just a null of the child. So [] and null are different
+// rows, and so are [null] and [].
+//
+// The child can be any column, a `Fixed`, a `Variable` or a whole `ColumnType` (which makes lists
+// of lists possible). Kernels that compare values are implemented per child type.
+
+use std::ops::Range;
+
+use crate::bitvec::BitVec;
+use crate::boolean::BooleanArray;
+use crate::database::{ColumnType, FieldType};
+use crate::fixed::{Fixed, Native};
+use crate::stats::{ColumnStats, ToScalar};
+use crate::variable::{FromByteRef, Offset, ToBytesRef, Variable};
+
+/// A column that can hold the values of a [`List`].
+pub trait ListChild: Sized {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copies `length` values starting at `offset` into a new column.
+    fn slice(&self, offset: usize, length: usize) -> Self;
+
+    /// Adds every value of other to the end.
+    fn append(&mut self, other: &Self);
+
+    fn add_null(&mut self);
+
+    fn stats(&self) -> ColumnStats;
+
+    /// A column holding value i of this one for every `Some(i)` and a null for every `None`.
+    fn take(&self, indices: &[Option<usize>]) -> Self {
+        let mut taken = self.slice(0, 0);
+        for index in indices {
+            match index {
+                Some(index) => taken.append(&self.slice(*index, 1)),
+                None => taken.add_null(),
+            }
+        }
+        taken
+    }
+}
+
+impl<T: Native> ListChild for Fixed<T> {
+    fn len(&self) -> usize {
+        Fixed::len(self)
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Fixed<T> {
+        Fixed::slice(self, offset, length)
+    }
+
+    fn append(&mut self, other: &Fixed<T>) {
+        Fixed::append(self, other)
+    }
+
+    fn add_null(&mut self) {
+        Fixed::add_null(self)
+    }
+
+    fn stats(&self) -> ColumnStats {
+        Fixed::stats(self)
+    }
+}
+
+impl<T, O> ListChild for Variable<T, O>
+where
+    T: FromByteRef + ToBytesRef + ToScalar + ?Sized,
+    O: Offset,
+{
+    fn len(&self) -> usize {
+        Variable::len(self)
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> Variable<T, O> {
+        Variable::slice(self, offset, length)
+    }
+
+    fn append(&mut self, other: &Variable<T, O>) {
+        Variable::append(self, other)
+    }
+
+    fn add_null(&mut self) {
+        Variable::add_null(self)
+    }
+
+    fn stats(&self) -> ColumnStats {
+        Variable::stats(self)
+    }
+}
+
+impl ListChild for ColumnType {
+    fn len(&self) -> usize {
+        ColumnType::len(self)
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> ColumnType {
+        ColumnType::slice(self, offset, length)
+    }
+
+    /// Panics when the column types don't match.
+    fn append(&mut self, other: &ColumnType) {
+        ColumnType::append(self, other).expect("list values have a different type")
+    }
+
+    fn add_null(&mut self) {
+        ColumnType::add_null(self)
+    }
+
+    fn stats(&self) -> ColumnStats {
+        ColumnType::stats(self)
+    }
+}
+
+#[derive(Debug)]
+pub struct List<C> {
+    offsets: Vec<i32>,
+    // boxed so a ColumnType can hold a list of ColumnType
+    values: Box<C>,
+    validity: Option<BitVec>,
+}
+
+impl<C: ListChild> List<C> {
+    /// A column without lists, `values` must be empty.
+    pub fn new(values: C) -> List<C> {
+        List::with_capacity(values, 0)
+    }
+
+    pub fn with_capacity(values: C, capacity: usize) -> List<C> {
+        assert!(
+            values.is_empty(),
+            "values of a new list column must be empty"
+        );
+        let mut offsets = Vec::with_capacity(capacity + 1);
+        offsets.push(0);
+        List {
+            offsets,
+            values: Box::new(values),
+            validity: None,
+        }
+    }
+
+    // wraps existing offsets and values, e.g. when reading a file. A null list must not have any
+    // values, otherwise validity works like in `Fixed::from_parts`.
+    pub fn from_parts(
+        offsets: Vec<i32>,
+        values: C,
+        validity: impl Into<Option<BitVec>>,
+    ) -> List<C> {
+        assert_eq!(offsets.first(), Some(&0), "offsets must start at 0");
+        assert!(
+            offsets.windows(2).all(|w| w[0] <= w[1]),
+            "offsets are out of order"
+        );
+        assert_eq!(
+            offsets[offsets.len() - 1].to_usize(),
+            values.len(),
+            "offsets don't cover the values"
+        );
+        let validity = validity.into().filter(|validity| validity.null_count() > 0);
+        if let Some(validity) = &validity {
+            assert_eq!(
+                offsets.len() - 1,
+                validity.len(),
+                "offsets and validity length missmatch"
+            );
+            assert!(
+                (0..validity.len()).all(|i| validity[i] || offsets[i] == offsets[i + 1]),
+                "null lists can't have values"
+            );
+        }
+        List {
+            offsets,
+            values: Box::new(values),
+            validity,
+        }
+    }
+
+    /// Where every list starts, plus where the last one ends.
+    pub fn offsets(&self) -> &[i32] {
+        &self.offsets
+    }
+
+    /// The values of every list back to back.
+    pub fn values(&self) -> &C {
+        &self.values
+    }
+
+    /// Values added here belong to the next list, see `finish_list`.
+    pub fn values_mut(&mut self) -> &mut C {
+        &mut self.values
+    }
+
+    /// The validity bitmap of the lists, `None` when every list is valid.
+    pub fn validity(&self) -> Option<&BitVec> {
+        self.validity.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.validity
+            .as_ref()
+            .is_none_or(|validity| validity[index])
+    }
+
+    /// Number of null lists, nulls inside lists are counted by the values.
+    pub fn null_count(&self) -> usize {
+        self.validity.as_ref().map_or(0, BitVec::null_count)
+    }
+
+    /// Ends a list holding every value added to `values_mut` since the last one ended.
+    pub fn finish_list(&mut self) {
+        self.offsets.push(i32::from_usize(self.values.len()));
+        if let Some(validity) = &mut self.validity {
+            validity.push(true);
+        }
+    }
+
+    /// Panics when values were added for a list that wasn't finished.
+    pub fn add_null(&mut self) {
+        assert_eq!(
+            self.end(),
+            self.values.len(),
+            "null lists can't have values"
+        );
+        let len = self.len();
+        self.validity
+            .get_or_insert_with(|| BitVec::ones(len))
+            .push(false);
+        self.offsets.push(i32::from_usize(self.values.len()));
+    }
+
+    // values past the last offset belong to a list that isn't finished yet
+    fn end(&self) -> usize {
+        self.offsets[self.len()].to_usize()
+    }
+
+    /// Where the values of list `index` are in `values`.
+    pub fn value_range(&self, index: usize) -> Option<Option<Range<usize>>> {
+        if index >= self.len() {
+            return None;
+        }
+        if !self.is_valid(index) {
+            return Some(None);
+        }
+        let (start, end) = (self.offsets[index], self.offsets[index + 1]);
+        Some(Some(start.to_usize()..end.to_usize()))
+    }
+
+    /// A copy of the values of list `index`.
+    pub fn get(&self, index: usize) -> Option<Option<C>> {
+        Some(
+            self.value_range(index)?
+                .map(|range| self.values.slice(range.start, range.len())),
+        )
+    }
+
+    // copies `length` lists starting at `offset` into a new column.
+    pub fn slice(&self, offset: usize, length: usize) -> List<C> {
+        assert!(offset + length <= self.len(), "range out of bounds");
+        let start = self.offsets[offset];
+        let end = self.offsets[offset + length];
+        List::from_parts(
+            self.offsets[offset..=offset + length]
+                .iter()
+                .map(|o| o - start)
+                .collect(),
+            self.values
+                .slice(start.to_usize(), (end - start).to_usize()),
+            self.validity
+                .as_ref()
+                .map(|validity| validity.slice(offset, length)),
+        )
+    }
+
+    // adds every list of other to the end.
+    pub fn append(&mut self, other: &List<C>) {
+        assert_eq!(
+            self.end(),
+            self.values.len(),
+            "can't append while a list isn't finished"
+        );
+        self.values.append(&other.values.slice(0, other.end()));
+        if self.validity.is_some() || other.validity.is_some() {
+            let len = self.len();
+            let validity = self.validity.get_or_insert_with(|| BitVec::ones(len));
+            for index in 0..other.len() {
+                validity.push(other.is_valid(index));
+            }
+        }
+        let base = *self.offsets.last().unwrap();
+        self.offsets
+            .extend(other.offsets[1..].iter().map(|o| o + base));
+    }
+
+    /// The values of every list back to back, null and empty lists add nothing.
+    pub fn flatten(&self) -> C {
+        self.values.slice(0, self.end())
+    }
+
+    /// One row per value: the index of the list each value came from and the values, like
+    /// `flatten`. Repeating the other columns of the table by those indexes turns a list of
+    /// tags into a row per tag. Null and empty lists produce no rows.
+    pub fn explode(&self) -> (Vec<usize>, C) {
+        let mut indices = Vec::with_capacity(self.end());
+        for (index, w) in self.offsets.windows(2).enumerate() {
+            indices.extend(std::iter::repeat_n(index, (w[1] - w[0]).to_usize()));
+        }
+        (indices, self.flatten())
+    }
+
+    /// The number of values in every list, null for null lists.
+    pub fn lengths(&self) -> Fixed<i32> {
+        let mut lengths = Fixed::with_capacity(self.len());
+        for (index, w) in self.offsets.windows(2).enumerate() {
+            if self.is_valid(index) {
+                lengths.add_record(w[1] - w[0]);
+            } else {
+                lengths.add_null();
+            }
+        }
+        lengths
+    }
+
+    // a list column has no min or max, its size includes the values.
+    pub fn stats(&self) -> ColumnStats {
+        let byte_size = self.offsets.len() * 4
+            + self.validity.as_ref().map_or(0, |v| v.as_bytes().len())
+            + self.values.stats().byte_size();
+        ColumnStats::new(None, None, self.null_count(), 0, byte_size)
+    }
+
+    // `matches` tells if value i of `values` is the one searched for, None when it is null. Like
+    // SQL, a list without the value but with a null gives null since the null might be it.
+    fn contains_by(&self, matches: impl Fn(usize) -> Option<bool>) -> BooleanArray {
+        let mut result = BooleanArray::with_capacity(self.len());
+        for index in 0..self.len() {
+            let Some(range) = self.value_range(index).unwrap() else {
+                result.add_null();
+                continue;
+            };
+            let mut found = Some(false);
+            for value in range {
+                match matches(value) {
+                    Some(true) => {
+                        found = Some(true);
+                        break;
+                    }
+                    Some(false) => {}
+                    None => found = None,
+                }
+            }
+            match found {
+                Some(found) => result.add_record(found),
+                None => result.add_null(),
+            }
+        }
+        result
+    }
+}
+
+impl<T: Native> List<Fixed<T>> {
+    /// Adds a list holding `values`.
+    pub fn add_list(&mut self, values: &[Option<T>]) {
+        for value in values {
+            match value {
+                Some(value) => self.values.add_record(*value),
+                None => self.values.add_null(),
+            }
+        }
+        self.finish_list();
+    }
+
+    /// Whether every list holds `value`, see `contains_by` for nulls.
+    pub fn contains(&self, value: T) -> BooleanArray {
+        let values = self.values.data();
+        self.contains_by(|i| self.values.is_valid(i).then(|| values[i] == value))
+    }
+}
+
+impl<T, O> List<Variable<T, O>>
+where
+    T: FromByteRef + ToBytesRef + ToScalar + PartialEq + ?Sized,
+    O: Offset,
+{
+    /// Adds a list holding `values`.
+    pub fn add_list(&mut self, values: &[Option<&T>]) {
+        for value in values {
+            match value {
+                Some(value) => self.values.add(value),
+                None => self.values.add_null(),
+            }
+        }
+        self.finish_list();
+    }
+
+    /// Whether every list holds `value`, see `contains_by` for nulls.
+    pub fn contains(&self, value: &T) -> BooleanArray {
+        self.contains_by(|i| self.values.get(i).unwrap().map(|v| v == value))
+    }
+}
+
+impl List<ColumnType> {
+    /// Adds a list holding `fields`. When a field doesn't fit the values nothing is added.
+    pub fn add_fields(&mut self, fields: &[FieldType]) -> Result<(), &'static str> {
+        let mut values = ColumnType::empty(&self.values.data_type(), fields.len());
+        for field in fields {
+            values.add_field(field)?;
+        }
+        self.values.append(&values)?;
+        self.finish_list();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::List;
+    use crate::database::{ColumnType, DataType, FieldType};
+    use crate::fixed::Fixed;
+    use crate::variable::StringArray;
+
+    fn tags() -> List<StringArray> {
+        let mut tags = List::new(StringArray::new());
+        tags.add_list(&[Some("red"), Some("big")]);
+        tags.add_list(&[]);
+        tags.add_null();
+        tags.add_list(&[None, Some("small")]);
+        tags
+    }
+
+    #[test]
+    fn lists_and_nulls() {
+        let tags = tags();
+        assert_eq!(tags.len(), 4);
+        assert_eq!(tags.offsets(), &[0, 2, 2, 2, 4]);
+        assert_eq!(tags.null_count(), 1);
+        assert_eq!(tags.values().null_count(), 1);
+        assert_eq!(tags.value_range(0), Some(Some(0..2)));
+        assert_eq!(tags.value_range(1), Some(Some(2..2)));
+        assert_eq!(tags.value_range(2), Some(None));
+        assert_eq!(tags.value_range(4), None);
+
+        let last = tags.get(3).unwrap().unwrap();
+        assert_eq!(last.get(0), Some(None));
+        assert_eq!(last.get(1), Some(Some("small")));
+        assert!(tags.get(1).unwrap().unwrap().is_empty());
+        assert!(tags.get(2).unwrap().is_none());
+
+        let lengths: Vec<_> = tags.lengths().get_records().collect();
+        assert_eq!(lengths, vec![Some(2), Some(0), None, Some(2)]);
+        assert_eq!(tags.stats().null_count(), 1);
+        assert_eq!(tags.stats().min(), None);
+    }
+
+    #[test]
+    fn slice_and_append() {
+        let tags = tags();
+        let sliced = tags.slice(2, 2);
+        assert_eq!(sliced.offsets(), &[0, 0, 2]);
+        assert!(!sliced.is_valid(0));
+        assert_eq!(sliced.values().len(), 2);
+
+        let mut appended = tags.slice(0, 1);
+        assert_eq!(appended.validity(), None);
+        appended.append(&sliced);
+        assert_eq!(appended.offsets(), &[0, 2, 2, 4]);
+        assert_eq!(appended.null_count(), 1);
+        assert_eq!(
+            appended.get(2).unwrap().unwrap().get(1),
+            Some(Some("small"))
+        );
+    }
+
+    #[test]
+    fn flatten_and_explode() {
+        let tags = tags();
+        let (indices, values) = tags.explode();
+        assert_eq!(indices, vec![0, 0, 3, 3]);
+        assert_eq!(values.len(), 4);
+        assert_eq!(values.get(1), Some(Some("big")));
+        assert_eq!(tags.flatten().get(2), Some(None));
+    }
+
+    #[test]
+    fn contains() {
+        let tags = tags();
+        let found: Vec<_> = tags.contains("big").get_records().collect();
+        assert_eq!(found, vec![Some(true), Some(false), None, None]);
+        let found: Vec<_> = tags.contains("small").get_records().collect();
+        assert_eq!(found, vec![Some(false), Some(false), None, Some(true)]);
+
+        let mut measurements = List::new(Fixed::<f64>::new());
+        measurements.add_list(&[Some(1.5), Some(2.0)]);
+        measurements.add_list(&[Some(f64::NAN)]);
+        let found: Vec<_> = measurements.contains(2.0).get_records().collect();
+        assert_eq!(found, vec![Some(true), Some(false)]);
+    }
+
+    #[test]
+    fn column_values() {
+        let data_type = DataType::List(Box::new(DataType::Int32));
+        let ColumnType::List(mut lists) = ColumnType::empty(&data_type, 2) else {
+            panic!("expected a list column");
+        };
+        lists
+            .add_fields(&[FieldType::Int32(1), FieldType::Null])
+            .unwrap();
+        // nothing of a list that doesn't fit is kept
+        assert!(
+            lists
+                .add_fields(&[FieldType::Int32(2), FieldType::Text("x")])
+                .is_err()
+        );
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists.values().len(), 2);
+        let column = ColumnType::List(lists);
+        assert_eq!(column.data_type(), data_type);
+        assert_eq!(column.type_str(), "list");
+    }
+
+    #[test]
+    #[should_panic(expected = "null lists can't have values")]
+    fn null_lists_have_no_values() {
+        let mut lists = List::new(Fixed::<i32>::new());
+        lists.values_mut().add_record(1);
+        lists.add_null();
+    }
+}
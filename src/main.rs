@@ -1,16 +1,11 @@
-use crate::{
+use columnar_data::{
     database::{Column, ColumnType, Database, FieldType, Row},
     fixed::Int8Array,
     variable::StringArray,
 };
 
-mod bitvec;
-mod database;
-mod fixed;
-mod storage;
+#[allow(dead_code)]
 mod text;
-mod util;
-mod variable;
 
 fn main() {
     // let col = fixed::FloatArray::test_new();
@@ -0,0 +1,375 @@
+// A map column holds a bag of key/value pairs per row, e.g. the labels of a metric. It is a list
+// column whose values are entries: a key column and a value column of the same length, like a
+// struct with the fields key and value. Map i is entries[offsets[i]..offsets[i + 1]].
+//
+// Keys are never null, values can be. A null map has no entries and differs from an empty one.
+// Keys are not deduplicated, a lookup finds the first entry with the key.
+//
+// The maps of a `ColumnType` have text keys (what JSON objects have) and values of any type.
+
+use std::ops::Range;
+
+use crate::bitvec::BitVec;
+use crate::database::{ColumnType, FieldType};
+use crate::fixed::{Fixed, Native};
+use crate::list::{List, ListChild};
+use crate::stats::{ColumnStats, ToScalar};
+use crate::variable::{FromByteRef, Offset, StringArray, ToBytesRef, Variable};
+
+/// The entries of a map column: the key and the value of every entry.
+#[derive(Debug)]
+pub struct MapEntries<K, V> {
+    keys: K,
+    values: V,
+}
+
+impl<K: ListChild, V: ListChild> MapEntries<K, V> {
+    /// Panics when `keys` and `values` have different lengths.
+    pub fn new(keys: K, values: V) -> MapEntries<K, V> {
+        assert_eq!(keys.len(), values.len(), "keys and values length missmatch");
+        MapEntries { keys, values }
+    }
+
+    pub fn keys(&self) -> &K {
+        &self.keys
+    }
+
+    pub fn values(&self) -> &V {
+        &self.values
+    }
+}
+
+impl<K: ListChild, V: ListChild> ListChild for MapEntries<K, V> {
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn slice(&self, offset: usize, length: usize) -> MapEntries<K, V> {
+        MapEntries {
+            keys: self.keys.slice(offset, length),
+            values: self.values.slice(offset, length),
+        }
+    }
+
+    fn append(&mut self, other: &MapEntries<K, V>) {
+        self.keys.append(&other.keys);
+        self.values.append(&other.values);
+    }
+
+    /// Panics, keys can't be null.
+    fn add_null(&mut self) {
+        panic!("map keys can't be null");
+    }
+
+    // entries have no min or max, their size is the size of both columns.
+    fn stats(&self) -> ColumnStats {
+        let byte_size = self.keys.stats().byte_size() + self.values.stats().byte_size();
+        ColumnStats::new(None, None, 0, 0, byte_size)
+    }
+}
+
+#[derive(Debug)]
+pub struct Map<K, V> {
+    entries: List<MapEntries<K, V>>,
+}
+
+impl<K: ListChild, V: ListChild> Map<K, V> {
+    /// A column without maps, `keys` and `values` must be empty.
+    pub fn new(keys: K, values: V) -> Map<K, V> {
+        Map::with_capacity(keys, values, 0)
+    }
+
+    pub fn with_capacity(keys: K, values: V, capacity: usize) -> Map<K, V> {
+        Map {
+            entries: List::with_capacity(MapEntries::new(keys, values), capacity),
+        }
+    }
+
+    // builds a column from existing buffers, e.g. when reading a file. Offsets and validity work
+    // like the ones of a `List`.
+    pub fn from_parts(
+        offsets: Vec<i32>,
+        keys: K,
+        values: V,
+        validity: impl Into<Option<BitVec>>,
+    ) -> Map<K, V> {
+        assert_eq!(keys.stats().null_count(), 0, "map keys can't be null");
+        let entries = MapEntries::new(keys, values);
+        Map {
+            entries: List::from_parts(offsets, entries, validity),
+        }
+    }
+
+    /// The maps as a list of entries.
+    pub fn entries(&self) -> &List<MapEntries<K, V>> {
+        &self.entries
+    }
+
+    /// The keys of every map back to back.
+    pub fn keys(&self) -> &K {
+        &self.entries.values().keys
+    }
+
+    /// The values of every map back to back.
+    pub fn values(&self) -> &V {
+        &self.entries.values().values
+    }
+
+    /// Keys added here belong to the next map, see `finish_map`.
+    pub fn keys_mut(&mut self) -> &mut K {
+        &mut self.entries.values_mut().keys
+    }
+
+    /// Values added here belong to the next map, see `finish_map`.
+    pub fn values_mut(&mut self) -> &mut V {
+        &mut self.entries.values_mut().values
+    }
+
+    pub fn offsets(&self) -> &[i32] {
+        self.entries.offsets()
+    }
+
+    /// The validity bitmap of the maps, `None` when every map is valid.
+    pub fn validity(&self) -> Option<&BitVec> {
+        self.entries.validity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.entries.is_valid(index)
+    }
+
+    /// Number of null maps.
+    pub fn null_count(&self) -> usize {
+        self.entries.null_count()
+    }
+
+    /// Ends a map holding every key and value added since the last one ended. Panics when a
+    /// different number of keys and values were added, or a null key.
+    pub fn finish_map(&mut self) {
+        let entries = self.entries.values();
+        assert_eq!(
+            entries.keys.len(),
+            entries.values.len(),
+            "keys and values length missmatch"
+        );
+        assert_eq!(
+            entries.keys.stats().null_count(),
+            0,
+            "map keys can't be null"
+        );
+        self.entries.finish_list();
+    }
+
+    pub fn add_null(&mut self) {
+        self.entries.add_null();
+    }
+
+    /// Where the entries of map `index` are in `keys` and `values`.
+    pub fn value_range(&self, index: usize) -> Option<Option<Range<usize>>> {
+        self.entries.value_range(index)
+    }
+
+    /// A copy of the entries of map `index`.
+    pub fn get(&self, index: usize) -> Option<Option<MapEntries<K, V>>> {
+        self.entries.get(index)
+    }
+
+    // copies `length` maps starting at `offset` into a new column.
+    pub fn slice(&self, offset: usize, length: usize) -> Map<K, V> {
+        Map {
+            entries: self.entries.slice(offset, length),
+        }
+    }
+
+    // adds every map of other to the end.
+    pub fn append(&mut self, other: &Map<K, V>) {
+        self.entries.append(&other.entries);
+    }
+
+    /// The keys of every map as a list, null for null maps.
+    pub fn map_keys(&self) -> List<K> {
+        let end = *self.offsets().last().unwrap() as usize;
+        List::from_parts(
+            self.offsets().to_vec(),
+            self.keys().slice(0, end),
+            self.validity().cloned(),
+        )
+    }
+
+    /// The values of every map as a list, null for null maps.
+    pub fn map_values(&self) -> List<V> {
+        let end = *self.offsets().last().unwrap() as usize;
+        List::from_parts(
+            self.offsets().to_vec(),
+            self.values().slice(0, end),
+            self.validity().cloned(),
+        )
+    }
+
+    // a map column has no min or max, its size includes the entries.
+    pub fn stats(&self) -> ColumnStats {
+        self.entries.stats()
+    }
+
+    // `matches` tells if key i of `keys` is the one searched for. The first entry of every map
+    // that matches, None for null maps and maps without the key.
+    fn find_by(&self, matches: impl Fn(usize) -> bool) -> Vec<Option<usize>> {
+        (0..self.len())
+            .map(|index| self.value_range(index).unwrap()?.find(|&i| matches(i)))
+            .collect()
+    }
+}
+
+impl<T: Native, V: ListChild> Map<Fixed<T>, V> {
+    /// Where the value of `key` in map `index` is in `values`.
+    pub fn find(&self, index: usize, key: T) -> Option<usize> {
+        self.value_range(index)??
+            .find(|&i| self.keys().data()[i] == key)
+    }
+
+    /// The value of `key` in every map, null for null maps and maps without the key.
+    pub fn lookup(&self, key: T) -> V {
+        let keys = self.keys().data();
+        self.values().take(&self.find_by(|i| keys[i] == key))
+    }
+}
+
+impl<T, O, V> Map<Variable<T, O>, V>
+where
+    T: FromByteRef + ToBytesRef + ToScalar + PartialEq + ?Sized,
+    O: Offset,
+    V: ListChild,
+{
+    /// Where the value of `key` in map `index` is in `values`.
+    pub fn find(&self, index: usize, key: &T) -> Option<usize> {
+        self.value_range(index)??
+            .find(|&i| self.keys().get(i) == Some(Some(key)))
+    }
+
+    /// The value of `key` in every map, null for null maps and maps without the key.
+    pub fn lookup(&self, key: &T) -> V {
+        let keys = self.keys();
+        self.values()
+            .take(&self.find_by(|i| keys.get(i) == Some(Some(key))))
+    }
+}
+
+impl Map<StringArray, ColumnType> {
+    /// Adds a map holding `entries`. When a value doesn't fit the values nothing is added.
+    pub fn add_fields(&mut self, entries: &[(&str, FieldType)]) -> Result<(), &'static str> {
+        let mut values = ColumnType::empty(&self.values().data_type(), entries.len());
+        for (_, value) in entries {
+            values.add_field(value)?;
+        }
+        for (key, _) in entries {
+            self.keys_mut().add(key);
+        }
+        self.values_mut().append(&values)?;
+        self.finish_map();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Map;
+    use crate::database::{ColumnType, DataType, FieldType};
+    use crate::fixed::Fixed;
+    use crate::variable::StringArray;
+
+    fn labels() -> Map<StringArray, Fixed<i32>> {
+        let mut labels = Map::new(StringArray::new(), Fixed::new());
+        for (key, value) in [("env", Some(1)), ("zone", None)] {
+            labels.keys_mut().add(key);
+            match value {
+                Some(value) => labels.values_mut().add_record(value),
+                None => labels.values_mut().add_null(),
+            }
+        }
+        labels.finish_map();
+        labels.add_null();
+        labels.finish_map();
+        labels.keys_mut().add("zone");
+        labels.values_mut().add_record(3);
+        labels.finish_map();
+        labels
+    }
+
+    #[test]
+    fn lookups() {
+        let labels = labels();
+        assert_eq!(labels.len(), 4);
+        assert_eq!(labels.offsets(), &[0, 2, 2, 2, 3]);
+        assert_eq!(labels.find(0, "zone"), Some(1));
+        assert_eq!(labels.find(3, "zone"), Some(2));
+        assert_eq!(labels.find(1, "zone"), None);
+        assert_eq!(labels.find(3, "env"), None);
+
+        let zones: Vec<_> = labels.lookup("zone").get_records().collect();
+        assert_eq!(zones, vec![None, None, None, Some(3)]);
+        let envs: Vec<_> = labels.lookup("env").get_records().collect();
+        assert_eq!(envs, vec![Some(1), None, None, None]);
+
+        let entries = labels.get(0).unwrap().unwrap();
+        assert_eq!(entries.keys().get(1), Some(Some("zone")));
+        assert!(labels.get(1).unwrap().is_none());
+    }
+
+    #[test]
+    fn keys_and_values() {
+        let labels = labels();
+        let keys = labels.map_keys();
+        assert_eq!(keys.len(), 4);
+        assert!(!keys.is_valid(1));
+        assert_eq!(keys.get(3).unwrap().unwrap().get(0), Some(Some("zone")));
+        let lengths: Vec<_> = labels.map_values().lengths().get_records().collect();
+        assert_eq!(lengths, vec![Some(2), None, Some(0), Some(1)]);
+
+        let mut appended = labels.slice(2, 2);
+        appended.append(&labels.slice(0, 1));
+        assert_eq!(appended.offsets(), &[0, 0, 1, 3]);
+        assert_eq!(appended.find(2, "env"), Some(1));
+        assert_eq!(appended.null_count(), 0);
+    }
+
+    #[test]
+    fn integer_keys() {
+        let mut counts = Map::new(Fixed::<i64>::new(), StringArray::new());
+        counts.keys_mut().add_record(7);
+        counts.values_mut().add("seven");
+        counts.finish_map();
+        assert_eq!(counts.lookup(7).get(0), Some(Some("seven")));
+        assert_eq!(counts.lookup(8).get(0), Some(None));
+    }
+
+    #[test]
+    fn column_values() {
+        let data_type = DataType::Map(Box::new(DataType::Float64));
+        let ColumnType::Map(mut map) = ColumnType::empty(&data_type, 1) else {
+            panic!("expected a map column");
+        };
+        map.add_fields(&[("a", FieldType::Float64(1.0)), ("b", FieldType::Null)])
+            .unwrap();
+        assert!(map.add_fields(&[("c", FieldType::Text("x"))]).is_err());
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.keys().len(), 2);
+        assert_eq!(ColumnType::Map(map).data_type(), data_type);
+    }
+
+    #[test]
+    #[should_panic(expected = "map keys can't be null")]
+    fn keys_are_not_null() {
+        let mut map = Map::new(StringArray::new(), Fixed::<i32>::new());
+        map.keys_mut().add_null();
+        map.values_mut().add_record(1);
+        map.finish_map();
+    }
+}
@@ -0,0 +1,295 @@
+// predicates that can be pushed down to the file reader. The reader doesn't evaluate them row by
+// row, it only uses them to prove that a row group or page can't contain a matching row by looking
+// at its statistics. If the largest age in a page is 60, `age > 60` can't match anything there and
+// the page is never read. Everything that can't be proven is kept, so the rows handed back still
+// have to be filtered.
+
+use std::cmp::Ordering;
+
+use crate::bloom::BloomFilter;
+use crate::stats::{ColumnStats, Scalar};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    Compare {
+        column: String,
+        op: CompareOp,
+        value: Scalar,
+    },
+    /// `low <= column <= high`
+    Between {
+        column: String,
+        low: Scalar,
+        high: Scalar,
+    },
+    /// `column IN (values...)`
+    In {
+        column: String,
+        values: Vec<Scalar>,
+    },
+    IsNull(String),
+    IsNotNull(String),
+    And(Vec<Predicate>),
+    Or(Vec<Predicate>),
+}
+
+impl Predicate {
+    pub fn compare(column: &str, op: CompareOp, value: Scalar) -> Predicate {
+        Predicate::Compare {
+            column: column.to_string(),
+            op,
+            value,
+        }
+    }
+
+    pub fn eq(column: &str, value: Scalar) -> Predicate {
+        Predicate::compare(column, CompareOp::Eq, value)
+    }
+
+    pub fn lt(column: &str, value: Scalar) -> Predicate {
+        Predicate::compare(column, CompareOp::Lt, value)
+    }
+
+    pub fn gt(column: &str, value: Scalar) -> Predicate {
+        Predicate::compare(column, CompareOp::Gt, value)
+    }
+
+    pub fn between(column: &str, low: Scalar, high: Scalar) -> Predicate {
+        Predicate::Between {
+            column: column.to_string(),
+            low,
+            high,
+        }
+    }
+
+    pub fn is_in(column: &str, values: Vec<Scalar>) -> Predicate {
+        Predicate::In {
+            column: column.to_string(),
+            values,
+        }
+    }
+
+    pub fn is_null(column: &str) -> Predicate {
+        Predicate::IsNull(column.to_string())
+    }
+
+    pub fn is_not_null(column: &str) -> Predicate {
+        Predicate::IsNotNull(column.to_string())
+    }
+
+    /// Names of every column the predicate looks at.
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            Predicate::Compare { column, .. }
+            | Predicate::Between { column, .. }
+            | Predicate::In { column, .. }
+            | Predicate::IsNull(column)
+            | Predicate::IsNotNull(column) => vec![column.as_str()],
+            Predicate::And(predicates) | Predicate::Or(predicates) => {
+                predicates.iter().flat_map(|p| p.columns()).collect()
+            }
+        }
+    }
+
+    /// Returns true when the statistics prove that none of the `num_rows` rows can match.
+    /// `stats` looks up the statistics of a column by name; unknown columns never prune.
+    pub fn can_skip<'a>(
+        &self,
+        stats: &dyn Fn(&str) -> Option<&'a ColumnStats>,
+        num_rows: usize,
+    ) -> bool {
+        match self {
+            Predicate::Compare { column, op, value } => {
+                stats(column).is_some_and(|stats| compare_can_skip(stats, num_rows, *op, value))
+            }
+            Predicate::Between { column, low, high } => stats(column).is_some_and(|stats| {
+                compare_can_skip(stats, num_rows, CompareOp::GtEq, low)
+                    || compare_can_skip(stats, num_rows, CompareOp::LtEq, high)
+            }),
+            Predicate::In { column, values } => stats(column).is_some_and(|stats| {
+                values
+                    .iter()
+                    .all(|v| compare_can_skip(stats, num_rows, CompareOp::Eq, v))
+            }),
+            Predicate::IsNull(column) => stats(column).is_some_and(|s| s.null_count() == 0),
+            Predicate::IsNotNull(column) => {
+                stats(column).is_some_and(|s| s.null_count() == num_rows)
+            }
+            Predicate::And(predicates) => predicates.iter().any(|p| p.can_skip(stats, num_rows)),
+            Predicate::Or(predicates) => predicates.iter().all(|p| p.can_skip(stats, num_rows)),
+        }
+    }
+
+    /// Returns true when bloom filters prove that no row can match. Only equality and `IN` can
+    /// use a bloom filter; `bloom` looks up the filter of a column, if it has one.
+    pub fn bloom_can_skip<'a>(&self, bloom: &dyn Fn(&str) -> Option<&'a BloomFilter>) -> bool {
+        match self {
+            Predicate::Compare {
+                column,
+                op: CompareOp::Eq,
+                value,
+            } => bloom(column).is_some_and(|filter| !filter.might_contain(value)),
+            Predicate::In { column, values } => {
+                bloom(column).is_some_and(|filter| values.iter().all(|v| !filter.might_contain(v)))
+            }
+            Predicate::And(predicates) => predicates.iter().any(|p| p.bloom_can_skip(bloom)),
+            Predicate::Or(predicates) => predicates.iter().all(|p| p.bloom_can_skip(bloom)),
+            _ => false,
+        }
+    }
+
+    /// Names of columns whose bloom filter could help this predicate.
+    pub fn bloom_columns(&self) -> Vec<&str> {
+        match self {
+            Predicate::Compare {
+                column,
+                op: CompareOp::Eq,
+                ..
+            }
+            | Predicate::In { column, .. } => vec![column.as_str()],
+            Predicate::And(predicates) | Predicate::Or(predicates) => {
+                predicates.iter().flat_map(|p| p.bloom_columns()).collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+fn compare_can_skip(stats: &ColumnStats, num_rows: usize, op: CompareOp, value: &Scalar) -> bool {
+    // null never compares true
+    if stats.null_count() == num_rows {
+        return true;
+    }
+    let (Some(min), Some(max)) = (stats.min(), stats.max()) else {
+        return false;
+    };
+    // None means the types can't be compared, so nothing can be proven
+    let (Some(min_cmp), Some(max_cmp)) = (min.partial_cmp(value), max.partial_cmp(value)) else {
+        return false;
+    };
+    match op {
+        CompareOp::Eq => min_cmp == Ordering::Greater || max_cmp == Ordering::Less,
+        // values that are not comparable (NaN) are not equal to anything, so even a page where
+        // min == max == value may still match
+        CompareOp::NotEq => false,
+        CompareOp::Lt => min_cmp != Ordering::Less,
+        CompareOp::LtEq => min_cmp == Ordering::Greater,
+        CompareOp::Gt => max_cmp != Ordering::Greater,
+        CompareOp::GtEq => max_cmp == Ordering::Less,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Predicate;
+    use crate::bloom::BloomFilter;
+    use crate::stats::{ColumnStats, Scalar};
+
+    fn ages(min: i32, max: i32, null_count: usize) -> ColumnStats {
+        ColumnStats::new(
+            Some(Scalar::Int32(min)),
+            Some(Scalar::Int32(max)),
+            null_count,
+            0,
+            0,
+        )
+    }
+
+    fn skip(predicate: &Predicate, stats: &ColumnStats) -> bool {
+        predicate.can_skip(&|name| (name == "age").then_some(stats), 10)
+    }
+
+    #[test]
+    fn comparisons() {
+        let stats = ages(20, 60, 0);
+        assert!(skip(&Predicate::gt("age", Scalar::Int32(60)), &stats));
+        assert!(!skip(&Predicate::gt("age", Scalar::Int32(59)), &stats));
+        assert!(skip(&Predicate::lt("age", Scalar::Int8(20)), &stats));
+        assert!(!skip(&Predicate::lt("age", Scalar::Float64(20.5)), &stats));
+        assert!(skip(&Predicate::eq("age", Scalar::Int32(61)), &stats));
+        assert!(!skip(&Predicate::eq("age", Scalar::Int32(40)), &stats));
+        assert!(skip(
+            &Predicate::between("age", Scalar::Int32(70), Scalar::Int32(80)),
+            &stats
+        ));
+        assert!(!skip(
+            &Predicate::between("age", Scalar::Int32(50), Scalar::Int32(80)),
+            &stats
+        ));
+        // other types and unknown columns prove nothing
+        assert!(!skip(
+            &Predicate::eq("age", Scalar::Text("x".into())),
+            &stats
+        ));
+        assert!(!skip(&Predicate::gt("height", Scalar::Int32(1000)), &stats));
+    }
+
+    #[test]
+    fn nulls() {
+        assert!(skip(&Predicate::is_null("age"), &ages(1, 2, 0)));
+        assert!(!skip(&Predicate::is_null("age"), &ages(1, 2, 1)));
+        assert!(skip(&Predicate::is_not_null("age"), &ages(1, 2, 10)));
+
+        let all_null = ColumnStats::new(None, None, 10, 0, 0);
+        assert!(skip(&Predicate::gt("age", Scalar::Int32(0)), &all_null));
+    }
+
+    #[test]
+    fn and_or() {
+        let stats = ages(20, 60, 0);
+        let young = Predicate::lt("age", Scalar::Int32(10));
+        let old = Predicate::gt("age", Scalar::Int32(70));
+        let middle = Predicate::eq("age", Scalar::Int32(30));
+        assert!(skip(
+            &Predicate::Or(vec![young.clone(), old.clone()]),
+            &stats
+        ));
+        assert!(!skip(
+            &Predicate::Or(vec![young.clone(), middle.clone()]),
+            &stats
+        ));
+        assert!(skip(&Predicate::And(vec![middle, old]), &stats));
+    }
+
+    #[test]
+    fn in_list() {
+        let stats = ages(20, 60, 0);
+        let outside = vec![Scalar::Int32(1), Scalar::Int32(99)];
+        assert!(skip(&Predicate::is_in("age", outside.clone()), &stats));
+        let mut inside = outside;
+        inside.push(Scalar::Int32(30));
+        assert!(!skip(&Predicate::is_in("age", inside), &stats));
+        assert!(skip(&Predicate::is_in("age", vec![]), &stats));
+    }
+
+    #[test]
+    fn bloom() {
+        let mut filter = BloomFilter::with_capacity(10, 0.01);
+        filter.insert(&Scalar::Text("a81f".to_string()));
+        let lookup = |name: &str| (name == "id").then_some(&filter);
+
+        let hit = Predicate::eq("id", Scalar::Text("a81f".to_string()));
+        let miss = Predicate::eq("id", Scalar::Text("ffff".to_string()));
+        assert!(!hit.bloom_can_skip(&lookup));
+        assert!(miss.bloom_can_skip(&lookup));
+        assert!(!Predicate::eq("other", Scalar::Int32(1)).bloom_can_skip(&lookup));
+
+        let values = vec![Scalar::Text("0000".into()), Scalar::Text("ffff".into())];
+        assert!(Predicate::is_in("id", values.clone()).bloom_can_skip(&lookup));
+        let mut values = values;
+        values.push(Scalar::Text("a81f".into()));
+        assert!(!Predicate::is_in("id", values).bloom_can_skip(&lookup));
+        // ranges can't use a bloom filter
+        assert!(!Predicate::gt("id", Scalar::Text("zzzz".into())).bloom_can_skip(&lookup));
+    }
+}
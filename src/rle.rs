@@ -0,0 +1,416 @@
+// run length encoding. Sorted or slowly changing columns repeat the same value many times in a
+// row, e.g. a date column of an append only log. Instead of storing every copy, each run is stored
+// once with its length.
+//
+//   values  [7, 7, 7, null, null, 9]
+//   runs    (7, 3), (null, 2), (9, 1)
+//
+// Aggregates don't need the rows back, a sum is just value * length for every run.
+//
+// Validity bitmaps are usually all ones with a few zeros, so they get their own version that only
+// stores where the runs end.
+
+use crate::bitvec::BitVec;
+use crate::fixed::{Fixed, Native};
+
+/// A bitmap stored as runs of ones and zeros. Runs alternate, so only the first value and the
+/// length of every run are needed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RunLengthBitVec {
+    first: bool,
+    // end (exclusive) of every run, so lookups can binary search
+    ends: Vec<usize>,
+}
+
+impl RunLengthBitVec {
+    pub fn encode(bits: &BitVec) -> RunLengthBitVec {
+        let mut encoded = RunLengthBitVec {
+            first: bits.get(0).unwrap_or(false),
+            ends: vec![],
+        };
+        let mut previous = encoded.first;
+        for (index, bit) in bits.iter().enumerate() {
+            if bit != previous {
+                encoded.ends.push(index);
+                previous = bit;
+            }
+        }
+        if !bits.is_empty() {
+            encoded.ends.push(bits.len());
+        }
+        encoded
+    }
+
+    pub fn decode(&self) -> BitVec {
+        let mut bits = BitVec::with_capacity(self.len());
+        for bit in self.iter() {
+            bits.push(bit);
+        }
+        bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn num_runs(&self) -> usize {
+        self.ends.len()
+    }
+
+    fn run_value(&self, run: usize) -> bool {
+        self.first ^ (run % 2 == 1)
+    }
+
+    /// Value and length of every run.
+    pub fn runs(&self) -> impl Iterator<Item = (bool, usize)> + '_ {
+        let starts = std::iter::once(0).chain(self.ends.iter().copied());
+        self.ends
+            .iter()
+            .zip(starts)
+            .enumerate()
+            .map(|(run, (end, start))| (self.run_value(run), end - start))
+    }
+
+    /// Every bit, decoded on the fly.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        self.runs()
+            .flat_map(|(bit, length)| std::iter::repeat_n(bit, length))
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(self.run_value(self.ends.partition_point(|&end| end <= index)))
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.runs()
+            .filter(|(bit, _)| *bit)
+            .map(|(_, len)| len)
+            .sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len() - self.count_ones()
+    }
+
+    // first bit, then the length of every run as u32.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.ends.len() * 4);
+        bytes.push(self.first as u8);
+        for (_, length) in self.runs() {
+            bytes.extend_from_slice(&(length as u32).to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<RunLengthBitVec, &'static str> {
+        let Some((&first, lengths)) = bytes.split_first() else {
+            return Err("missing first bit of runs");
+        };
+        if first > 1 || !lengths.len().is_multiple_of(4) {
+            return Err("malformed runs");
+        }
+        let mut ends = Vec::with_capacity(lengths.len() / 4);
+        let mut end = 0usize;
+        for length in lengths.chunks_exact(4) {
+            let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+            if length == 0 {
+                return Err("empty run");
+            }
+            end += length;
+            ends.push(end);
+        }
+        Ok(RunLengthBitVec {
+            first: first == 1,
+            ends,
+        })
+    }
+}
+
+/// A fixed column stored as runs of equal values. Nulls form runs of their own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunLengthArray<T> {
+    values: Vec<T>,
+    // one bit per run
+    validity: BitVec,
+    ends: Vec<usize>,
+}
+
+impl<T: Native> RunLengthArray<T> {
+    // values are compared by their bits so -0.0 and NaN payloads survive the round trip.
+    pub fn encode(column: &Fixed<T>) -> RunLengthArray<T> {
+        let mut encoded: RunLengthArray<T> = RunLengthArray {
+            values: vec![],
+            validity: BitVec::new(),
+            ends: vec![],
+        };
+        for (index, value) in column.get_records().enumerate() {
+            let run = encoded.ends.len();
+            let same = run > 0
+                && match (value, encoded.validity[run - 1]) {
+                    (Some(value), true) => value.raw_bits() == encoded.values[run - 1].raw_bits(),
+                    (None, false) => true,
+                    _ => false,
+                };
+            if same {
+                encoded.ends[run - 1] = index + 1;
+            } else {
+                encoded.values.push(value.unwrap_or_default());
+                encoded.validity.push(value.is_some());
+                encoded.ends.push(index + 1);
+            }
+        }
+        encoded
+    }
+
+    /// Builds an array from the value, validity and length of every run.
+    pub fn from_runs(
+        values: Vec<T>,
+        validity: BitVec,
+        lengths: &[usize],
+    ) -> Result<RunLengthArray<T>, &'static str> {
+        if values.len() != validity.len() || values.len() != lengths.len() {
+            return Err("runs have different lengths");
+        }
+        let mut ends = Vec::with_capacity(lengths.len());
+        let mut end = 0;
+        for &length in lengths {
+            if length == 0 {
+                return Err("empty run");
+            }
+            end += length;
+            ends.push(end);
+        }
+        Ok(RunLengthArray {
+            values,
+            validity,
+            ends,
+        })
+    }
+
+    pub fn decode(&self) -> Fixed<T> {
+        let mut data = Vec::with_capacity(self.len());
+        let mut nulls = BitVec::with_capacity(self.len());
+        for (value, length) in self.runs() {
+            data.extend(std::iter::repeat_n(value.unwrap_or_default(), length));
+            for _ in 0..length {
+                nulls.push(value.is_some());
+            }
+        }
+        Fixed::from_parts(data, nulls)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn num_runs(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Value of every run, nulls hold the default value.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Validity of every run.
+    pub fn validity(&self) -> &BitVec {
+        &self.validity
+    }
+
+    /// Value and length of every run.
+    pub fn runs(&self) -> impl Iterator<Item = (Option<T>, usize)> + '_ {
+        let starts = std::iter::once(0).chain(self.ends.iter().copied());
+        self.ends
+            .iter()
+            .zip(starts)
+            .enumerate()
+            .map(|(run, (end, start))| {
+                let value = self.validity[run].then_some(self.values[run]);
+                (value, end - start)
+            })
+    }
+
+    /// Every row, decoded on the fly.
+    pub fn iter(&self) -> RunLengthIter<'_, T> {
+        RunLengthIter {
+            array: self,
+            run: 0,
+            index: 0,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<Option<T>> {
+        if index >= self.len() {
+            return None;
+        }
+        let run = self.ends.partition_point(|&end| end <= index);
+        Some(self.validity[run].then_some(self.values[run]))
+    }
+
+    /// Number of non null rows.
+    pub fn count(&self) -> usize {
+        self.runs()
+            .filter(|(value, _)| value.is_some())
+            .map(|(_, length)| length)
+            .sum()
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.len() - self.count()
+    }
+}
+
+impl<T: RunSum> RunLengthArray<T> {
+    /// Sum of the non null rows, one multiplication per run.
+    pub fn sum(&self) -> T::Sum {
+        self.runs()
+            .fold(T::Sum::default(), |sum, (value, length)| match value {
+                Some(value) => T::add_run(sum, value, length),
+                None => sum,
+            })
+    }
+}
+
+pub struct RunLengthIter<'a, T> {
+    array: &'a RunLengthArray<T>,
+    run: usize,
+    index: usize,
+}
+
+impl<'a, T: Native> Iterator for RunLengthIter<'a, T> {
+    type Item = Option<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = *self.array.ends.get(self.run)?;
+        let value = self.array.validity[self.run].then_some(self.array.values[self.run]);
+        self.index += 1;
+        if self.index == end {
+            self.run += 1;
+        }
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.array.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: Native> ExactSizeIterator for RunLengthIter<'a, T> {}
+
+/// Types that can be summed run by run. Integers sum into 64 bits, floats into f64.
+pub trait RunSum: Native {
+    type Sum: Default + Copy;
+
+    fn add_run(sum: Self::Sum, value: Self, length: usize) -> Self::Sum;
+}
+
+macro_rules! run_sum {
+    ($($type:ty => $sum:ty),*) => {
+        $(
+            impl RunSum for $type {
+                type Sum = $sum;
+
+                fn add_run(sum: $sum, value: $type, length: usize) -> $sum {
+                    sum + value as $sum * length as $sum
+                }
+            }
+        )*
+    };
+}
+
+run_sum!(
+    i8 => i64, i16 => i64, i32 => i64, i64 => i128,
+    u8 => u64, u16 => u64, u32 => u64, u64 => u128,
+    f32 => f64, f64 => f64
+);
+
+#[cfg(test)]
+mod test {
+    use super::{RunLengthArray, RunLengthBitVec};
+    use crate::bitvec::BitVec;
+    use crate::fixed::Fixed;
+
+    #[test]
+    fn bitvec_runs() {
+        let mut bits = BitVec::new();
+        for i in 0..1000 {
+            bits.push(i != 10 && i != 11 && i != 500);
+        }
+        let runs = RunLengthBitVec::encode(&bits);
+        assert_eq!(runs.num_runs(), 5);
+        assert_eq!(runs.len(), 1000);
+        assert_eq!(runs.count_zeros(), 3);
+        assert_eq!(runs.get(11), Some(false));
+        assert_eq!(runs.get(12), Some(true));
+        assert_eq!(runs.get(1000), None);
+        assert_eq!(runs.decode(), bits);
+
+        let read = RunLengthBitVec::from_bytes(&runs.to_bytes()).unwrap();
+        assert_eq!(read, runs);
+        assert_eq!(runs.to_bytes().len(), 21);
+        assert!(RunLengthBitVec::from_bytes(&[1, 0, 0, 0, 0]).is_err());
+
+        let empty = RunLengthBitVec::encode(&BitVec::new());
+        assert!(empty.is_empty());
+        assert_eq!(RunLengthBitVec::from_bytes(&empty.to_bytes()), Ok(empty));
+    }
+
+    #[test]
+    fn fixed_runs() {
+        let mut col = Fixed::new();
+        for value in [7, 7, 7] {
+            col.add_record(value);
+        }
+        col.add_null();
+        col.add_null();
+        col.add_record(9);
+        col.add_record(7);
+
+        let runs = RunLengthArray::encode(&col);
+        assert_eq!(runs.num_runs(), 4);
+        assert_eq!(
+            runs.runs().collect::<Vec<_>>(),
+            vec![(Some(7), 3), (None, 2), (Some(9), 1), (Some(7), 1)]
+        );
+        assert_eq!(
+            runs.iter().collect::<Vec<_>>(),
+            col.get_records().collect::<Vec<_>>()
+        );
+        assert_eq!(runs.get(4), Some(None));
+        assert_eq!(runs.get(5), Some(Some(9)));
+        assert_eq!(runs.count(), 5);
+        assert_eq!(runs.null_count(), 2);
+        assert_eq!(runs.sum(), 7 * 4 + 9i64);
+
+        let decoded = runs.decode();
+        assert_eq!(
+            decoded.get_records().collect::<Vec<_>>(),
+            col.get_records().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn float_runs_keep_bits() {
+        let mut col = Fixed::new();
+        for value in [0.0, -0.0, -0.0, f64::NAN, f64::NAN, 1.5] {
+            col.add_record(value);
+        }
+        let runs = RunLengthArray::encode(&col);
+        assert_eq!(runs.num_runs(), 4);
+        let decoded: Vec<u64> = runs.iter().map(|v| v.unwrap().to_bits()).collect();
+        let original: Vec<u64> = col.data().iter().map(|v| v.to_bits()).collect();
+        assert_eq!(decoded, original);
+    }
+}
@@ -0,0 +1,446 @@
+// statistics of a column chunk. Scanning a whole table to answer `age > 60` is wasteful when we
+// already know the smallest and largest value of each chunk; if the largest age in a chunk is 50,
+// the chunk can be skipped without touching its data (zone maps).
+//
+// Stats are kept up to date while records are added, so building them is free for the common
+// append only case. Columns built from existing buffers (a file, the output of a kernel) start
+// without stats and compute them the first time they are asked for. Removing a value (update or
+// delete) may loosen min/max and the distinct sketch can't forget values, so that drops them too.
+// Either way a column is scanned at most once until it changes again.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+use crate::util::mix64;
+
+/// An owned single value of any column type. Used for statistics and predicates.
+#[derive(Debug, Clone)]
+pub enum Scalar {
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    UInt8(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    Float32(f32),
+    Float64(f64),
+    Text(String),
+    Binary(Vec<u8>),
+    Boolean(bool),
+    /// Unscaled value and scale, `Decimal128(150, 2)` is 1.50.
+    Decimal128(i128, u8),
+}
+
+impl Scalar {
+    pub(crate) fn as_i128(&self) -> Option<i128> {
+        Some(match *self {
+            Scalar::Int8(v) => v as i128,
+            Scalar::Int16(v) => v as i128,
+            Scalar::Int32(v) => v as i128,
+            Scalar::Int64(v) => v as i128,
+            Scalar::UInt8(v) => v as i128,
+            Scalar::UInt16(v) => v as i128,
+            Scalar::UInt32(v) => v as i128,
+            Scalar::UInt64(v) => v as i128,
+            // only decimals without a fraction are integers
+            Scalar::Decimal128(v, scale) => {
+                let divisor = 10i128.checked_pow(scale as u32)?;
+                if v % divisor != 0 {
+                    return None;
+                }
+                v / divisor
+            }
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Scalar::Float32(v) => Some(v as f64),
+            Scalar::Float64(v) => Some(v),
+            Scalar::Decimal128(v, scale) => Some(v as f64 / 10f64.powi(scale as i32)),
+            _ => self.as_i128().map(|v| v as f64),
+        }
+    }
+}
+
+// numbers compare by value whatever their width is, so `age > 60` works on an int8 column. Values
+// of unrelated types (text and numbers) are not comparable. Pruning trusts every answer, so
+// integers and decimals compare exactly with each other and with floats, and when that can't be
+// done the answer is None rather than a rounded guess.
+impl PartialOrd for Scalar {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Scalar::Text(a), Scalar::Text(b)) => a.partial_cmp(b),
+            (Scalar::Binary(a), Scalar::Binary(b)) => a.partial_cmp(b),
+            (Scalar::Boolean(a), Scalar::Boolean(b)) => a.partial_cmp(b),
+            _ => match (self.as_decimal(), other.as_decimal()) {
+                (Some(a), Some(b)) => compare_decimals(a, b),
+                (Some(a), None) => compare_decimal_float(a, other.as_float()?),
+                (None, Some(b)) => Some(compare_decimal_float(b, self.as_float()?)?.reverse()),
+                (None, None) => self.as_float()?.partial_cmp(&other.as_float()?),
+            },
+        }
+    }
+}
+
+// equal when they compare equal, so Int8(1) == Int32(1) like the ordering says.
+impl PartialEq for Scalar {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl Scalar {
+    // integers and decimals as `(unscaled, scale)`.
+    fn as_decimal(&self) -> Option<(i128, u8)> {
+        match *self {
+            Scalar::Decimal128(v, scale) => Some((v, scale)),
+            _ => Some((self.as_i128()?, 0)),
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match *self {
+            Scalar::Float32(v) => Some(v as f64),
+            Scalar::Float64(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+// splits a decimal into its integer part and the fraction in units of 10^-scale, 0 <= fraction.
+fn split_decimal((v, scale): (i128, u8)) -> Option<(i128, i128)> {
+    let divisor = 10i128.checked_pow(scale as u32)?;
+    Some((v.div_euclid(divisor), v.rem_euclid(divisor)))
+}
+
+fn compare_decimals(a: (i128, u8), b: (i128, u8)) -> Option<Ordering> {
+    let ((int_a, frac_a), (int_b, frac_b)) = (split_decimal(a)?, split_decimal(b)?);
+    if int_a != int_b {
+        return Some(int_a.cmp(&int_b));
+    }
+    // both fractions are below 10^scale, so they fit once brought to the larger scale
+    let scale = a.1.max(b.1);
+    let frac_a = frac_a * 10i128.pow((scale - a.1) as u32);
+    let frac_b = frac_b * 10i128.pow((scale - b.1) as u32);
+    Some(frac_a.cmp(&frac_b))
+}
+
+fn compare_decimal_float(decimal: (i128, u8), float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        return None;
+    }
+    let (int, frac) = split_decimal(decimal)?;
+    // every i128 lies in [-2^127, 2^127), which also covers infinities
+    let floor = float.floor();
+    let bound = 2f64.powi(127);
+    if floor >= bound {
+        return Some(Ordering::Less);
+    }
+    if floor < -bound {
+        return Some(Ordering::Greater);
+    }
+    match int.cmp(&(floor as i128)) {
+        Ordering::Equal => {}
+        ordering => return Some(ordering),
+    }
+    // same integer part, compare frac / 10^scale with the float's fraction. Subtracting the floor
+    // is exact and the fraction is m / 2^k with an integer m.
+    let mut float_frac = float - floor;
+    let mut k = 0;
+    while float_frac.fract() != 0.0 {
+        float_frac *= 2.0;
+        k += 1;
+    }
+    let left = (frac as u128).checked_mul(1u128.checked_shl(k)?)?;
+    let right = (float_frac as u128).checked_mul(10u128.pow(decimal.1 as u32))?;
+    Some(left.cmp(&right))
+}
+
+pub trait ToScalar {
+    fn to_scalar(&self) -> Scalar;
+}
+
+impl ToScalar for str {
+    fn to_scalar(&self) -> Scalar {
+        Scalar::Text(self.to_string())
+    }
+}
+
+impl ToScalar for [u8] {
+    fn to_scalar(&self) -> Scalar {
+        Scalar::Binary(self.to_vec())
+    }
+}
+
+/// Statistics of a single column chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    min: Option<Scalar>,
+    max: Option<Scalar>,
+    null_count: usize,
+    distinct_count: u64,
+    byte_size: usize,
+}
+
+impl ColumnStats {
+    pub fn new(
+        min: Option<Scalar>,
+        max: Option<Scalar>,
+        null_count: usize,
+        distinct_count: u64,
+        byte_size: usize,
+    ) -> ColumnStats {
+        ColumnStats {
+            min,
+            max,
+            null_count,
+            distinct_count,
+            byte_size,
+        }
+    }
+
+    /// Smallest non null value, `None` when the chunk has no comparable values.
+    pub fn min(&self) -> Option<&Scalar> {
+        self.min.as_ref()
+    }
+
+    /// Largest non null value, `None` when the chunk has no comparable values.
+    pub fn max(&self) -> Option<&Scalar> {
+        self.max.as_ref()
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.null_count
+    }
+
+    /// Estimated number of distinct non null values.
+    pub fn distinct_count(&self) -> u64 {
+        self.distinct_count
+    }
+
+    /// Size of the chunk's buffers in bytes.
+    pub fn byte_size(&self) -> usize {
+        self.byte_size
+    }
+}
+
+// HyperLogLog with 2^10 registers, around 3% standard error for a KiB per chunk.
+const SKETCH_BITS: u32 = 10;
+const SKETCH_REGISTERS: usize = 1 << SKETCH_BITS;
+
+/// Cardinality estimator fed with 64-bit hashes.
+#[derive(Clone)]
+pub struct DistinctSketch {
+    registers: Vec<u8>,
+}
+
+impl Default for DistinctSketch {
+    fn default() -> Self {
+        DistinctSketch {
+            registers: vec![0; SKETCH_REGISTERS],
+        }
+    }
+}
+
+impl std::fmt::Debug for DistinctSketch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DistinctSketch(~{})", self.estimate())
+    }
+}
+
+impl DistinctSketch {
+    pub fn new() -> DistinctSketch {
+        DistinctSketch::default()
+    }
+
+    pub fn insert(&mut self, hash: u64) {
+        // mixing again is cheap and protects against weak hashes of small integers
+        let hash = mix64(hash);
+        let index = (hash >> (64 - SKETCH_BITS)) as usize;
+        let rest = hash << SKETCH_BITS;
+        let rank = (rest.leading_zeros() + 1).min(64 - SKETCH_BITS + 1) as u8;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    pub fn estimate(&self) -> u64 {
+        let m = SKETCH_REGISTERS as f64;
+        let mut sum = 0f64;
+        let mut zeros = 0;
+        for &r in &self.registers {
+            sum += 1.0 / (1u64 << r) as f64;
+            if r == 0 {
+                zeros += 1;
+            }
+        }
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let estimate = alpha * m * m / sum;
+        // small cardinalities are better served by linear counting
+        if estimate <= 2.5 * m && zeros > 0 {
+            return (m * (m / zeros as f64).ln()).round() as u64;
+        }
+        estimate.round() as u64
+    }
+}
+
+// incremental min/max/distinct tracking shared by fixed and variable columns. Values are observed
+// in their borrowed form (i32 or [u8]) so variable columns only allocate when a bound changes.
+#[derive(Debug, Clone)]
+pub(crate) struct StatsTracker<T> {
+    min: Option<T>,
+    max: Option<T>,
+    sketch: DistinctSketch,
+}
+
+impl<T> Default for StatsTracker<T> {
+    fn default() -> Self {
+        StatsTracker {
+            min: None,
+            max: None,
+            sketch: DistinctSketch::default(),
+        }
+    }
+}
+
+impl<T> StatsTracker<T> {
+    pub(crate) fn observe<Q>(&mut self, value: &Q, hash: u64)
+    where
+        Q: ?Sized + PartialOrd + ToOwned<Owned = T>,
+        T: Borrow<Q>,
+    {
+        self.sketch.insert(hash);
+        // values not comparable to themselves (NaN) can't bound anything
+        if value.partial_cmp(value).is_none() {
+            return;
+        }
+        if self.min.as_ref().is_none_or(|min| value < min.borrow()) {
+            self.min = Some(value.to_owned());
+        }
+        if self.max.as_ref().is_none_or(|max| value > max.borrow()) {
+            self.max = Some(value.to_owned());
+        }
+    }
+
+    pub(crate) fn to_stats(
+        &self,
+        to_scalar: impl Fn(&T) -> Scalar,
+        null_count: usize,
+        byte_size: usize,
+    ) -> ColumnStats {
+        ColumnStats {
+            min: self.min.as_ref().map(&to_scalar),
+            max: self.max.as_ref().map(&to_scalar),
+            null_count,
+            distinct_count: self.sketch.estimate(),
+            byte_size,
+        }
+    }
+}
+
+// the tracker of a column once it is known, see the top of the file.
+#[derive(Debug, Clone)]
+pub(crate) struct LazyStats<T>(OnceLock<StatsTracker<T>>);
+
+impl<T> Default for LazyStats<T> {
+    // an empty column has empty stats, appends keep them current from there
+    fn default() -> Self {
+        LazyStats(OnceLock::from(StatsTracker::default()))
+    }
+}
+
+impl<T> LazyStats<T> {
+    /// Nothing known yet, the first `get` computes them.
+    pub(crate) fn unknown() -> LazyStats<T> {
+        LazyStats(OnceLock::new())
+    }
+
+    pub(crate) fn observe<Q>(&mut self, value: &Q, hash: u64)
+    where
+        Q: ?Sized + PartialOrd + ToOwned<Owned = T>,
+        T: Borrow<Q>,
+    {
+        if let Some(stats) = self.0.get_mut() {
+            stats.observe(value, hash);
+        }
+    }
+
+    // a value left the chunk.
+    pub(crate) fn invalidate(&mut self) {
+        self.0.take();
+    }
+
+    pub(crate) fn get(&self, compute: impl FnOnce() -> StatsTracker<T>) -> &StatsTracker<T> {
+        self.0.get_or_init(compute)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DistinctSketch, Scalar};
+    use crate::util::hash_bytes;
+
+    #[test]
+    fn scalar_order() {
+        assert!(Scalar::Int8(-1) < Scalar::UInt32(0));
+        assert!(Scalar::UInt32(u32::MAX) > Scalar::Int32(i32::MAX));
+        assert!(Scalar::Float64(60.5) > Scalar::Int32(60));
+        assert!(Scalar::Text("b".to_string()) > Scalar::Text("a".to_string()));
+        assert_eq!(
+            Scalar::Text("1".to_string()).partial_cmp(&Scalar::Int32(1)),
+            None
+        );
+        assert_eq!(
+            Scalar::Float32(f32::NAN).partial_cmp(&Scalar::Int8(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn scalars_compare_exactly() {
+        use std::cmp::Ordering;
+
+        assert_eq!(Scalar::Int8(1), Scalar::Int32(1));
+        assert_eq!(Scalar::Float32(1.5), Scalar::Decimal128(150, 2));
+        assert_ne!(Scalar::Int8(1), Scalar::Text("1".to_string()));
+        // 2^53 + 1 has no f64, it must not round down to 2^53
+        let big = Scalar::Int64((1 << 53) + 1);
+        assert_eq!(
+            big.partial_cmp(&Scalar::Float64(2f64.powi(53))),
+            Some(Ordering::Greater)
+        );
+        assert!(Scalar::UInt64(u64::MAX) < Scalar::Float64(2f64.powi(64)));
+        assert!(Scalar::Int8(-1) > Scalar::Float64(f64::NEG_INFINITY));
+        // the f64 closest to 0.1 is a bit larger than 0.1
+        assert!(Scalar::Decimal128(1, 1) < Scalar::Float64(0.1));
+        assert!(Scalar::Decimal128(-25, 1) < Scalar::Float32(-2.0));
+        assert_eq!(Scalar::Decimal128(10, 2), Scalar::Decimal128(1, 1));
+        assert!(Scalar::Decimal128(1, 38) < Scalar::Decimal128(i128::MAX, 0));
+        assert_eq!(
+            Scalar::Decimal128(1, 1).partial_cmp(&Scalar::Float64(f64::NAN)),
+            None
+        );
+    }
+
+    #[test]
+    fn distinct_estimate() {
+        let mut sketch = DistinctSketch::new();
+        assert_eq!(sketch.estimate(), 0);
+        for i in 0..100u32 {
+            sketch.insert(hash_bytes(&i.to_le_bytes()));
+            sketch.insert(hash_bytes(&i.to_le_bytes()));
+        }
+        assert!((95..=105).contains(&sketch.estimate()));
+
+        for i in 0..50_000u32 {
+            sketch.insert(hash_bytes(&i.to_le_bytes()));
+        }
+        let estimate = sketch.estimate() as f64;
+        assert!((estimate - 50_000.0).abs() / 50_000.0 < 0.1, "{estimate}");
+    }
+}
@@ -0,0 +1,404 @@
+// text kernels, the cleanup steps of a pipeline: case, trimming, substrings, lengths, concat,
+// replace, split, prefix/suffix/contains masks and SQL LIKE. Every kernel reads a string column
+// and returns a new column, the input is left alone.
+//
+// Kernels that produce text write the bytes of every row straight into the new data buffer and
+// push its end offset as they go, so the output is built in one pass without a String per row.
+// Null rows stay null and take no bytes. Lengths and positions are in chars unless a name says
+// bytes, so "ü" is one char but two bytes.
+
+use crate::bitvec::BitVec;
+use crate::boolean::BooleanArray;
+use crate::fixed::Fixed;
+use crate::list::List;
+use crate::variable::{FromByteRef, Offset, Variable};
+
+impl<O: Offset> Variable<str, O> {
+    /// Lowercases every value, following Unicode.
+    pub fn lower(&self) -> Variable<str, O> {
+        self.map_text(|value, data| {
+            if value.is_ascii() {
+                let start = data.len();
+                data.extend_from_slice(value.as_bytes());
+                data[start..].make_ascii_lowercase();
+            } else {
+                data.extend_from_slice(value.to_lowercase().as_bytes());
+            }
+        })
+    }
+
+    /// Uppercases every value, following Unicode. A value can get longer, "ß" becomes "SS".
+    pub fn upper(&self) -> Variable<str, O> {
+        self.map_text(|value, data| {
+            if value.is_ascii() {
+                let start = data.len();
+                data.extend_from_slice(value.as_bytes());
+                data[start..].make_ascii_uppercase();
+            } else {
+                data.extend_from_slice(value.to_uppercase().as_bytes());
+            }
+        })
+    }
+
+    /// Removes whitespace from both ends of every value.
+    pub fn trim(&self) -> Variable<str, O> {
+        self.map_text(|value, data| data.extend_from_slice(value.trim().as_bytes()))
+    }
+
+    /// Removes whitespace from the start of every value.
+    pub fn trim_start(&self) -> Variable<str, O> {
+        self.map_text(|value, data| data.extend_from_slice(value.trim_start().as_bytes()))
+    }
+
+    /// Removes whitespace from the end of every value.
+    pub fn trim_end(&self) -> Variable<str, O> {
+        self.map_text(|value, data| data.extend_from_slice(value.trim_end().as_bytes()))
+    }
+
+    /// `length` chars of every value starting at char `start` (counted from 0), or everything
+    /// from `start` on for `None`. Values that are too short give what they have, maybe "".
+    pub fn substring(&self, start: usize, length: Option<usize>) -> Variable<str, O> {
+        self.map_text(|value, data| {
+            let substring = if value.is_ascii() {
+                let start = start.min(value.len());
+                let end = length.map_or(value.len(), |l| start.saturating_add(l).min(value.len()));
+                &value[start..end]
+            } else {
+                let from = byte_len(value, start);
+                let to = length.map_or(value.len(), |l| from + byte_len(&value[from..], l));
+                &value[from..to]
+            };
+            data.extend_from_slice(substring.as_bytes());
+        })
+    }
+
+    /// The length of every value in bytes, straight from the offsets.
+    pub fn byte_length(&self) -> Fixed<O> {
+        let lengths = self
+            .offsets()
+            .windows(2)
+            .map(|w| O::from_usize(w[1].to_usize() - w[0].to_usize()))
+            .collect();
+        Fixed::from_parts(lengths, self.validity().cloned())
+    }
+
+    /// The length of every value in chars.
+    pub fn char_length(&self) -> Fixed<O> {
+        let lengths = (0..self.len())
+            .map(|index| O::from_usize(self.text(index).chars().count()))
+            .collect();
+        Fixed::from_parts(lengths, self.validity().cloned())
+    }
+
+    /// Every value of this column followed by the one of `other` in the same row, null when
+    /// either is null.
+    pub fn concat(&self, other: &Variable<str, O>) -> Variable<str, O> {
+        assert_eq!(self.len(), other.len(), "arrays must have the same length");
+        let validity = match (self.validity(), other.validity()) {
+            (Some(a), Some(b)) => Some(a.and(b)),
+            (Some(v), None) | (None, Some(v)) => Some(v.clone()),
+            (None, None) => None,
+        };
+        let capacity = self.data().len() + other.data().len();
+        build_text(self.len(), validity, capacity, |index, data| {
+            data.extend_from_slice(self.text(index).as_bytes());
+            data.extend_from_slice(other.text(index).as_bytes());
+        })
+    }
+
+    /// Replaces every match of `from` with `to`, see `str::replace`.
+    pub fn replace(&self, from: &str, to: &str) -> Variable<str, O> {
+        self.map_text(|value, data| {
+            let mut last = 0;
+            for (start, part) in value.match_indices(from) {
+                data.extend_from_slice(&value.as_bytes()[last..start]);
+                data.extend_from_slice(to.as_bytes());
+                last = start + part.len();
+            }
+            data.extend_from_slice(&value.as_bytes()[last..]);
+        })
+    }
+
+    /// Splits every value at `separator` into a list, "" gives [""] and a null gives a null list.
+    /// Panics when the separator is empty.
+    pub fn split(&self, separator: &str) -> List<Variable<str, O>> {
+        assert!(!separator.is_empty(), "separator can't be empty");
+        let mut data = Vec::with_capacity(self.data().len());
+        let mut offsets = vec![O::default()];
+        let mut lists = Vec::with_capacity(self.len() + 1);
+        lists.push(0);
+        for index in 0..self.len() {
+            if self.is_valid(index) {
+                for part in self.text(index).split(separator) {
+                    data.extend_from_slice(part.as_bytes());
+                    offsets.push(end_offset(&data));
+                }
+            }
+            let count = i32::try_from(offsets.len() - 1).expect("too many values for a list");
+            lists.push(count);
+        }
+        let values = Variable::from_parts(data, offsets, None);
+        List::from_parts(lists, values, self.validity().cloned())
+    }
+
+    /// Whether every value starts with `prefix`.
+    pub fn starts_with(&self, prefix: &str) -> BooleanArray {
+        self.mask(|value| value.starts_with(prefix))
+    }
+
+    /// Whether every value ends with `suffix`.
+    pub fn ends_with(&self, suffix: &str) -> BooleanArray {
+        self.mask(|value| value.ends_with(suffix))
+    }
+
+    /// Whether every value contains `pattern`.
+    pub fn contains(&self, pattern: &str) -> BooleanArray {
+        self.mask(|value| value.contains(pattern))
+    }
+
+    /// Whether every value matches the SQL LIKE `pattern`: `%` is any number of chars, `_` is
+    /// exactly one and a backslash takes the char after it literally. Fails when the pattern ends
+    /// with a lone backslash.
+    pub fn like(&self, pattern: &str) -> Result<BooleanArray, &'static str> {
+        let tokens = parse_like(pattern)?;
+        let mut chars = Vec::new();
+        Ok(self.mask(|value| {
+            chars.clear();
+            chars.extend(value.chars());
+            like_matches(&tokens, &chars)
+        }))
+    }
+
+    // the value at `index`, "" for nulls since they take no bytes.
+    fn text(&self, index: usize) -> &str {
+        let offsets = self.offsets();
+        let (start, end) = (offsets[index].to_usize(), offsets[index + 1].to_usize());
+        str::from_bytes(&self.data()[start..end])
+    }
+
+    fn map_text(&self, mut f: impl FnMut(&str, &mut Vec<u8>)) -> Variable<str, O> {
+        let validity = self.validity().cloned();
+        build_text(self.len(), validity, self.data().len(), |index, data| {
+            f(self.text(index), data)
+        })
+    }
+
+    // nulls are false, and stay null.
+    fn mask(&self, mut f: impl FnMut(&str) -> bool) -> BooleanArray {
+        let mut values = BitVec::with_capacity(self.len());
+        for index in 0..self.len() {
+            values.push(self.is_valid(index) && f(self.text(index)));
+        }
+        BooleanArray::from_parts(values, self.validity().cloned())
+    }
+}
+
+// calls `f` for every valid row to write its bytes, then pushes where the row ends.
+fn build_text<O: Offset>(
+    len: usize,
+    validity: Option<BitVec>,
+    capacity: usize,
+    mut f: impl FnMut(usize, &mut Vec<u8>),
+) -> Variable<str, O> {
+    let mut data = Vec::with_capacity(capacity);
+    let mut offsets = Vec::with_capacity(len + 1);
+    offsets.push(O::default());
+    for index in 0..len {
+        if validity.as_ref().is_none_or(|v| v[index]) {
+            f(index, &mut data);
+        }
+        offsets.push(end_offset(&data));
+    }
+    Variable::from_parts(data, offsets, validity)
+}
+
+fn end_offset<O: Offset>(data: &[u8]) -> O {
+    assert!(
+        data.len() <= O::MAX,
+        "column data outgrew its offsets, use a large array"
+    );
+    O::from_usize(data.len())
+}
+
+// bytes taken by the first `chars` chars of `value`, all of it when it is shorter.
+fn byte_len(value: &str, chars: usize) -> usize {
+    value
+        .char_indices()
+        .nth(chars)
+        .map_or(value.len(), |(i, _)| i)
+}
+
+#[derive(Debug, PartialEq)]
+enum LikeToken {
+    // %
+    Any,
+    // _
+    One,
+    Char(char),
+}
+
+fn parse_like(pattern: &str) -> Result<Vec<LikeToken>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            // %% is the same as %
+            '%' if tokens.last() == Some(&LikeToken::Any) => continue,
+            '%' => LikeToken::Any,
+            '_' => LikeToken::One,
+            '\\' => LikeToken::Char(chars.next().ok_or("LIKE pattern ends with an escape")?),
+            c => LikeToken::Char(c),
+        });
+    }
+    Ok(tokens)
+}
+
+// walks the pattern and the text together. On a mismatch after a % the % takes one more char
+// and matching starts over behind it, only the last % needs to be retried.
+fn like_matches(tokens: &[LikeToken], text: &[char]) -> bool {
+    let (mut t, mut p) = (0, 0);
+    let mut retry = None;
+    while t < text.len() {
+        match tokens.get(p) {
+            Some(LikeToken::Any) => {
+                retry = Some((p, t));
+                p += 1;
+            }
+            Some(LikeToken::One) => (p, t) = (p + 1, t + 1),
+            Some(LikeToken::Char(c)) if *c == text[t] => (p, t) = (p + 1, t + 1),
+            _ => match retry {
+                Some((any, from)) => {
+                    retry = Some((any, from + 1));
+                    (p, t) = (any + 1, from + 1);
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[p..].iter().all(|token| *token == LikeToken::Any)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::variable::{LargeStringArray, StringArray};
+
+    fn column(values: &[Option<&str>]) -> StringArray {
+        values.iter().copied().collect()
+    }
+
+    fn read(column: &StringArray) -> Vec<Option<&str>> {
+        (0..column.len()).map(|i| column.get(i).unwrap()).collect()
+    }
+
+    #[test]
+    fn case_and_trim() {
+        let names = column(&[Some("  Ada "), None, Some("Straße"), Some("")]);
+        assert_eq!(
+            read(&names.lower()),
+            vec![Some("  ada "), None, Some("straße"), Some("")]
+        );
+        assert_eq!(
+            read(&names.upper()),
+            vec![Some("  ADA "), None, Some("STRASSE"), Some("")]
+        );
+        let trimmed = names.trim();
+        assert_eq!(
+            read(&trimmed),
+            vec![Some("Ada"), None, Some("Straße"), Some("")]
+        );
+        assert_eq!(trimmed.offsets(), &[0, 3, 3, 10, 10]);
+        assert_eq!(names.trim_start().get(0), Some(Some("Ada ")));
+        assert_eq!(names.trim_end().get(0), Some(Some("  Ada")));
+
+        let lengths: Vec<_> = names.byte_length().get_records().collect();
+        assert_eq!(lengths, vec![Some(6), None, Some(7), Some(0)]);
+        let lengths: Vec<_> = names.char_length().get_records().collect();
+        assert_eq!(lengths, vec![Some(6), None, Some(6), Some(0)]);
+    }
+
+    #[test]
+    fn substring_concat_replace() {
+        let words = column(&[Some("hello"), Some("grüße"), None]);
+        assert_eq!(
+            read(&words.substring(1, Some(3))),
+            vec![Some("ell"), Some("rüß"), None]
+        );
+        assert_eq!(
+            read(&words.substring(3, None)),
+            vec![Some("lo"), Some("ße"), None]
+        );
+        assert_eq!(
+            read(&words.substring(9, Some(2))),
+            vec![Some(""), Some(""), None]
+        );
+
+        let other = column(&[Some(" world"), None, Some("!")]);
+        assert_eq!(
+            read(&words.concat(&other)),
+            vec![Some("hello world"), None, None]
+        );
+        let replaced = words.replace("l", "LL");
+        assert_eq!(read(&replaced), vec![Some("heLLLLo"), Some("grüße"), None]);
+
+        let large = LargeStringArray::new().upper();
+        assert!(large.is_empty());
+    }
+
+    #[test]
+    fn split_into_lists() {
+        let tags = column(&[Some("a,b,,c"), None, Some("")]);
+        let lists = tags.split(",");
+        assert_eq!(lists.offsets(), &[0, 4, 4, 5]);
+        assert!(!lists.is_valid(1));
+        let values = read(lists.values());
+        assert_eq!(
+            values,
+            vec![Some("a"), Some("b"), Some(""), Some("c"), Some("")]
+        );
+    }
+
+    #[test]
+    fn masks_and_like() {
+        let files = column(&[
+            Some("report_2024.csv"),
+            Some("notes.txt"),
+            None,
+            Some("50%.csv"),
+        ]);
+        let bits = |mask: crate::boolean::BooleanArray| -> Vec<Option<bool>> {
+            mask.get_records().collect()
+        };
+        let csv = vec![Some(true), Some(false), None, Some(true)];
+        assert_eq!(bits(files.ends_with(".csv")), csv);
+        assert_eq!(
+            bits(files.starts_with("notes")),
+            vec![Some(false), Some(true), None, Some(false)]
+        );
+        assert_eq!(
+            bits(files.contains("_")),
+            vec![Some(true), Some(false), None, Some(false)]
+        );
+
+        assert_eq!(bits(files.like("%.csv").unwrap()), csv);
+        assert_eq!(
+            bits(files.like("report_____.%").unwrap()),
+            vec![Some(true), Some(false), None, Some(false)]
+        );
+        assert_eq!(
+            bits(files.like("%\\%%").unwrap()),
+            vec![Some(false), Some(false), None, Some(true)]
+        );
+        assert_eq!(
+            bits(files.like("%e%t%").unwrap()),
+            vec![Some(true), Some(true), None, Some(false)]
+        );
+        assert_eq!(
+            bits(files.like("notes").unwrap()),
+            vec![Some(false), Some(false), None, Some(false)]
+        );
+        assert_eq!(
+            files.like("50\\").unwrap_err(),
+            "LIKE pattern ends with an escape"
+        );
+    }
+}
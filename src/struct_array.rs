@@ -0,0 +1,269 @@
+// A struct column holds a nested record per row, e.g. an address with a street and a city. Every
+// field is a named child column and all of them have one value per row, so row i of the struct
+// is value i of every child. The struct has a validity of its own: a null address is different
+// from an address whose city is null.
+//
+// A null struct still takes a slot in every child, and that slot is null. So the child can be
+// read on its own, the column at the path "address.city" is just the city child, and it is null
+// wherever the address is.
+
+use crate::bitvec::BitVec;
+use crate::database::{Column, ColumnType, DataType, Field, FieldType};
+use crate::stats::ColumnStats;
+
+#[derive(Debug)]
+pub struct StructArray {
+    fields: Vec<Column>,
+    validity: Option<BitVec>,
+}
+
+impl StructArray {
+    /// Panics when `fields` is empty.
+    pub fn new(fields: &[Field]) -> StructArray {
+        StructArray::with_capacity(fields, 0)
+    }
+
+    pub fn with_capacity(fields: &[Field], capacity: usize) -> StructArray {
+        let fields = fields
+            .iter()
+            .map(|f| {
+                let column = ColumnType::empty(f.data_type(), capacity);
+                Column::new(f.name().to_string(), column)
+            })
+            .collect();
+        StructArray::from_parts(fields, None)
+    }
+
+    // wraps existing children of the same length. Children keep their own nulls, the bitmap
+    // here marks whole structs and is handled like in `Fixed::from_parts`.
+    pub fn from_parts(fields: Vec<Column>, validity: impl Into<Option<BitVec>>) -> StructArray {
+        assert!(!fields.is_empty(), "a struct needs at least one field");
+        let len = fields[0].inner().len();
+        assert!(
+            fields.iter().all(|f| f.inner().len() == len),
+            "fields have different lengths"
+        );
+        let validity = validity.into().filter(|validity| validity.null_count() > 0);
+        if let Some(validity) = &validity {
+            assert_eq!(len, validity.len(), "fields and validity length missmatch");
+        }
+        StructArray { fields, validity }
+    }
+
+    pub fn fields(&self) -> &[Column] {
+        &self.fields
+    }
+
+    /// The child column called `name`.
+    pub fn field(&self, name: &str) -> Option<&ColumnType> {
+        self.fields
+            .iter()
+            .find(|f| f.name() == name)
+            .map(Column::inner)
+    }
+
+    /// The column at a dotted path below this struct, e.g. "city" or "address.city".
+    pub fn field_by_path(&self, path: &str) -> Option<&ColumnType> {
+        let (name, rest) = match path.split_once('.') {
+            Some((name, rest)) => (name, Some(rest)),
+            None => (path, None),
+        };
+        match (self.field(name)?, rest) {
+            (column, None) => Some(column),
+            (ColumnType::Struct(c), Some(rest)) => c.field_by_path(rest),
+            _ => None,
+        }
+    }
+
+    pub fn data_type(&self) -> DataType {
+        DataType::Struct(
+            self.fields
+                .iter()
+                .map(|f| Field::new(f.name().to_string(), f.inner().data_type()))
+                .collect(),
+        )
+    }
+
+    /// The validity bitmap of the structs, `None` when every struct is valid.
+    pub fn validity(&self) -> Option<&BitVec> {
+        self.validity.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields[0].inner().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.validity
+            .as_ref()
+            .is_none_or(|validity| validity[index])
+    }
+
+    /// Number of null structs, nulls of a field are counted by the field.
+    pub fn null_count(&self) -> usize {
+        self.validity.as_ref().map_or(0, BitVec::null_count)
+    }
+
+    /// Adds a struct holding `values`, one per field in the order of the fields. When a value
+    /// doesn't fit its field nothing is added.
+    pub fn add_fields(&mut self, values: &[FieldType]) -> Result<(), &'static str> {
+        if values.len() != self.fields.len() {
+            return Err("wrong number of struct fields");
+        }
+        let mut row = Vec::with_capacity(values.len());
+        for (field, value) in self.fields.iter().zip(values) {
+            let mut column = ColumnType::empty(&field.inner().data_type(), 1);
+            column.add_field(value)?;
+            row.push(column);
+        }
+        for (field, column) in self.fields.iter_mut().zip(&row) {
+            field.inner_mut().append(column)?;
+        }
+        if let Some(validity) = &mut self.validity {
+            validity.push(true);
+        }
+        Ok(())
+    }
+
+    /// Adds a null struct, every field gets a null too.
+    pub fn add_null(&mut self) {
+        let len = self.len();
+        self.validity
+            .get_or_insert_with(|| BitVec::ones(len))
+            .push(false);
+        for field in &mut self.fields {
+            field.inner_mut().add_null();
+        }
+    }
+
+    // copies `length` structs starting at `offset` into a new column.
+    pub fn slice(&self, offset: usize, length: usize) -> StructArray {
+        let fields = self
+            .fields
+            .iter()
+            .map(|f| Column::new(f.name().to_string(), f.inner().slice(offset, length)))
+            .collect();
+        StructArray::from_parts(
+            fields,
+            self.validity
+                .as_ref()
+                .map(|validity| validity.slice(offset, length)),
+        )
+    }
+
+    // adds every struct of other to the end, the fields must have the same names and types.
+    pub fn append(&mut self, other: &StructArray) -> Result<(), &'static str> {
+        if self.data_type() != other.data_type() {
+            return Err("struct fields don't match");
+        }
+        if self.validity.is_some() || other.validity.is_some() {
+            let len = self.len();
+            let validity = self.validity.get_or_insert_with(|| BitVec::ones(len));
+            for index in 0..other.len() {
+                validity.push(other.is_valid(index));
+            }
+        }
+        for (field, other) in self.fields.iter_mut().zip(&other.fields) {
+            field.inner_mut().append(other.inner())?;
+        }
+        Ok(())
+    }
+
+    // a struct has no min or max, the fields have their own stats. Its size includes the fields.
+    pub fn stats(&self) -> ColumnStats {
+        let byte_size = self.validity.as_ref().map_or(0, |v| v.as_bytes().len())
+            + self
+                .fields
+                .iter()
+                .map(|f| f.stats().byte_size())
+                .sum::<usize>();
+        ColumnStats::new(None, None, self.null_count(), 0, byte_size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StructArray;
+    use crate::database::{ColumnType, DataType, Field, FieldType};
+
+    fn address_type() -> Vec<Field> {
+        vec![
+            Field::new("street".to_string(), DataType::Text),
+            Field::new("city".to_string(), DataType::Text),
+            Field::new(
+                "geo".to_string(),
+                DataType::Struct(vec![
+                    Field::new("lat".to_string(), DataType::Float64),
+                    Field::new("lon".to_string(), DataType::Float64),
+                ]),
+            ),
+        ]
+    }
+
+    fn addresses() -> StructArray {
+        let mut addresses = StructArray::new(&address_type());
+        let geo = FieldType::Struct(vec![FieldType::Float64(52.5), FieldType::Float64(13.4)]);
+        addresses
+            .add_fields(&[FieldType::Text("Main St"), FieldType::Text("Berlin"), geo])
+            .unwrap();
+        addresses.add_null();
+        addresses
+            .add_fields(&[FieldType::Text("Elm St"), FieldType::Null, FieldType::Null])
+            .unwrap();
+        addresses
+    }
+
+    #[test]
+    fn fields_and_nulls() {
+        let addresses = addresses();
+        assert_eq!(addresses.len(), 3);
+        assert_eq!(addresses.null_count(), 1);
+        assert!(!addresses.is_valid(1));
+
+        let Some(ColumnType::Text(cities)) = addresses.field_by_path("city") else {
+            panic!("expected a text column");
+        };
+        assert_eq!(cities.get(0), Some(Some("Berlin")));
+        // null because the address is
+        assert_eq!(cities.get(1), Some(None));
+        // null because the city is
+        assert_eq!(cities.get(2), Some(None));
+
+        let Some(ColumnType::Float64(lat)) = addresses.field_by_path("geo.lat") else {
+            panic!("expected a float64 column");
+        };
+        let lat: Vec<_> = lat.get_records().collect();
+        assert_eq!(lat, vec![Some(52.5), None, None]);
+        assert!(addresses.field_by_path("geo.alt").is_none());
+        assert!(addresses.field_by_path("city.name").is_none());
+        assert_eq!(addresses.data_type(), DataType::Struct(address_type()));
+    }
+
+    #[test]
+    fn bad_rows_add_nothing() {
+        let mut addresses = addresses();
+        let row = [FieldType::Text("x"), FieldType::Int8(1), FieldType::Null];
+        assert!(addresses.add_fields(&row).is_err());
+        assert!(addresses.add_fields(&[FieldType::Null]).is_err());
+        assert_eq!(addresses.len(), 3);
+        assert!(addresses.fields().iter().all(|f| f.inner().len() == 3));
+    }
+
+    #[test]
+    fn slice_and_append() {
+        let addresses = addresses();
+        let mut appended = addresses.slice(0, 1);
+        assert_eq!(appended.validity(), None);
+        appended.append(&addresses.slice(1, 2)).unwrap();
+        assert_eq!(appended.len(), 3);
+        assert_eq!(appended.validity(), addresses.validity());
+
+        let other = StructArray::new(&address_type()[..2]);
+        assert!(appended.append(&other).is_err());
+        assert_eq!(appended.stats().null_count(), 1);
+    }
+}
@@ -0,0 +1,930 @@
+// dates and times are integers with a meaning attached: a date is the number of days since
+// 1970-01-01, a timestamp the number of units (seconds down to nanoseconds) since
+// 1970-01-01T00:00:00 UTC, a time the units since midnight and a duration just a number of units.
+// So a temporal column is a Fixed<i32> or Fixed<i64> plus its data type. Storage, encodings and
+// stats are the ones of the integers, only parsing, formatting and the calendar kernels know about
+// the meaning.
+//
+// Timestamps store UTC instants. The optional timezone only changes how they are shown and which
+// local day or hour the kernels see. Named zones need a zone database, so only "UTC" and fixed
+// offsets like "+05:30" are supported.
+//
+// The calendar math is Howard Hinnant's days_from_civil / civil_from_days, valid for the
+// proleptic gregorian calendar in both directions of the epoch.
+
+use crate::database::DataType;
+use crate::fixed::{Fixed, FixedViewer, Native};
+use crate::stats::ColumnStats;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Resolution of times, timestamps and durations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+impl TimeUnit {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeUnit::Second => "s",
+            TimeUnit::Millisecond => "ms",
+            TimeUnit::Microsecond => "us",
+            TimeUnit::Nanosecond => "ns",
+        }
+    }
+
+    pub fn per_second(&self) -> i64 {
+        10i64.pow(self.digits())
+    }
+
+    // digits of the fraction of a second
+    fn digits(&self) -> u32 {
+        match self {
+            TimeUnit::Second => 0,
+            TimeUnit::Millisecond => 3,
+            TimeUnit::Microsecond => 6,
+            TimeUnit::Nanosecond => 9,
+        }
+    }
+}
+
+/// A calendar or clock field, used by [`TemporalArray::extract`] and [`TemporalArray::truncate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatePart {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl DatePart {
+    // length in seconds of the parts that have a fixed one
+    fn seconds(&self) -> Option<i64> {
+        match self {
+            DatePart::Year | DatePart::Month => None,
+            DatePart::Day => Some(SECONDS_PER_DAY),
+            DatePart::Hour => Some(60 * 60),
+            DatePart::Minute => Some(60),
+            DatePart::Second => Some(1),
+        }
+    }
+}
+
+/// Integers a temporal column can be stored as.
+pub trait TemporalInt: Native {
+    fn to_i64(self) -> i64;
+
+    fn from_i64(value: i64) -> Option<Self>;
+}
+
+impl TemporalInt for i32 {
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+
+    fn from_i64(value: i64) -> Option<i32> {
+        i32::try_from(value).ok()
+    }
+}
+
+impl TemporalInt for i64 {
+    fn to_i64(self) -> i64 {
+        self
+    }
+
+    fn from_i64(value: i64) -> Option<i64> {
+        Some(value)
+    }
+}
+
+/// Days since the epoch.
+pub type Date32Array = TemporalArray<i32>;
+/// Units since midnight.
+pub type Time64Array = TemporalArray<i64>;
+/// Units since the epoch in UTC.
+pub type TimestampArray = TemporalArray<i64>;
+/// A number of units.
+pub type DurationArray = TemporalArray<i64>;
+
+#[derive(Debug)]
+pub struct TemporalArray<T> {
+    // always Date32, Time64, Timestamp or Duration
+    data_type: DataType,
+    values: Fixed<T>,
+}
+
+impl<T: TemporalInt> TemporalArray<T> {
+    /// Panics when `data_type` isn't temporal, is stored with another width than `T` or has a
+    /// timezone that isn't UTC or a fixed offset.
+    pub fn new(data_type: DataType) -> TemporalArray<T> {
+        TemporalArray::from_parts(data_type, Fixed::new())
+    }
+
+    pub fn with_capacity(data_type: DataType, capacity: usize) -> TemporalArray<T> {
+        TemporalArray::from_parts(data_type, Fixed::with_capacity(capacity))
+    }
+
+    // wraps existing integers, e.g. when reading a file.
+    pub fn from_parts(data_type: DataType, values: Fixed<T>) -> TemporalArray<T> {
+        let width = match &data_type {
+            DataType::Date32 => 4,
+            DataType::Time64(_) | DataType::Duration(_) => 8,
+            DataType::Timestamp(_, timezone) => {
+                if let Some(timezone) = timezone {
+                    assert!(
+                        parse_offset(timezone).is_some(),
+                        "unsupported timezone {timezone}"
+                    );
+                }
+                8
+            }
+            _ => panic!("{} is not a temporal type", data_type.name()),
+        };
+        assert_eq!(
+            width,
+            T::WIDTH,
+            "wrong storage width for {}",
+            data_type.name()
+        );
+        TemporalArray { data_type, values }
+    }
+
+    pub fn data_type(&self) -> &DataType {
+        &self.data_type
+    }
+
+    pub fn unit(&self) -> Option<TimeUnit> {
+        match self.data_type {
+            DataType::Time64(unit) | DataType::Timestamp(unit, _) | DataType::Duration(unit) => {
+                Some(unit)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn timezone(&self) -> Option<&str> {
+        match &self.data_type {
+            DataType::Timestamp(_, timezone) => timezone.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The raw integers.
+    pub fn values(&self) -> &Fixed<T> {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.values.null_count()
+    }
+
+    pub fn add_record(&mut self, record: T) {
+        self.values.add_record(record);
+    }
+
+    pub fn add_null(&mut self) {
+        self.values.add_null();
+    }
+
+    /// Parses ISO-8601 text and adds it.
+    pub fn add_text(&mut self, text: &str) -> Result<(), &'static str> {
+        let value = self.parse(text)?;
+        self.values.add_record(value);
+        Ok(())
+    }
+
+    pub fn get(&self, index: usize) -> Option<Option<T>> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(
+            self.values
+                .is_valid(index)
+                .then(|| self.values.data()[index]),
+        )
+    }
+
+    pub fn get_records(&self) -> FixedViewer<'_, T> {
+        self.values.get_records()
+    }
+
+    pub fn slice(&self, offset: usize, length: usize) -> TemporalArray<T> {
+        TemporalArray {
+            data_type: self.data_type.clone(),
+            values: self.values.slice(offset, length),
+        }
+    }
+
+    pub fn append(&mut self, other: &TemporalArray<T>) {
+        self.values.append(&other.values);
+    }
+
+    // min and max are the raw integers, which order the same way as the values they stand for.
+    pub fn stats(&self) -> ColumnStats {
+        self.values.stats()
+    }
+
+    /// The value at `index` as ISO-8601 text, `Some(None)` for nulls.
+    pub fn format(&self, index: usize) -> Option<Option<String>> {
+        Some(self.get(index)?.map(|value| self.format_value(value)))
+    }
+
+    /// Formats a single value of this column's type as ISO-8601 text.
+    pub fn format_value(&self, value: T) -> String {
+        let value = value.to_i64();
+        match &self.data_type {
+            DataType::Date32 => format_date(value),
+            DataType::Time64(unit) => {
+                let (seconds, fraction) = split_seconds(value, *unit);
+                format_time(seconds.rem_euclid(SECONDS_PER_DAY), fraction, *unit)
+            }
+            DataType::Timestamp(unit, timezone) => {
+                let offset = timezone.as_deref().and_then(parse_offset).unwrap_or(0);
+                let (seconds, fraction) = split_seconds(value, *unit);
+                let (days, seconds) = local_day(seconds, offset);
+                let mut text = format_date(days);
+                text.push('T');
+                text += &format_time(seconds, fraction, *unit);
+                if timezone.is_some() {
+                    text += &format_offset(offset);
+                }
+                text
+            }
+            DataType::Duration(unit) => format_duration(value, *unit),
+            _ => unreachable!("checked in from_parts"),
+        }
+    }
+
+    /// Parses ISO-8601 text into this column's integer. Timestamps without an offset are taken to
+    /// be in the column's timezone. Digits finer than the unit are dropped.
+    pub fn parse(&self, text: &str) -> Result<T, &'static str> {
+        let value = match &self.data_type {
+            DataType::Date32 => parse_date(text)?,
+            DataType::Time64(unit) => {
+                let (seconds, fraction) = parse_time(text, *unit)?;
+                seconds * unit.per_second() + fraction
+            }
+            DataType::Timestamp(unit, timezone) => {
+                let offset = timezone.as_deref().and_then(parse_offset).unwrap_or(0);
+                parse_timestamp(text, *unit, offset)?
+            }
+            DataType::Duration(unit) => parse_duration(text, *unit)?,
+            _ => unreachable!("checked in from_parts"),
+        };
+        T::from_i64(value).ok_or("value out of range")
+    }
+
+    /// Pulls one field out of every value, e.g. the year of a date. Dates have no clock fields
+    /// (they are 0), times have no calendar fields and durations have neither.
+    pub fn extract(&self, part: DatePart) -> Result<Fixed<i32>, &'static str> {
+        let calendar = matches!(part, DatePart::Year | DatePart::Month | DatePart::Day);
+        match self.data_type {
+            DataType::Time64(_) if calendar => return Err("a time has no date"),
+            DataType::Duration(_) => return Err("a duration has no calendar or clock fields"),
+            _ => {}
+        }
+        let mut fields = Fixed::with_capacity(self.len());
+        for value in self.values.get_records() {
+            match value {
+                Some(value) => {
+                    let (days, seconds) = self.local_day_and_seconds(value.to_i64());
+                    let (year, month, day) = civil_from_days(days);
+                    fields.add_record(match part {
+                        // second timestamps reach years far beyond an i32
+                        DatePart::Year => i32::try_from(year).map_err(|_| "value out of range")?,
+                        DatePart::Month => month as i32,
+                        DatePart::Day => day as i32,
+                        DatePart::Hour => (seconds / 3600) as i32,
+                        DatePart::Minute => (seconds / 60 % 60) as i32,
+                        DatePart::Second => (seconds % 60) as i32,
+                    })
+                }
+                None => fields.add_null(),
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Rounds every value down to the start of its `part`, e.g. the first day of the month.
+    /// Timestamps are truncated in their timezone, durations only to fixed length parts.
+    pub fn truncate(&self, part: DatePart) -> Result<TemporalArray<T>, &'static str> {
+        match self.data_type {
+            DataType::Time64(_) if part.seconds().is_none() => return Err("a time has no date"),
+            DataType::Duration(_) if part.seconds().is_none() => {
+                return Err("years and months have no fixed length");
+            }
+            _ => {}
+        }
+        let mut values = Fixed::with_capacity(self.len());
+        for value in self.values.get_records() {
+            match value {
+                Some(value) => {
+                    let truncated = self.truncate_value(value.to_i64(), part)?;
+                    values.add_record(T::from_i64(truncated).ok_or("value out of range")?);
+                }
+                None => values.add_null(),
+            }
+        }
+        Ok(TemporalArray {
+            data_type: self.data_type.clone(),
+            values,
+        })
+    }
+
+    // the local day since the epoch and the second within that day.
+    fn local_day_and_seconds(&self, value: i64) -> (i64, i64) {
+        match &self.data_type {
+            DataType::Date32 => (value, 0),
+            DataType::Time64(unit) => local_day(split_seconds(value, *unit).0, 0),
+            DataType::Timestamp(unit, timezone) => {
+                let offset = timezone.as_deref().and_then(parse_offset).unwrap_or(0);
+                local_day(split_seconds(value, *unit).0, offset)
+            }
+            _ => unreachable!("durations are rejected by the callers"),
+        }
+    }
+
+    // the start may lie before the smallest value of the unit, so the math is done in i128 and
+    // only the result has to fit.
+    fn truncate_value(&self, value: i64, part: DatePart) -> Result<i64, &'static str> {
+        if self.data_type == DataType::Date32 {
+            let (year, month, day) = civil_from_days(value);
+            return match part {
+                DatePart::Year => days_from_civil(year, 1, 1),
+                DatePart::Month => days_from_civil(year, month, 1),
+                _ => days_from_civil(year, month, day),
+            };
+        }
+        let (unit, offset) = match &self.data_type {
+            DataType::Timestamp(unit, timezone) => (
+                *unit,
+                timezone.as_deref().and_then(parse_offset).unwrap_or(0),
+            ),
+            DataType::Time64(unit) | DataType::Duration(unit) => (*unit, 0),
+            _ => unreachable!("checked in from_parts"),
+        };
+        let per_second = unit.per_second() as i128;
+        let offset = offset as i128 * per_second;
+        let local = value as i128 + offset;
+        let start = match part.seconds() {
+            Some(seconds) => {
+                let length = seconds as i128 * per_second;
+                local.div_euclid(length) * length
+            }
+            None => {
+                let per_day = SECONDS_PER_DAY as i128 * per_second;
+                let (year, month, _) = civil_from_days(local.div_euclid(per_day) as i64);
+                let month = if part == DatePart::Year { 1 } else { month };
+                days_from_civil(year, month, 1)? as i128 * per_day
+            }
+        };
+        i64::try_from(start - offset).map_err(|_| "value out of range")
+    }
+}
+
+// calendar
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> Result<i64, &'static str> {
+    let year = if month <= 2 {
+        year.checked_sub(1)
+    } else {
+        Some(year)
+    };
+    let year = year.ok_or("date out of range")?;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era.checked_mul(146097)
+        .and_then(|days| days.checked_add(day_of_era - 719468))
+        .ok_or("date out of range")
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// the day and the second within it of `seconds` shifted by `offset`. The offset is added to the
+// second of the day only, so values near the ends of i64 don't overflow.
+fn local_day(seconds: i64, offset: i64) -> (i64, i64) {
+    let local = seconds.rem_euclid(SECONDS_PER_DAY) + offset;
+    (
+        seconds.div_euclid(SECONDS_PER_DAY) + local.div_euclid(SECONDS_PER_DAY),
+        local.rem_euclid(SECONDS_PER_DAY),
+    )
+}
+
+// whole seconds (rounded down) and the rest in `unit`.
+fn split_seconds(value: i64, unit: TimeUnit) -> (i64, i64) {
+    (
+        value.div_euclid(unit.per_second()),
+        value.rem_euclid(unit.per_second()),
+    )
+}
+
+/// Offset from UTC in seconds of "UTC", "Z" or a fixed offset like "+05:30", "-0800" or "+01".
+pub fn parse_offset(text: &str) -> Option<i64> {
+    if text == "UTC" || text == "Z" {
+        return Some(0);
+    }
+    let (sign, rest) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.len() {
+        2 => (rest, "00"),
+        4 => rest.split_at(2),
+        5 if rest.as_bytes()[2] == b':' => (&rest[..2], &rest[3..]),
+        _ => return None,
+    };
+    let hours = number(hours).ok().filter(|h| *h < 24)?;
+    let minutes = number(minutes).ok().filter(|m| *m < 60)?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+// parsing
+
+// an unsigned decimal made of ascii digits only, "+1" and "" are rejected.
+fn number(text: &str) -> Result<i64, &'static str> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err("expected digits");
+    }
+    text.parse().map_err(|_| "number out of range")
+}
+
+// the digits after the decimal point in `unit`, finer digits are dropped.
+fn fraction(digits: &str, unit: TimeUnit) -> Result<i64, &'static str> {
+    number(digits)?;
+    let kept = &digits[..digits.len().min(unit.digits() as usize)];
+    let padding = 10i64.pow(unit.digits() - kept.len() as u32);
+    Ok(if kept.is_empty() {
+        0
+    } else {
+        number(kept)? * padding
+    })
+}
+
+// YYYY-MM-DD, the year may have a sign and more than 4 digits. 12 digits cover every timestamp
+// in seconds, longer years can't be stored in any unit.
+fn parse_date(text: &str) -> Result<i64, &'static str> {
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let mut parts = rest.splitn(3, '-');
+    let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err("expected a YYYY-MM-DD date");
+    };
+    if year.len() < 4 || month.len() != 2 || day.len() != 2 {
+        return Err("expected a YYYY-MM-DD date");
+    }
+    if year.len() > 12 {
+        return Err("date out of range");
+    }
+    let year = sign * number(year)?;
+    let month = number(month)? as u32;
+    let day = number(day)? as u32;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err("date out of range");
+    }
+    days_from_civil(year, month, day)
+}
+
+// HH:MM[:SS[.fraction]] as seconds since midnight and the fraction in `unit`.
+fn parse_time(text: &str, unit: TimeUnit) -> Result<(i64, i64), &'static str> {
+    let (clock, digits) = match text.split_once('.') {
+        Some((clock, digits)) => (clock, Some(digits)),
+        None => (text, None),
+    };
+    let mut parts = clock.split(':');
+    let (Some(hours), Some(minutes)) = (parts.next(), parts.next()) else {
+        return Err("expected a HH:MM:SS time");
+    };
+    let seconds = parts.next();
+    if parts.next().is_some()
+        || hours.len() != 2
+        || minutes.len() != 2
+        || seconds.is_some_and(|s| s.len() != 2)
+        || (seconds.is_none() && digits.is_some())
+    {
+        return Err("expected a HH:MM:SS time");
+    }
+    let (hours, minutes) = (number(hours)?, number(minutes)?);
+    let seconds = seconds.map_or(Ok(0), number)?;
+    if hours > 23 || minutes > 59 || seconds > 59 {
+        return Err("time out of range");
+    }
+    let fraction = digits.map_or(Ok(0), |digits| fraction(digits, unit))?;
+    Ok((hours * 3600 + minutes * 60 + seconds, fraction))
+}
+
+// a date, optionally followed by 'T' or a space, a time and an offset. Without an offset the
+// value is local to `default_offset`.
+fn parse_timestamp(text: &str, unit: TimeUnit, default_offset: i64) -> Result<i64, &'static str> {
+    // the date's own dashes come before the separator
+    let (date, rest) = match text.find(['T', ' ']) {
+        Some(index) => (&text[..index], Some(&text[index + 1..])),
+        None => (text, None),
+    };
+    let days = parse_date(date)?;
+    let (seconds, fraction, offset) = match rest {
+        None => (0, 0, default_offset),
+        Some(rest) => {
+            let (time, offset) = match rest.find(['Z', '+', '-']) {
+                Some(index) => {
+                    let offset = parse_offset(&rest[index..]).ok_or("bad utc offset")?;
+                    (&rest[..index], offset)
+                }
+                None => (rest, default_offset),
+            };
+            let (seconds, fraction) = parse_time(time, unit)?;
+            (seconds, fraction, offset)
+        }
+    };
+    days.checked_mul(SECONDS_PER_DAY)
+        .and_then(|s| s.checked_add(seconds - offset))
+        .and_then(|s| s.checked_mul(unit.per_second()))
+        .and_then(|v| v.checked_add(fraction))
+        .ok_or("timestamp out of range")
+}
+
+// [-]P[nW][nD][T[nH][nM][n[.fraction]S]], years and months are rejected since their length
+// depends on where they start.
+fn parse_duration(text: &str, unit: TimeUnit) -> Result<i64, &'static str> {
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text),
+    };
+    let rest = rest
+        .strip_prefix('P')
+        .ok_or("expected an ISO-8601 duration")?;
+    let (date, time) = match rest.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, time),
+        Some(_) => return Err("expected an ISO-8601 duration"),
+        None => (rest, ""),
+    };
+    if date.is_empty() && time.is_empty() {
+        return Err("expected an ISO-8601 duration");
+    }
+    let mut total: i64 = 0;
+    // each field is a number followed by its designator, in this order
+    for (in_date, part) in [(true, date), (false, time)] {
+        let mut allowed = if in_date {
+            &['W', 'D'][..]
+        } else {
+            &['H', 'M', 'S'][..]
+        };
+        let mut rest = part;
+        while !rest.is_empty() {
+            let index = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .ok_or("duration field without designator")?;
+            let (amount, designator) = (&rest[..index], rest[index..].chars().next().unwrap());
+            rest = &rest[index + designator.len_utf8()..];
+            if in_date && matches!(designator, 'Y' | 'M') {
+                return Err("years and months have no fixed length");
+            }
+            let position = allowed
+                .iter()
+                .position(|d| *d == designator)
+                .ok_or("unexpected duration designator")?;
+            allowed = &allowed[position + 1..];
+            let seconds = match designator {
+                'W' => 7 * SECONDS_PER_DAY,
+                'D' => SECONDS_PER_DAY,
+                'H' => 3600,
+                'M' => 60,
+                _ => 1,
+            };
+            let value = match amount.split_once('.') {
+                Some((whole, digits)) if designator == 'S' => number(whole)?
+                    .checked_mul(unit.per_second())
+                    .and_then(|v| v.checked_add(fraction(digits, unit).ok()?)),
+                Some(_) => return Err("only seconds can have a fraction"),
+                None => number(amount)?.checked_mul(seconds * unit.per_second()),
+            };
+            total = value
+                .and_then(|v| v.checked_add(total))
+                .ok_or("duration out of range")?;
+        }
+    }
+    Ok(sign * total)
+}
+
+// formatting
+
+fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    if (0..=9999).contains(&year) {
+        format!("{year:04}-{month:02}-{day:02}")
+    } else {
+        // ISO-8601 expanded years always carry a sign
+        format!("{year:+05}-{month:02}-{day:02}")
+    }
+}
+
+fn format_time(seconds: i64, fraction: i64, unit: TimeUnit) -> String {
+    let clock = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    match unit.digits() {
+        0 => clock,
+        digits => format!("{clock}.{fraction:0width$}", width = digits as usize),
+    }
+}
+
+fn format_offset(offset: i64) -> String {
+    if offset == 0 {
+        return "Z".to_string();
+    }
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    format!("{sign}{:02}:{:02}", offset / 3600, offset / 60 % 60)
+}
+
+fn format_duration(value: i64, unit: TimeUnit) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let value = value.unsigned_abs();
+    let per_second = unit.per_second() as u64;
+    let (seconds, fraction) = (value / per_second, value % per_second);
+    let days = seconds / SECONDS_PER_DAY as u64;
+    let (hours, minutes, seconds) = (seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+
+    let mut text = format!("{sign}P");
+    if days > 0 {
+        text += &format!("{days}D");
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || fraction > 0 || days == 0 {
+        text.push('T');
+        if hours > 0 {
+            text += &format!("{hours}H");
+        }
+        if minutes > 0 {
+            text += &format!("{minutes}M");
+        }
+        if fraction > 0 {
+            let digits = format!("{fraction:0width$}", width = unit.digits() as usize);
+            text += &format!("{seconds}.{}S", digits.trim_end_matches('0'));
+        } else if seconds > 0 || (hours == 0 && minutes == 0) {
+            text += &format!("{seconds}S");
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Date32Array, DatePart, DurationArray, Time64Array, TimeUnit, TimestampArray,
+        civil_from_days, days_from_civil,
+    };
+    use crate::database::DataType;
+
+    #[test]
+    fn calendar() {
+        assert_eq!(days_from_civil(1970, 1, 1), Ok(0));
+        assert_eq!(days_from_civil(2000, 3, 1), Ok(11017));
+        assert_eq!(days_from_civil(1969, 12, 31), Ok(-1));
+        for days in [-800_000, -1, 0, 59, 10_957, 19_782, 2_932_896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), Ok(days));
+        }
+        assert!(days_from_civil(i64::MAX, 6, 1).is_err());
+        assert!(days_from_civil(i64::MIN, 1, 1).is_err());
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn dates() {
+        let mut col = Date32Array::new(DataType::Date32);
+        col.add_text("2024-02-29").unwrap();
+        col.add_null();
+        col.add_text("1969-07-20").unwrap();
+        col.add_record(0);
+        assert_eq!(col.get(0), Some(Some(19782)));
+        assert_eq!(col.format(1), Some(None));
+        assert_eq!(col.format(2), Some(Some("1969-07-20".to_string())));
+        assert_eq!(col.format(3), Some(Some("1970-01-01".to_string())));
+        assert_eq!(col.format(4), None);
+
+        assert!(col.add_text("2023-02-29").is_err());
+        assert!(col.add_text("2023-13-01").is_err());
+        assert!(col.add_text("20230101").is_err());
+        assert_eq!(col.len(), 4);
+
+        let years = col.extract(DatePart::Year).unwrap();
+        assert_eq!(
+            years.get_records().collect::<Vec<_>>(),
+            vec![Some(2024), None, Some(1969), Some(1970)]
+        );
+        let months = col.truncate(DatePart::Month).unwrap();
+        assert_eq!(months.format(0), Some(Some("2024-02-01".to_string())));
+        assert_eq!(months.format(2), Some(Some("1969-07-01".to_string())));
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut col = TimestampArray::new(DataType::Timestamp(TimeUnit::Millisecond, None));
+        col.add_text("2024-03-10T14:05:09.123").unwrap();
+        col.add_text("2024-03-10 14:05:09.123456+02:00").unwrap();
+        col.add_text("1969-12-31T23:59:59.5Z").unwrap();
+        col.add_text("2024-03-10").unwrap();
+        assert_eq!(col.get(0), Some(Some(1_710_079_509_123)));
+        // the offset moves the instant, the sub millisecond digits are dropped
+        assert_eq!(col.get(1), Some(Some(1_710_079_509_123 - 2 * 3_600_000)));
+        assert_eq!(col.get(2), Some(Some(-500)));
+        assert_eq!(
+            col.format(2),
+            Some(Some("1969-12-31T23:59:59.500".to_string()))
+        );
+        assert!(col.add_text("2024-03-10T25:00").is_err());
+        assert!(col.add_text("2024-03-10T10:00+5").is_err());
+
+        let hours = col.extract(DatePart::Hour).unwrap();
+        assert_eq!(
+            hours.get_records().collect::<Vec<_>>(),
+            vec![Some(14), Some(12), Some(23), Some(0)]
+        );
+        let days = col.truncate(DatePart::Day).unwrap();
+        assert_eq!(
+            days.format(0),
+            Some(Some("2024-03-10T00:00:00.000".to_string()))
+        );
+        assert_eq!(days.get(2), Some(Some(-86_400_000)));
+
+        let nanos = TimestampArray::new(DataType::Timestamp(TimeUnit::Nanosecond, None));
+        assert!(nanos.parse("2262-04-11T23:47:16.854775807").is_ok());
+        assert!(nanos.parse("2262-04-12").is_err());
+    }
+
+    #[test]
+    fn timestamps_with_timezone() {
+        let data_type = DataType::Timestamp(TimeUnit::Second, Some("+05:30".to_string()));
+        let mut col = TimestampArray::new(data_type);
+        // local time of the column unless the text has its own offset
+        col.add_text("2024-01-01T01:00:00").unwrap();
+        col.add_text("2023-12-31T20:00:00Z").unwrap();
+        assert_eq!(col.get(0), Some(Some(1_704_051_000)));
+        assert_eq!(
+            col.format(0),
+            Some(Some("2024-01-01T01:00:00+05:30".to_string()))
+        );
+        assert_eq!(
+            col.format(1),
+            Some(Some("2024-01-01T01:30:00+05:30".to_string()))
+        );
+
+        // both are on new year's day in India but not in UTC
+        let days = col.extract(DatePart::Day).unwrap();
+        assert_eq!(
+            days.get_records().collect::<Vec<_>>(),
+            vec![Some(1), Some(1)]
+        );
+        let years = col.truncate(DatePart::Year).unwrap();
+        assert_eq!(
+            years.format(1),
+            Some(Some("2024-01-01T00:00:00+05:30".to_string()))
+        );
+
+        let utc = TimestampArray::new(DataType::Timestamp(TimeUnit::Second, Some("UTC".into())));
+        assert_eq!(utc.format_value(0), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn values_near_the_limits() {
+        let dates = Date32Array::new(DataType::Date32);
+        assert_eq!(
+            dates.parse("99999999999999999-01-01"),
+            Err("date out of range")
+        );
+        assert_eq!(dates.parse("9999999-01-01"), Err("value out of range"));
+
+        let mut nanos = TimestampArray::new(DataType::Timestamp(TimeUnit::Nanosecond, None));
+        nanos.add_record(i64::MIN);
+        // the start of 1677 is before the first nanosecond timestamp
+        assert_eq!(
+            nanos.truncate(DatePart::Year).err(),
+            Some("value out of range")
+        );
+        let mut nanos = nanos.slice(0, 0);
+        nanos.add_record(i64::MAX);
+        assert_eq!(
+            nanos.truncate(DatePart::Second).unwrap().get(0),
+            Some(Some(9_223_372_036_000_000_000))
+        );
+
+        let data_type = DataType::Timestamp(TimeUnit::Second, Some("+01:00".to_string()));
+        let mut seconds = TimestampArray::new(data_type);
+        seconds.add_record(i64::MAX);
+        assert_eq!(
+            seconds.format_value(i64::MAX),
+            "+292277026596-12-04T16:30:07+01:00"
+        );
+        assert_eq!(
+            seconds.extract(DatePart::Year).err(),
+            Some("value out of range")
+        );
+        assert_eq!(
+            seconds
+                .extract(DatePart::Hour)
+                .unwrap()
+                .get_records()
+                .collect::<Vec<_>>(),
+            vec![Some(16)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported timezone")]
+    fn named_timezones_are_rejected() {
+        TimestampArray::new(DataType::Timestamp(
+            TimeUnit::Second,
+            Some("Europe/Berlin".to_string()),
+        ));
+    }
+
+    #[test]
+    fn times_and_durations() {
+        let mut times = Time64Array::new(DataType::Time64(TimeUnit::Microsecond));
+        times.add_text("23:59:59.000001").unwrap();
+        times.add_text("08:30").unwrap();
+        assert_eq!(times.get(1), Some(Some(30_600_000_000)));
+        assert_eq!(times.format(0), Some(Some("23:59:59.000001".to_string())));
+        assert!(times.extract(DatePart::Year).is_err());
+        let minutes = times.extract(DatePart::Minute).unwrap();
+        assert_eq!(
+            minutes.get_records().collect::<Vec<_>>(),
+            vec![Some(59), Some(30)]
+        );
+        let hours = times.truncate(DatePart::Hour).unwrap();
+        assert_eq!(hours.format(0), Some(Some("23:00:00.000000".to_string())));
+
+        let mut durations = DurationArray::new(DataType::Duration(TimeUnit::Millisecond));
+        for text in ["PT1H30M", "P1DT2.5S", "-PT0.25S", "P2W", "PT0S"] {
+            durations.add_text(text).unwrap();
+        }
+        assert_eq!(
+            durations.get_records().collect::<Vec<_>>(),
+            vec![
+                Some(5_400_000),
+                Some(86_402_500),
+                Some(-250),
+                Some(1_209_600_000),
+                Some(0)
+            ]
+        );
+        let formatted: Vec<String> = (0..durations.len())
+            .map(|i| durations.format(i).flatten().unwrap())
+            .collect();
+        assert_eq!(
+            formatted,
+            vec!["PT1H30M", "P1DT2.5S", "-PT0.25S", "P14D", "PT0S"]
+        );
+        for text in ["P1Y", "P1M", "PT", "P", "PT1.5H", "PT1S2M", "1H"] {
+            assert!(durations.parse(text).is_err(), "{text}");
+        }
+        assert!(durations.extract(DatePart::Hour).is_err());
+        assert!(durations.truncate(DatePart::Month).is_err());
+        let hours = durations.truncate(DatePart::Hour).unwrap();
+        assert_eq!(hours.format(1), Some(Some("P1D".to_string())));
+    }
+}
@@ -0,0 +1,78 @@
+// small helpers shared by different modules.
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 64-bit hash of a byte slice.
+///
+/// FNV-1a mixes bytes cheaply but its high bits are weak, so the result goes through the murmur3
+/// finalizer. Sketches and filters rely on every bit of the hash being usable.
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    mix64(hash)
+}
+
+// murmur3 fmix64
+pub fn mix64(mut x: u64) -> u64 {
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xff51afd7ed558ccd);
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xc4ceb9fe1a85ec53);
+    x ^= x >> 33;
+    x
+}
+
+// crc32c (castagnoli), the checksum used by iscsi, ext4 and most storage formats. It catches
+// every burst error up to 32 bits which is exactly what flipped bits on disk look like.
+const CRC32C_POLY: u32 = 0x82f63b78; // reversed 0x1edc6f41
+
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32C_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+pub fn crc32c(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc = CRC32C_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crc32c, hash_bytes};
+
+    #[test]
+    fn crc32c_check_values() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xe3069283);
+        assert_eq!(crc32c(&[0; 32]), 0x8a9136aa);
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        assert_eq!(hash_bytes(b"apache"), hash_bytes(b"apache"));
+        assert_ne!(hash_bytes(b"apache"), hash_bytes(b"arrow"));
+        assert_ne!(hash_bytes(b""), hash_bytes(&[0]));
+    }
+}
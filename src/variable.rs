@@ -5,9 +5,17 @@
 // each offset indicates where the nth value ends. for example, [0, 4, 10] meangs we have two
 // values; there first one is 0..4 bytes in the data buffer and the second is 4..10. subsequently
 // length of first element is 4-0=4 and the second 10-4=6
+//
+// offsets are i32 by default, half the size of usize and the same on every platform, so files
+// don't depend on where they were written. They address up to 2 GiB of data per column chunk, the
+// "large" variants use i64 offsets for anything bigger.
 
 pub trait FromByteRef {
+    /// Only called with bytes `check_bytes` accepted.
     fn from_bytes(bytes: &[u8]) -> &Self;
+
+    /// Whether `bytes` are a valid value, e.g. utf-8 for text.
+    fn check_bytes(bytes: &[u8]) -> Result<(), &'static str>;
 }
 
 pub trait ToBytesRef {
@@ -23,72 +31,401 @@ impl<T: AsRef<[u8]> + ?Sized> ToBytesRef for T {
 use std::marker::PhantomData;
 
 use crate::bitvec::BitVec;
+use crate::fixed::Native;
+use crate::stats::{ColumnStats, LazyStats, StatsTracker, ToScalar};
+use crate::util::hash_bytes;
+
+/// Integer type of the offsets of a [`Variable`] column.
+pub trait Offset: Native + Ord {
+    /// Largest data buffer the offsets can address.
+    const MAX: usize;
+
+    fn to_usize(self) -> usize;
+
+    /// Panics when `value` is larger than `MAX`.
+    fn from_usize(value: usize) -> Self;
+}
+
+macro_rules! offset {
+    ($($type:ty),*) => {
+        $(
+            impl Offset for $type {
+                const MAX: usize = <$type>::MAX as usize;
+
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+
+                fn from_usize(value: usize) -> $type {
+                    <$type>::try_from(value).expect("offset out of range")
+                }
+            }
+        )*
+    };
+}
+
+offset!(i32, i64);
 
 pub type StringArray = Variable<str>;
+pub type BinaryArray = Variable<[u8]>;
+/// Strings with i64 offsets, for columns holding more than 2 GiB.
+pub type LargeStringArray = Variable<str, i64>;
+/// Bytes with i64 offsets, for columns holding more than 2 GiB.
+pub type LargeBinaryArray = Variable<[u8], i64>;
 
 #[derive(Debug)]
-pub struct Variable<T: ?Sized> {
+pub struct Variable<T: ?Sized, O = i32> {
     data: Vec<u8>,
-    offset: Vec<usize>,
-    pub validity: BitVec,
+    offset: Vec<O>,
+    validity: Option<BitVec>,
+    stats: LazyStats<Vec<u8>>,
     _phantom: PhantomData<T>,
 }
 
 // dervie(Default) asks for T: Default in other impls
-impl<T: ?Sized> Default for Variable<T> {
+impl<T: ?Sized, O: Offset> Default for Variable<T, O> {
     fn default() -> Self {
         Variable {
             data: Default::default(),
-            offset: vec![0], // 0 is always there
-            validity: Default::default(),
+            offset: vec![O::default()], // 0 is always there
+            validity: None,
+            stats: Default::default(),
             _phantom: Default::default(),
         }
     }
 }
 
-impl<T: ToBytesRef + ?Sized> Variable<T> {
-    pub fn new() -> Variable<T> {
+impl<T: ?Sized, O: Offset> Variable<T, O> {
+    pub fn len(&self) -> usize {
+        self.offset.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn offsets(&self) -> &[O] {
+        &self.offset
+    }
+
+    /// The validity bitmap, `None` when every value is valid.
+    pub fn validity(&self) -> Option<&BitVec> {
+        self.validity.as_ref()
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.validity
+            .as_ref()
+            .is_none_or(|validity| validity[index])
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.validity.as_ref().map_or(0, BitVec::null_count)
+    }
+
+    fn compute_stats(&self) -> StatsTracker<Vec<u8>> {
+        let mut stats = StatsTracker::default();
+        for (index, window) in self.offset.windows(2).enumerate() {
+            if self.is_valid(index) {
+                let bytes = &self.data[window[0].to_usize()..window[1].to_usize()];
+                stats.observe(bytes, hash_bytes(bytes));
+            }
+        }
+        stats
+    }
+
+    /// Copies the column into one with `P` offsets, fails when the data is too large for them.
+    pub fn convert_offsets<P: Offset>(&self) -> Result<Variable<T, P>, &'static str> {
+        if self.data.len() > P::MAX {
+            return Err("data is too large for the offset type");
+        }
+        Ok(Variable {
+            data: self.data.clone(),
+            offset: self
+                .offset
+                .iter()
+                .map(|offset| P::from_usize(offset.to_usize()))
+                .collect(),
+            validity: self.validity.clone(),
+            stats: self.stats.clone(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<T: ?Sized> Variable<T, i32> {
+    /// The same column with i64 offsets.
+    pub fn to_large(&self) -> Variable<T, i64> {
+        self.convert_offsets()
+            .expect("i64 offsets address more than i32 ones")
+    }
+}
+
+impl<T: ToBytesRef + ?Sized, O: Offset> Variable<T, O> {
+    pub fn new() -> Variable<T, O> {
         Variable::default()
     }
 
+    // only offsets and validity can be sized from the number of values, data depends on the
+    // length of each value.
+    pub fn with_capacity(capacity: usize) -> Variable<T, O> {
+        let mut offset = Vec::with_capacity(capacity + 1);
+        offset.push(O::default());
+        Variable {
+            data: Vec::new(),
+            offset,
+            validity: None,
+            stats: LazyStats::default(),
+            _phantom: PhantomData,
+        }
+    }
+
     pub fn add(&mut self, item: &T) {
-        let bytes: &[u8] = item.to_bytes();
-        let length = bytes.len() + self.offset.last().unwrap();
+        self.add_bytes(item.to_bytes());
+    }
 
+    // panics when the data outgrows the offsets, bigger columns need the large variant.
+    fn add_bytes(&mut self, bytes: &[u8]) {
+        let length = bytes.len() + self.offset.last().unwrap().to_usize();
+        assert!(
+            length <= O::MAX,
+            "column data outgrew its offsets, use a large array"
+        );
+
+        self.stats.observe(bytes, hash_bytes(bytes));
         self.data.extend(bytes);
-        self.offset.push(length);
-        self.validity.push(true);
+        self.offset.push(O::from_usize(length));
+        if let Some(validity) = &mut self.validity {
+            validity.push(true);
+        }
+    }
+
+    // a null takes no bytes in data, its start and end offsets are equal.
+    pub fn add_null(&mut self) {
+        let len = self.len();
+        self.validity
+            .get_or_insert_with(|| BitVec::ones(len))
+            .push(false);
+        self.offset.push(*self.offset.last().unwrap());
+    }
+
+    // copies `length` values starting at `offset` into a new column.
+    pub fn slice(&self, offset: usize, length: usize) -> Variable<T, O> {
+        let mut sliced = Variable::with_capacity(length);
+        sliced.append_range(self, offset, length);
+        sliced
+    }
+
+    // adds every value of other to the end.
+    pub fn append(&mut self, other: &Variable<T, O>) {
+        self.append_range(other, 0, other.len());
+    }
+
+    fn append_range(&mut self, other: &Variable<T, O>, offset: usize, length: usize) {
+        assert!(offset + length <= other.len(), "range out of bounds");
+        for index in offset..offset + length {
+            if other.is_valid(index) {
+                let (start, end) = (other.offset[index], other.offset[index + 1]);
+                let (start, end) = (start.to_usize(), end.to_usize());
+                self.add_bytes(&other.data[start..end]);
+            } else {
+                self.add_null();
+            }
+        }
     }
 }
 
-impl<T: FromByteRef + ?Sized> Variable<T> {
+impl<T: FromByteRef + ToBytesRef + ?Sized, O: Offset> Variable<T, O> {
+    // wraps existing buffers, validity as in `Fixed::from_parts`. Panics on buffers
+    // `try_from_parts` rejects.
+    pub fn from_parts(
+        data: Vec<u8>,
+        offset: Vec<O>,
+        validity: impl Into<Option<BitVec>>,
+    ) -> Variable<T, O> {
+        Variable::try_from_parts(data, offset, validity).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like `from_parts`, but fails when the offsets are out of order or out of bounds or a value
+    /// isn't valid for `T`, e.g. text that isn't utf-8.
+    pub fn try_from_parts(
+        data: Vec<u8>,
+        offset: Vec<O>,
+        validity: impl Into<Option<BitVec>>,
+    ) -> Result<Variable<T, O>, &'static str> {
+        let validity = validity.into().filter(|validity| validity.null_count() > 0);
+        if offset.first() != Some(&O::default()) {
+            return Err("offsets must start at 0");
+        }
+        if let Some(validity) = &validity
+            && offset.len() - 1 != validity.len()
+        {
+            return Err("offset and validity length missmatch");
+        }
+        if offset.windows(2).any(|w| w[0] > w[1]) {
+            return Err("offsets are out of order");
+        }
+        if offset.last().map(|o| o.to_usize()) != Some(data.len()) {
+            return Err("last offset must be the data length");
+        }
+        for window in offset.windows(2) {
+            T::check_bytes(&data[window[0].to_usize()..window[1].to_usize()])?;
+        }
+        Ok(Variable {
+            data,
+            offset,
+            validity,
+            stats: LazyStats::unknown(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+// `None` adds a null, like for `Fixed`.
+impl<'a, T: ToBytesRef + ?Sized + 'a, O: Offset> FromIterator<Option<&'a T>> for Variable<T, O> {
+    fn from_iter<I: IntoIterator<Item = Option<&'a T>>>(items: I) -> Variable<T, O> {
+        let items = items.into_iter();
+        let mut column = Variable::with_capacity(items.size_hint().0);
+        for item in items {
+            match item {
+                Some(item) => column.add(item),
+                None => column.add_null(),
+            }
+        }
+        column
+    }
+}
+
+impl<T: FromByteRef + ?Sized, O: Offset> Variable<T, O> {
     pub fn get(&self, index: usize) -> Option<Option<&T>> {
-        if self.offset.len() - 1 < index {
+        if index >= self.len() {
             return None;
         }
-        // check validity
-        if let Some(not_null) = self.validity.get(index) {
-            if !not_null {
-                return Some(None);
-            }
-        } else {
-            panic!("validity bitvec and data length missmatch!")
+        if !self.is_valid(index) {
+            return Some(None);
         }
 
         let (start, end) = (self.offset[index], self.offset[index + 1]);
+        let (start, end) = (start.to_usize(), end.to_usize());
         let item = T::from_bytes(&self.data[start..end]);
         Some(Some(item))
     }
 }
 
+impl<T: FromByteRef + ToScalar + ?Sized, O: Offset> Variable<T, O> {
+    pub fn stats(&self) -> ColumnStats {
+        let byte_size = self.data.len()
+            + self.offset.len() * O::WIDTH
+            + self.validity.as_ref().map_or(0, |v| v.as_bytes().len());
+        self.stats.get(|| self.compute_stats()).to_stats(
+            |bytes| T::from_bytes(bytes).to_scalar(),
+            self.null_count(),
+            byte_size,
+        )
+    }
+}
+
+// every constructor checks the bytes of text, so they don't have to be checked again on reads.
 impl FromByteRef for str {
     fn from_bytes(bytes: &[u8]) -> &Self {
         unsafe { str::from_utf8_unchecked(bytes) }
     }
+
+    fn check_bytes(bytes: &[u8]) -> Result<(), &'static str> {
+        str::from_utf8(bytes)
+            .map(|_| ())
+            .map_err(|_| "text is not utf-8")
+    }
 }
 
 impl FromByteRef for [u8] {
     fn from_bytes(bytes: &[u8]) -> &Self {
         bytes
     }
+
+    fn check_bytes(_: &[u8]) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BinaryArray, LargeStringArray, StringArray};
+    use crate::stats::Scalar;
+
+    #[test]
+    fn add_and_get() {
+        let mut col = StringArray::new();
+        col.add("apache");
+        col.add_null();
+        col.add("");
+        col.add("arrow");
+
+        assert_eq!(col.len(), 4);
+        assert_eq!(col.get(0), Some(Some("apache")));
+        assert_eq!(col.get(1), Some(None));
+        assert_eq!(col.get(2), Some(Some("")));
+        assert_eq!(col.get(3), Some(Some("arrow")));
+        assert_eq!(col.get(4), None);
+        assert_eq!(col.offsets(), &[0, 6, 6, 6, 11]);
+        assert_eq!(col.validity().unwrap().null_count(), 1);
+
+        let mut col = StringArray::new();
+        col.add("no");
+        col.add("nulls");
+        assert_eq!(col.validity(), None);
+        assert_eq!(col.slice(1, 1).get(0), Some(Some("nulls")));
+    }
+
+    #[test]
+    fn stats() {
+        let mut col = StringArray::new();
+        col.add("pending");
+        col.add("closed");
+        col.add_null();
+        col.add("open");
+        col.add("closed");
+
+        let stats = col.stats();
+        assert_eq!(stats.min(), Some(&Scalar::Text("closed".to_string())));
+        assert_eq!(stats.max(), Some(&Scalar::Text("pending".to_string())));
+        assert_eq!(stats.null_count(), 1);
+        assert_eq!(stats.distinct_count(), 3);
+    }
+
+    #[test]
+    fn large_offsets() {
+        let mut col = LargeStringArray::new();
+        col.add("wide");
+        col.add_null();
+        col.add("offsets");
+        assert_eq!(col.offsets(), &[0i64, 4, 4, 11]);
+        assert_eq!(col.stats().byte_size(), 11 + 4 * 8 + 1);
+
+        let small: StringArray = col.convert_offsets().unwrap();
+        assert_eq!(small.offsets(), &[0, 4, 4, 11]);
+        assert_eq!(small.get(1), Some(None));
+        assert_eq!(small.get(2), Some(Some("offsets")));
+        assert_eq!(small.to_large().offsets(), col.offsets());
+        assert_eq!(small.stats().byte_size(), 11 + 4 * 4 + 1);
+        assert_eq!(small.stats().max(), col.stats().max());
+    }
+
+    #[test]
+    fn checked_parts() {
+        let text = StringArray::try_from_parts(vec![0xff], vec![0, 1], None);
+        assert_eq!(text.unwrap_err(), "text is not utf-8");
+        let bytes = BinaryArray::try_from_parts(vec![0xff], vec![0, 1], None).unwrap();
+        assert_eq!(bytes.get(0), Some(Some(&[0xff][..])));
+
+        let backwards = StringArray::try_from_parts(b"ab".to_vec(), vec![0, 2, 1, 2], None);
+        assert_eq!(backwards.unwrap_err(), "offsets are out of order");
+        // a char split between two values
+        let split = StringArray::try_from_parts("ü".as_bytes().to_vec(), vec![0, 1, 2], None);
+        assert!(split.is_err());
+    }
 }